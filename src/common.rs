@@ -368,6 +368,22 @@ rect_deserialize!(i32);
 rect_deserialize!(isize);
 rect_deserialize!(usize);
 
+/// One pixel in the engine's 9-bit fixed point coordinates.
+pub const FIX9_ONE: i32 = 0x200;
+
+/// Converts pixels to fixed point, rounding to the nearest subpixel (halfway cases away from zero).
+#[inline(always)]
+pub fn to_fixed(val: f32) -> i32 {
+    (val * FIX9_ONE as f32).round() as i32
+}
+
+/// Converts fixed point to pixels without any snapping, see `fix9_scale` for the one used for drawing.
+#[inline(always)]
+pub fn from_fixed(val: i32) -> f32 {
+    val as f32 / FIX9_ONE as f32
+}
+
+/// Converts fixed point to pixels, snapped down to the current subpixel resolution.
 #[inline(always)]
 pub fn fix9_scale(val: i32) -> f32 {
     unsafe {
@@ -383,7 +399,7 @@ fn lerp_f64(v1: f64, v2: f64, t: f64) -> f64 {
 
 pub fn interpolate_fix9_scale(old_val: i32, val: i32, frame_delta: f64) -> f32 {
     if abs(old_val - val) > 0x1800 {
-        return val as f32 / 512.0;
+        return from_fixed(val);
     }
 
     unsafe {
@@ -393,6 +409,18 @@ pub fn interpolate_fix9_scale(old_val: i32, val: i32, frame_delta: f64) -> f32 {
     }
 }
 
+/// Like `interpolate_fix9_scale`, but rounded to the nearest step of the current subpixel resolution,
+/// also when the value jumped too far to be interpolated.
+pub fn interpolate_fix9_snapped(old_val: i32, val: i32, frame_delta: f64) -> f32 {
+    let interpolated =
+        if abs(old_val - val) > 0x1800 { val as f64 } else { lerp_f64(old_val as f64, val as f64, frame_delta) };
+
+    unsafe {
+        let mag = G_MAG as f64;
+        ((interpolated * mag / 512.0).round() / mag) as f32
+    }
+}
+
 pub fn get_timestamp() -> u64 {
     let now = SystemTime::now();
     now.duration_since(UNIX_EPOCH).unwrap().as_secs() as u64
@@ -400,7 +428,7 @@ pub fn get_timestamp() -> u64 {
 
 /// A RGBA color in the `sRGB` color space represented as `f32`'s in the range `[0.0-1.0]`
 ///
-/// For convenience, [`WHITE`](constant.WHITE.html) and [`BLACK`](constant.BLACK.html) are provided.
+/// For convenience, [`Color::WHITE`], [`Color::BLACK`] and [`Color::TRANSPARENT`] are provided.
 #[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Color {
     /// Red component
@@ -414,6 +442,10 @@ pub struct Color {
 }
 
 impl Color {
+    pub const WHITE: Color = Color::new(1.0, 1.0, 1.0, 1.0);
+    pub const BLACK: Color = Color::new(0.0, 0.0, 0.0, 1.0);
+    pub const TRANSPARENT: Color = Color::new(0.0, 0.0, 0.0, 0.0);
+
     /// Create a new `Color` from four `f32`'s in the range `[0.0-1.0]`
     pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
         Color { r, g, b, a }
@@ -470,6 +502,67 @@ impl Color {
 
         u32::from_be_bytes([0, r, g, b])
     }
+
+    /// Linearly interpolate between this `Color` and `other`, `t` is clamped to `[0.0-1.0]`.
+    pub fn lerp(self, other: Color, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+
+        Color::new(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+            self.a + (other.a - self.a) * t,
+        )
+    }
+
+    /// Multiply the color components (but not alpha) by `factor`, saturating at `0.0` and `1.0`.
+    #[allow(clippy::should_implement_trait)]
+    pub fn mul(self, factor: f32) -> Color {
+        Color::new(
+            (self.r * factor).clamp(0.0, 1.0),
+            (self.g * factor).clamp(0.0, 1.0),
+            (self.b * factor).clamp(0.0, 1.0),
+            self.a,
+        )
+    }
+
+    /// Add `other` to this `Color` component-wise, saturating at `1.0`.
+    #[allow(clippy::should_implement_trait)]
+    pub fn add(self, other: Color) -> Color {
+        Color::new(
+            (self.r + other.r).clamp(0.0, 1.0),
+            (self.g + other.g).clamp(0.0, 1.0),
+            (self.b + other.b).clamp(0.0, 1.0),
+            (self.a + other.a).clamp(0.0, 1.0),
+        )
+    }
+
+    /// Convert the color components (but not alpha) from `sRGB` to linear light, which is what blending
+    /// should operate on to look physically correct.
+    pub fn to_linear(self) -> Color {
+        fn conv(c: f32) -> f32 {
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        }
+
+        Color::new(conv(self.r), conv(self.g), conv(self.b), self.a)
+    }
+
+    /// Convert the color components (but not alpha) from linear light back to `sRGB`.
+    pub fn from_linear(self) -> Color {
+        fn conv(c: f32) -> f32 {
+            if c <= 0.0031308 {
+                c * 12.92
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            }
+        }
+
+        Color::new(conv(self.r), conv(self.g), conv(self.b), self.a)
+    }
 }
 
 impl From<(u8, u8, u8, u8)> for Color {
@@ -566,3 +659,90 @@ impl<T> SliceExt for [T] {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_point_round_trip() {
+        assert_eq!(to_fixed(1.0), FIX9_ONE);
+        assert_eq!(to_fixed(-2.5), -0x500);
+        assert_eq!(from_fixed(0x300), 1.5);
+        assert_eq!(from_fixed(-0x100), -0.5);
+
+        for val in [-0x1234, -1, 0, 1, 0x1ff, 0x7fff] {
+            assert_eq!(to_fixed(from_fixed(val)), val);
+        }
+    }
+
+    #[test]
+    fn fixed_point_rounding() {
+        // half a subpixel rounds away from zero, less than that rounds to zero
+        assert_eq!(to_fixed(1.0 / 1024.0), 1);
+        assert_eq!(to_fixed(-1.0 / 1024.0), -1);
+        assert_eq!(to_fixed(1.0 / 2048.0), 0);
+        assert_eq!(to_fixed(-1.0 / 2048.0), 0);
+
+        // fix9_scale floors, so negative values snap towards negative infinity
+        assert_eq!(fix9_scale(0x1ff), 0.0);
+        assert_eq!(fix9_scale(-1), -1.0);
+        assert_eq!(fix9_scale(-0x200), -1.0);
+    }
+
+    #[test]
+    fn snapped_interpolation() {
+        assert_eq!(interpolate_fix9_snapped(0, 0x200, 0.75), 1.0);
+        assert_eq!(interpolate_fix9_snapped(0, 0x200, 0.25), 0.0);
+        // too far to interpolate, but still snapped
+        assert_eq!(interpolate_fix9_snapped(0, 0x2100, 0.5), 17.0);
+    }
+
+    #[test]
+    fn color_add_saturates() {
+        let c = Color::from_rgba(200, 100, 0, 255).add(Color::from_rgba(100, 100, 0, 100));
+        assert_eq!(c.to_rgba(), (255, 200, 0, 255));
+    }
+
+    #[test]
+    fn color_linear_round_trip() {
+        let c = Color::from_rgba(255, 128, 0, 77).to_linear();
+        assert!((c.g - 0.2158).abs() < 0.001);
+        assert_eq!((c.r, c.b, c.a), (1.0, 0.0, Color::from_rgba(255, 128, 0, 77).a));
+
+        for v in [0u8, 1, 10, 64, 128, 200, 255] {
+            let c = Color::from_rgb(v, v, v);
+            assert!((c.to_linear().from_linear().r - c.r).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn color_mul_saturates() {
+        assert_eq!(Color::from_rgba(200, 100, 10, 128).mul(2.0).to_rgba(), (255, 200, 20, 128));
+        assert_eq!(Color::from_rgba(200, 100, 10, 128).mul(-1.0).to_rgba(), (0, 0, 0, 128));
+    }
+
+    #[test]
+    fn color_u32_round_trip() {
+        for c in [0x00000000, 0xffffffff, 0x12345678, 0xcd09aa80] {
+            assert_eq!(Color::from_rgba_u32(c).to_rgba_u32(), c);
+        }
+
+        assert_eq!(Color::from_rgba_u32(0xff000080).to_rgba(), (255, 0, 0, 128));
+        assert_eq!(Color::from_rgb_u32(0xcd09aa).to_rgb_u32(), 0xcd09aa);
+        assert_eq!(Color::from_rgb_u32(0xffffff), Color::WHITE);
+        assert_eq!(Color::from_rgba_u32(0), Color::TRANSPARENT);
+    }
+
+    #[test]
+    fn color_lerp() {
+        let black = Color::BLACK;
+        let white = Color::WHITE;
+
+        assert_eq!(black.lerp(white, 0.0), black);
+        assert_eq!(black.lerp(white, 1.0), white);
+        assert_eq!(black.lerp(white, 2.0), white);
+        assert_eq!(black.lerp(white, -1.0), black);
+        assert_eq!(black.lerp(white, 0.5).to_rgba(), (127, 127, 127, 255));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::common::Rect;
+use crate::components::draw_common::{draw_bar, draw_bar_fill};
 use crate::entity::GameEntity;
 use crate::framework::context::Context;
 use crate::framework::error::GameResult;
@@ -53,11 +54,11 @@ impl BossLifeBar {
         let text_rect = Rect::new_size(0, 48, 32, 8);
         let box_rect1 = Rect::new_size(0, 0, 244, 8);
         let box_rect2 = Rect::new_size(0, 16, 244, 8);
-        let mut rect_prev_bar = Rect::new_size(0, 32, 232, 8);
-        let mut rect_life_bar = Rect::new_size(0, 24, 232, 8);
+        let rect_prev_bar = Rect::new_size(0, 32, 232, 8);
+        let rect_life_bar = Rect::new_size(0, 24, 232, 8);
 
-        rect_prev_bar.right = ((self.prev_life as u32 * bar_length) / self.max_life as u32).min(bar_length) as u16;
-        rect_life_bar.right = ((self.life as u32 * bar_length) / self.max_life as u32).min(bar_length) as u16;
+        let prev_width = ((self.prev_life as u32 * bar_length) / self.max_life as u32).min(bar_length) as u16;
+        let life_width = ((self.life as u32 * bar_length) / self.max_life as u32).min(bar_length) as u16;
 
         batch.add_rect(
             ((state.canvas_size.0 - box_length as f32) / 2.0).floor(),
@@ -74,16 +75,9 @@ impl BossLifeBar {
             state.canvas_size.1 - 20.0,
             &box_rect1,
         );
-        batch.add_rect(
-            ((state.canvas_size.0 - box_length as f32) / 2.0 + 40.0).floor(),
-            state.canvas_size.1 - 16.0,
-            &rect_prev_bar,
-        );
-        batch.add_rect(
-            ((state.canvas_size.0 - box_length as f32) / 2.0 + 40.0).floor(),
-            state.canvas_size.1 - 16.0,
-            &rect_life_bar,
-        );
+        let bar_x = ((state.canvas_size.0 - box_length as f32) / 2.0 + 40.0).floor();
+        draw_bar_fill(&mut **batch, bar_x, state.canvas_size.1 - 16.0, prev_width, &rect_prev_bar);
+        draw_bar_fill(&mut **batch, bar_x, state.canvas_size.1 - 16.0, life_width, &rect_life_bar);
         batch.add_rect(
             ((state.canvas_size.0 - box_length as f32) / 2.0 + 8.0).floor(),
             state.canvas_size.1 - 16.0,
@@ -102,26 +96,28 @@ impl BossLifeBar {
         let bar_length = box_length - 52;
 
         let text_rect = Rect::new_size(0, 48, 32, 8);
-        let box_rect1 = Rect::new_size(0, 0, 136, 8);
-        let box_rect2 = Rect::new_size(0, 16, 136, 8);
-        let box_rect3 = Rect::new_size(238, 0, 6, 8);
-        let box_rect4 = Rect::new_size(238, 16, 6, 8);
-        let mut rect_prev_bar = Rect::new_size(0, 32, 124, 8);
-        let mut rect_life_bar = Rect::new_size(0, 24, 124, 8);
+        let rect_prev_bar = Rect::new_size(0, 32, 124, 8);
+        let rect_life_bar = Rect::new_size(0, 24, 124, 8);
 
-        rect_prev_bar.right = ((self.prev_life as u32 * bar_length) / self.max_life as u32).min(bar_length) as u16;
-        rect_life_bar.right = ((self.life as u32 * bar_length) / self.max_life as u32).min(bar_length) as u16;
+        let prev_width = ((self.prev_life as u32 * bar_length) / self.max_life as u32).min(bar_length) as u16;
+        let life_width = ((self.life as u32 * bar_length) / self.max_life as u32).min(bar_length) as u16;
 
         let base_x = state.canvas_size.0 - box_length as f32;
-
-        batch.add_rect((base_x - 6.0).floor(), state.canvas_size.1 - 20.0, &box_rect1);
-        batch.add_rect((base_x - 6.0).floor(), state.canvas_size.1 - 12.0, &box_rect2);
-        batch.add_rect((base_x - 6.0).floor(), state.canvas_size.1 - 20.0, &box_rect1);
-        batch.add_rect((state.canvas_size.0 - 18.0).floor(), state.canvas_size.1 - 20.0, &box_rect3);
-        batch.add_rect((state.canvas_size.0 - 18.0).floor(), state.canvas_size.1 - 12.0, &box_rect4);
-        batch.add_rect((state.canvas_size.0 - 18.0).floor(), state.canvas_size.1 - 20.0, &box_rect3);
-        batch.add_rect((base_x + 34.0).floor(), state.canvas_size.1 - 16.0, &rect_prev_bar);
-        batch.add_rect((base_x + 34.0).floor(), state.canvas_size.1 - 16.0, &rect_life_bar);
+        let frame_x = (base_x - 6.0).floor();
+        // the text box frame is shortened to fit: left part of the box, then its right edge
+        for top in [0, 16] {
+            draw_bar(
+                &mut **batch,
+                frame_x,
+                state.canvas_size.1 - 20.0 + top as f32 / 2.0,
+                142,
+                &Rect::new_size(0, top, 8, 8),
+                &Rect::new_size(8, top, 128, 8),
+                &Rect::new_size(238, top, 6, 8),
+            );
+        }
+        draw_bar_fill(&mut **batch, (base_x + 34.0).floor(), state.canvas_size.1 - 16.0, prev_width, &rect_prev_bar);
+        draw_bar_fill(&mut **batch, (base_x + 34.0).floor(), state.canvas_size.1 - 16.0, life_width, &rect_life_bar);
         batch.add_rect((base_x + 2.0).floor(), state.canvas_size.1 - 16.0, &text_rect);
 
         batch.draw(ctx)?;
@@ -0,0 +1,113 @@
+use crate::common::{interpolate_fix9_scale, Color, Rect};
+use crate::entity::GameEntity;
+use crate::framework::backend::SpriteBatchCommand;
+use crate::framework::context::Context;
+use crate::framework::error::GameResult;
+use crate::framework::graphics;
+use crate::framework::graphics::BlendMode;
+use crate::game::frame::Frame;
+use crate::game::shared_game_state::SharedGameState;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DarknessParams {
+    /// Radius of the lit area around each light, in canvas pixels.
+    pub radius: f32,
+    /// Color everything outside of the lit areas is multiplied by.
+    pub color: Color,
+}
+
+/// A light source revealing the darkness around it, position is in fixed point world coordinates.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DarknessLight {
+    pub x: i32,
+    pub y: i32,
+    pub prev_x: i32,
+    pub prev_y: i32,
+}
+
+/// Darkens the whole screen except for a radius around each light, for dark rooms.
+pub struct Darkness {
+    params: Option<DarknessParams>,
+    lights: Vec<DarknessLight>,
+}
+
+impl Darkness {
+    pub fn new() -> Darkness {
+        Darkness { params: None, lights: Vec::new() }
+    }
+
+    /// Enables the overlay with given parameters, `None` turns it off.
+    pub fn set_darkness(&mut self, params: Option<DarknessParams>) {
+        self.params = params;
+        if params.is_none() {
+            self.lights.clear();
+        }
+    }
+
+    pub fn darkness(&self) -> Option<DarknessParams> {
+        self.params
+    }
+}
+
+/// The lights are passed every tick, usually the players and anything they carry that gives off light.
+/// They're copied into a buffer that's kept between ticks.
+impl GameEntity<&[DarknessLight]> for Darkness {
+    fn tick(&mut self, _state: &mut SharedGameState, lights: &[DarknessLight]) -> GameResult {
+        self.lights.clear();
+        self.lights.extend_from_slice(lights);
+
+        Ok(())
+    }
+
+    fn draw(&self, state: &mut SharedGameState, ctx: &mut Context, frame: &Frame) -> GameResult {
+        let params = match self.params {
+            Some(params) => params,
+            None => return Ok(()),
+        };
+
+        // without vertex drawing there's no way to cut the holes, better to not darken anything at all
+        if !graphics::supports_vertex_draw(ctx)? || state.lightmap_canvas.is_none() {
+            return Ok(());
+        }
+
+        graphics::push_state(ctx)?;
+
+        let result = graphics::set_render_target(ctx, state.lightmap_canvas.as_ref()).and_then(|_| {
+            graphics::clear(ctx, params.color);
+
+            // adding the inverse of the darkness color brings the middle of each light back to white
+            let hole_color = Color::new(1.0 - params.color.r, 1.0 - params.color.g, 1.0 - params.color.b, 1.0);
+            for light in &self.lights {
+                let x = interpolate_fix9_scale(light.prev_x - frame.prev_x, light.x - frame.x, state.frame_time);
+                let y = interpolate_fix9_scale(light.prev_y - frame.prev_y, light.y - frame.y, state.frame_time);
+
+                graphics::draw_flash(
+                    ctx,
+                    (x * state.scale, y * state.scale),
+                    params.radius * state.scale,
+                    hole_color,
+                    1.0,
+                )?;
+            }
+
+            Ok(())
+        });
+
+        graphics::pop_state(ctx)?;
+        result?;
+
+        graphics::push_state(ctx)?;
+
+        let result = graphics::set_blend_mode(ctx, BlendMode::Multiply).and_then(|_| {
+            let canvas = state.lightmap_canvas.as_mut().unwrap();
+            let rect = Rect { left: 0.0, top: 0.0, right: state.screen_size.0, bottom: state.screen_size.1 };
+
+            canvas.clear();
+            canvas.add(SpriteBatchCommand::DrawRect(rect, rect));
+            canvas.draw()
+        });
+
+        graphics::pop_state(ctx)?;
+        result
+    }
+}
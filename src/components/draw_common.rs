@@ -1,7 +1,10 @@
 use crate::common::Rect;
 use crate::framework::context::Context;
-use crate::framework::error::GameResult;
+use crate::framework::error::{GameError, GameResult};
+use crate::framework::graphics;
 use crate::game::shared_game_state::SharedGameState;
+use crate::graphics::bmfont::is_full_width;
+use crate::graphics::texture_set::SpriteBatch;
 
 #[derive(Debug, EnumIter, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum Alignment {
@@ -9,6 +12,185 @@ pub enum Alignment {
     Right,
 }
 
+/// Vertical placement of a text box, the original engine can show it at either edge of the screen.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum TextBoxPosition {
+    Top,
+    Bottom,
+}
+
+/// Returns the top-left corner of a text box, horizontally centered within the safe area of the screen.
+pub fn textbox_origin(position: TextBoxPosition, state: &SharedGameState, ctx: &mut Context) -> (f32, f32) {
+    let (off_left, off_top, off_right, off_bottom) = graphics::screen_insets_scaled(ctx, state.scale);
+
+    let center = ((state.canvas_size.0 - off_left - off_right) / 2.0).floor();
+    let top = match position {
+        TextBoxPosition::Top => 32.0 + off_top,
+        TextBoxPosition::Bottom => state.canvas_size.1 - off_bottom - 66.0,
+    };
+
+    (off_left + center - 122.0, top)
+}
+
+/// Draws a text box outside of the script engine: the frame, an optional face portrait from the `Face` sheet and
+/// the text, wrapped at word boundaries to fit the box. Only the first 3 lines fit, the rest is cut off.
+pub fn draw_textbox(
+    lines: &[&str],
+    face: Option<u16>,
+    position: TextBoxPosition,
+    state: &mut SharedGameState,
+    ctx: &mut Context,
+) -> GameResult {
+    let (left, top) = textbox_origin(position, state, ctx);
+
+    let batch = state.texture_set.get_or_load_batch(ctx, &state.constants, "TextBox")?;
+    batch.add_rect(left, top, &state.constants.textscript.textbox_rect_top);
+    for i in 1..7 {
+        batch.add_rect(left, top + i as f32 * 8.0, &state.constants.textscript.textbox_rect_middle);
+    }
+    batch.add_rect(left, top + 56.0, &state.constants.textscript.textbox_rect_bottom);
+    batch.draw(ctx)?;
+
+    let text_offset = if let Some(face) = face {
+        let face = face % 100;
+        let batch = state.texture_set.get_or_load_batch(ctx, &state.constants, "Face")?;
+        batch.add_rect(left + 14.0, top + 8.0, &Rect::new_size((face % 6) * 48, (face / 6) * 48, 48, 48));
+        batch.draw(ctx)?;
+
+        56.0
+    } else {
+        0.0
+    };
+
+    let max_width = 216.0 - text_offset;
+    let mut wrapped = Vec::new();
+    for line in lines {
+        let mut current = String::new();
+
+        for word in line.split(' ') {
+            let candidate = if current.is_empty() { word.to_owned() } else { format!("{} {}", current, word) };
+
+            if !current.is_empty() && state.font.builder().compute_width(&candidate) > max_width {
+                wrapped.push(std::mem::replace(&mut current, word.to_owned()));
+            } else {
+                current = candidate;
+            }
+        }
+
+        wrapped.push(current);
+    }
+
+    for (idx, line) in wrapped.iter().take(3).enumerate() {
+        state
+            .font
+            .builder()
+            .position(left + text_offset + 14.0, top + 10.0 + idx as f32 * 16.0)
+            .shadow(state.constants.textscript.text_shadow)
+            .draw(line, ctx, &state.constants, &mut state.texture_set)?;
+    }
+
+    Ok(())
+}
+
+/// Reveals a message for `draw_textbox` a character at a time, the way the script engine prints text.
+/// `<WAInnnn` in the message pauses the reveal for `nnnn` ticks. Full-width characters take twice as long as
+/// half-width ones, matching their width.
+pub struct TextboxState {
+    text: String,
+    /// Byte offset in `text` and length in ticks of every pause, in order.
+    pauses: Vec<(usize, u16)>,
+    next_pause: usize,
+    /// Byte length of the revealed part of `text`.
+    visible: usize,
+    progress: f32,
+    chars_per_tick: f32,
+    wait: u16,
+}
+
+impl TextboxState {
+    pub fn new(message: &str, chars_per_tick: f32) -> TextboxState {
+        let mut text = String::with_capacity(message.len());
+        let mut pauses = Vec::new();
+        let mut rest = message;
+
+        while let Some(idx) = rest.find("<WAI") {
+            text.push_str(&rest[..idx]);
+
+            match rest.get(idx + 4..idx + 8).and_then(|ticks| ticks.parse::<u16>().ok()) {
+                Some(ticks) => {
+                    pauses.push((text.len(), ticks));
+                    rest = &rest[idx + 8..];
+                }
+                None => {
+                    text.push_str("<WAI");
+                    rest = &rest[idx + 4..];
+                }
+            }
+        }
+        text.push_str(rest);
+
+        TextboxState {
+            text,
+            pauses,
+            next_pause: 0,
+            visible: 0,
+            progress: 0.0,
+            chars_per_tick: chars_per_tick.max(0.0),
+            wait: 0,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        if self.wait > 0 {
+            self.wait -= 1;
+            return;
+        }
+
+        self.progress += self.chars_per_tick;
+        loop {
+            if let Some(&(pos, ticks)) = self.pauses.get(self.next_pause) {
+                if pos == self.visible {
+                    self.next_pause += 1;
+                    self.wait = ticks;
+                    self.progress = 0.0;
+                    return;
+                }
+            }
+
+            let chr = match self.text[self.visible..].chars().next() {
+                Some(chr) => chr,
+                None => {
+                    self.progress = 0.0;
+                    return;
+                }
+            };
+
+            let cost = if is_full_width(chr) { 2.0 } else { 1.0 };
+            if self.progress < cost {
+                return;
+            }
+
+            self.progress -= cost;
+            self.visible += chr.len_utf8();
+        }
+    }
+
+    /// Shows the whole message at once, skipping any remaining pauses. Usually done when the player presses a button.
+    pub fn reveal_all(&mut self) {
+        self.visible = self.text.len();
+        self.next_pause = self.pauses.len();
+        self.wait = 0;
+    }
+
+    pub fn visible_text(&self) -> &str {
+        &self.text[..self.visible]
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.visible == self.text.len() && self.wait == 0
+    }
+}
+
 pub fn draw_number(x: f32, y: f32, val: usize, align: Alignment, state: &mut SharedGameState, ctx: &mut Context) -> GameResult {
     let batch = state.texture_set.get_or_load_batch(ctx, &state.constants, "TextBox")?;
 
@@ -38,3 +220,142 @@ pub fn draw_number_zeros(x: f32, y: f32, val: usize, align: Alignment, zeros: us
     batch.draw(ctx)?;
     Ok(())
 }
+
+/// Draws a horizontal bar `width` pixels long: the left cap, the middle segment repeated as many times as needed
+/// (the last repetition is clipped) and the right cap.
+pub fn draw_bar(
+    batch: &mut dyn SpriteBatch,
+    x: f32,
+    y: f32,
+    width: u16,
+    src_left: &Rect<u16>,
+    src_mid: &Rect<u16>,
+    src_right: &Rect<u16>,
+) {
+    let caps_width = src_left.width() + src_right.width();
+
+    batch.add_rect(x, y, src_left);
+    draw_bar_fill(batch, x + src_left.width() as f32, y, width.saturating_sub(caps_width), src_mid);
+    batch.add_rect(x + width.max(caps_width) as f32 - src_right.width() as f32, y, src_right);
+}
+
+/// Draws the left `progress` part of the whole texture in `batch`, meant for the `Loading` sheet so it fills up
+/// as assets load. `progress` is clamped to `[0.0-1.0]`.
+pub fn draw_loading_bar(batch: &mut dyn SpriteBatch, x: f32, y: f32, progress: f32) {
+    let width = (batch.width() as f32 * progress.clamp(0.0, 1.0)).round() as u16;
+    if width == 0 {
+        return;
+    }
+
+    batch.add_rect(x, y, &Rect::new(0, 0, width, batch.height() as u16));
+}
+
+/// Draws a panel of any size from a 3x3 grid of source rects, given row by row from the top left. Corners keep
+/// their size, edges are stretched along their length and the center is stretched both ways to fill `dest`.
+/// The grid has to tile: rects in a row share their vertical span, rects in a column share their horizontal one,
+/// and neighbours touch.
+pub fn draw_nine_slice(batch: &mut dyn SpriteBatch, src_grid: &[Rect<u16>; 9], dest: Rect<f32>) -> GameResult {
+    for row in 0..3 {
+        for col in 0..3 {
+            let rect = &src_grid[row * 3 + col];
+            let row_start = &src_grid[row * 3];
+            let col_start = &src_grid[col];
+
+            let tiles = rect.top == row_start.top
+                && rect.bottom == row_start.bottom
+                && rect.left == col_start.left
+                && rect.right == col_start.right
+                && (col == 0 || src_grid[row * 3 + col - 1].right == rect.left)
+                && (row == 0 || src_grid[(row - 1) * 3 + col].bottom == rect.top);
+
+            if !tiles {
+                let idx = row * 3 + col;
+                return Err(GameError::InvalidValue(format!("Nine-slice rect {} doesn't line up with the grid.", idx)));
+            }
+        }
+    }
+
+    let (left_w, right_w) = (src_grid[0].width() as f32, src_grid[2].width() as f32);
+    let (top_h, bottom_h) = (src_grid[0].height() as f32, src_grid[6].height() as f32);
+    let mid_w = (dest.width() - left_w - right_w).max(0.0);
+    let mid_h = (dest.height() - top_h - bottom_h).max(0.0);
+
+    let xs = [dest.left, dest.left + left_w, dest.left + left_w + mid_w];
+    let ys = [dest.top, dest.top + top_h, dest.top + top_h + mid_h];
+    let widths = [left_w, mid_w, right_w];
+    let heights = [top_h, mid_h, bottom_h];
+
+    for (idx, rect) in src_grid.iter().enumerate() {
+        let (col, row) = (idx % 3, idx / 3);
+        if rect.width() == 0 || rect.height() == 0 || widths[col] == 0.0 || heights[row] == 0.0 {
+            continue;
+        }
+
+        let scale_x = widths[col] / rect.width() as f32;
+        let scale_y = heights[row] / rect.height() as f32;
+        batch.add_rect_scaled(xs[col], ys[row], scale_x, scale_y, rect);
+    }
+
+    Ok(())
+}
+
+/// Fills `filled` pixels of a bar starting at `x` by repeating `src_fill`, the last repetition is clipped.
+/// Used for the filled part of life and experience bars.
+pub fn draw_bar_fill(batch: &mut dyn SpriteBatch, x: f32, y: f32, filled: u16, src_fill: &Rect<u16>) {
+    let segment = src_fill.width();
+    if segment == 0 {
+        return;
+    }
+
+    let mut offset = 0;
+    while offset < filled {
+        let len = segment.min(filled - offset);
+        batch.add_rect(x + offset as f32, y, &Rect::new_size(src_fill.left, src_fill.top, len, src_fill.height()));
+        offset += len;
+    }
+}
+
+/// Draws the current weapon's experience bar from the `TextBox` sheet at `x`, `y`: the empty box with `xp` out of
+/// `max_xp` filled, or the full "MAX" bar once the weapon is at its last level.
+pub fn draw_weapon_xp_bar(batch: &mut dyn SpriteBatch, x: f32, y: f32, xp: u16, max_xp: u16, max_level: bool) {
+    batch.add_rect(x, y, &Rect::new_size(0, 72, 40, 8));
+
+    if max_level {
+        batch.add_rect(x, y, &Rect::new_size(40, 72, 40, 8));
+    } else if max_xp > 0 {
+        let filled = (xp.min(max_xp) as f32 / max_xp as f32 * 40.0) as u16;
+        draw_bar_fill(batch, x, y, filled, &Rect::new_size(0, 80, 40, 8));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn textbox_typewriter() {
+        let mut textbox = TextboxState::new("Hi<WAI0002あ!", 1.0);
+
+        textbox.tick();
+        textbox.tick();
+        assert_eq!(textbox.visible_text(), "Hi");
+
+        // paused for two ticks, then the full-width character takes two more
+        for _ in 0..3 {
+            textbox.tick();
+        }
+        assert_eq!(textbox.visible_text(), "Hi");
+        textbox.tick();
+        assert_eq!(textbox.visible_text(), "Hiあ");
+        assert!(!textbox.is_done());
+
+        textbox.tick();
+        assert_eq!(textbox.visible_text(), "Hiあ!");
+        assert!(textbox.is_done());
+
+        let mut textbox = TextboxState::new("<WAI00x1 skipped", 0.5);
+        textbox.reveal_all();
+        assert_eq!(textbox.visible_text(), "<WAI00x1 skipped");
+        assert!(textbox.is_done());
+    }
+}
@@ -6,6 +6,9 @@ use crate::framework::graphics;
 use crate::game::frame::Frame;
 use crate::game::shared_game_state::SharedGameState;
 
+/// Opacity of flashes with `reduce_flashing` enabled.
+const REDUCED_FLASH_ALPHA: f32 = 0.4;
+
 pub enum FlashState {
     None,
     Cross(i32, i32, u16),
@@ -59,7 +62,8 @@ impl GameEntity<()> for Flash {
     }
 
     fn draw(&self, state: &mut SharedGameState, ctx: &mut Context, frame: &Frame) -> GameResult<()> {
-        const WHITE: Color = Color::new(1.0, 1.0, 1.0, 1.0);
+        let reduced = state.settings.reduce_flashing;
+        let color = if reduced { Color::new(1.0, 1.0, 1.0, REDUCED_FLASH_ALPHA) } else { Color::WHITE };
 
         match self.state {
             FlashState::None => {}
@@ -86,7 +90,7 @@ impl GameEntity<()> for Flash {
                     bottom: ((cen_y + width) * state.scale) as isize,
                 };
 
-                graphics::draw_rect(ctx, rect, WHITE)?;
+                graphics::draw_rect(ctx, rect, color)?;
 
                 if tick <= 100.0 {
                     rect = Rect {
@@ -96,12 +100,24 @@ impl GameEntity<()> for Flash {
                         bottom: (state.canvas_size.1 * state.scale) as isize,
                     };
 
-                    graphics::draw_rect(ctx, rect, WHITE)?;
+                    graphics::draw_rect(ctx, rect, color)?;
+                }
+            }
+            // the blink strobes at 12.5Hz, with reduced flashing it lights up just once instead
+            FlashState::Blink(tick) if reduced => {
+                if tick / 10 % 2 != 0 {
+                    let rect = Rect {
+                        left: 0,
+                        top: 0,
+                        right: (state.canvas_size.0 * state.scale) as isize,
+                        bottom: (state.canvas_size.1 * state.scale) as isize,
+                    };
+                    graphics::draw_rect(ctx, rect, color)?;
                 }
             }
             FlashState::Blink(tick) => {
                 if tick / 2 % 2 != 0 {
-                    graphics::clear(ctx, WHITE);
+                    graphics::clear(ctx, Color::WHITE);
                 }
             }
         }
@@ -1,8 +1,12 @@
-use crate::common::Rect;
-use crate::components::draw_common::{Alignment, draw_number};
+use std::cell::RefCell;
+
+use crate::common::{Color, Rect};
+use crate::components::draw_common::{Alignment, draw_bar_fill, draw_number, draw_weapon_xp_bar};
 use crate::entity::GameEntity;
+use crate::framework::backend::{BackendTexture, SpriteBatchCommand};
 use crate::framework::context::Context;
 use crate::framework::error::GameResult;
+use crate::framework::graphics;
 use crate::framework::graphics::screen_insets_scaled;
 use crate::game::frame::Frame;
 use crate::game::inventory::Inventory;
@@ -32,6 +36,44 @@ pub struct HUD {
     current_weapon: isize,
     weapon_types: [u8; 16],
     shock: bool,
+    /// Render the HUD into a texture and only redraw it when what it shows changes. Off by default.
+    pub cache_enabled: bool,
+    cache: RefCell<HudCache>,
+}
+
+/// Everything the HUD image depends on, a change of any of these invalidates the cached texture.
+#[derive(PartialEq, Clone)]
+struct HudCacheKey {
+    weapon_x_pos: usize,
+    has_player2: bool,
+    ammo: u16,
+    max_ammo: u16,
+    xp: u16,
+    max_xp: u16,
+    xp_bar_blink: bool,
+    max_level: bool,
+    life: u16,
+    max_life: u16,
+    life_bar: u16,
+    air: u16,
+    air_counter: u16,
+    current_level: usize,
+    weapon_count: usize,
+    current_weapon: isize,
+    weapon_types: [u8; 16],
+    shock: bool,
+    screen_size: (f32, f32),
+    insets: (f32, f32, f32, f32),
+    scale: f32,
+    texture_generation: u32,
+}
+
+#[derive(Default)]
+struct HudCache {
+    texture: Option<Box<dyn BackendTexture>>,
+    key: Option<HudCacheKey>,
+    /// Set if creating the render target failed, the HUD is drawn directly from then on.
+    unsupported: bool,
 }
 
 impl HUD {
@@ -58,7 +100,90 @@ impl HUD {
             current_weapon: 0,
             weapon_types: [0; 16],
             shock: false,
+            cache_enabled: false,
+            cache: RefCell::new(HudCache::default()),
+        }
+    }
+
+    /// Forces the cached HUD texture to be redrawn on the next frame.
+    pub fn invalidate_cache(&self) {
+        self.cache.borrow_mut().key = None;
+    }
+
+    fn cache_key(&self, state: &SharedGameState, ctx: &mut Context) -> HudCacheKey {
+        HudCacheKey {
+            weapon_x_pos: self.weapon_x_pos,
+            has_player2: self.has_player2,
+            ammo: self.ammo,
+            max_ammo: self.max_ammo,
+            xp: self.xp,
+            max_xp: self.max_xp,
+            xp_bar_blink: (self.xp_bar_counter & 0x02) != 0,
+            max_level: self.max_level,
+            life: self.life,
+            max_life: self.max_life,
+            life_bar: self.life_bar,
+            air: self.air,
+            air_counter: self.air_counter,
+            current_level: self.current_level,
+            weapon_count: self.weapon_count,
+            current_weapon: self.current_weapon,
+            weapon_types: self.weapon_types,
+            shock: self.shock,
+            screen_size: state.screen_size,
+            insets: screen_insets_scaled(ctx, state.scale),
+            scale: state.scale,
+            texture_generation: state.texture_set.generation(),
+        }
+    }
+
+    /// Redraws the cached texture if needed and draws it, returns false if render targets aren't available.
+    fn draw_cached(&self, state: &mut SharedGameState, ctx: &mut Context) -> GameResult<bool> {
+        let key = self.cache_key(state, ctx);
+        let mut cache = self.cache.borrow_mut();
+
+        if cache.unsupported {
+            return Ok(false);
+        }
+
+        let (width, height) = (state.screen_size.0 as u16, state.screen_size.1 as u16);
+        if cache.texture.as_ref().map_or(true, |texture| texture.dimensions() != (width, height)) {
+            cache.key = None;
+            cache.texture = match graphics::create_texture_mutable(ctx, width, height) {
+                Ok(texture) => Some(texture),
+                Err(_) => {
+                    cache.unsupported = true;
+                    return Ok(false);
+                }
+            };
+        }
+
+        if cache.key.as_ref() != Some(&key) {
+            graphics::push_state(ctx)?;
+
+            let result = graphics::set_render_target(ctx, cache.texture.as_ref()).and_then(|_| {
+                graphics::clear(ctx, Color::new(0.0, 0.0, 0.0, 0.0));
+                self.draw_hud(state, ctx)
+            });
+
+            graphics::pop_state(ctx)?;
+            result?;
+
+            cache.key = Some(key);
+        }
+
+        if let Some(texture) = cache.texture.as_mut() {
+            let (width, height) = (width as f32, height as f32);
+
+            texture.clear();
+            texture.add(SpriteBatchCommand::DrawRect(
+                Rect::new(0.0, 0.0, width, height),
+                Rect::new(0.0, 0.0, width, height),
+            ));
+            texture.draw()?;
         }
+
+        Ok(true)
     }
 }
 
@@ -180,6 +305,16 @@ impl GameEntity<(&Player, &mut Inventory)> for HUD {
             return Ok(());
         }
 
+        if self.cache_enabled && self.draw_cached(state, ctx)? {
+            return Ok(());
+        }
+
+        self.draw_hud(state, ctx)
+    }
+}
+
+impl HUD {
+    fn draw_hud(&self, state: &mut SharedGameState, ctx: &mut Context) -> GameResult {
         let (left, top, right, bottom) = screen_insets_scaled(ctx, state.scale);
 
         // none
@@ -213,17 +348,9 @@ impl GameEntity<(&Player, &mut Inventory)> for HUD {
             batch.add_rect(bar_offset + weap_x + 32.0, 24.0 + top, &Rect::new_size(72, 48, 8, 8));
             // lv
             batch.add_rect(num_offset + weap_x, 32.0 + top, &Rect::new_size(80, 80, 16, 8));
-            // xp box
-            batch.add_rect(bar_offset + weap_x + 24.0, 32.0 + top, &Rect::new_size(0, 72, 40, 8));
-
-            if self.max_level {
-                batch.add_rect(bar_offset + weap_x + 24.0, 32.0 + top, &Rect::new_size(40, 72, 40, 8));
-            } else if self.max_xp > 0 {
-                // xp bar
-                let bar_width = (self.xp as f32 / self.max_xp as f32 * 40.0) as u16;
-
-                batch.add_rect(bar_offset + weap_x + 24.0, 32.0 + top, &Rect::new_size(0, 80, bar_width, 8));
-            }
+            // xp bar
+            let (x, y) = (bar_offset + weap_x + 24.0, 32.0 + top);
+            draw_weapon_xp_bar(&mut **batch, x, y, self.xp, self.max_xp, self.max_level);
 
             if (self.xp_bar_counter & 0x02) != 0 {
                 batch.add_rect(bar_offset + weap_x + 24.0, 32.0 + top, &Rect::new_size(40, 80, 40, 8));
@@ -237,10 +364,11 @@ impl GameEntity<(&Player, &mut Inventory)> for HUD {
                 batch.add_rect(num_offset + 16.0, 40.0 + top, &Rect::new_size(0, 40, 24, 8));
                 // life box
                 batch.add_rect(bar_offset + 40.0, 40.0 + top, &Rect::new_size(24, 40, 40, 8));
+                let (x, y) = (bar_offset + 40.0, 40.0 + top);
                 // yellow bar
-                batch.add_rect(bar_offset + 40.0, 40.0 + top, &Rect::new_size(0, 32, yellow_bar_width, 8));
+                draw_bar_fill(&mut **batch, x, y, yellow_bar_width, &Rect::new_size(0, 32, 39, 8));
                 // life
-                batch.add_rect(bar_offset + 40.0, 40.0 + top, &Rect::new_size(0, 24, bar_width, 8));
+                draw_bar_fill(&mut **batch, x, y, bar_width, &Rect::new_size(0, 24, 39, 8));
             }
         }
 
@@ -53,26 +53,16 @@ impl MapSystem {
 
         let batch = state.texture_set.get_or_load_batch(ctx, &state.constants, "TextBox")?;
 
+        let consts = &state.constants.map_system;
+        let last_class = consts.tile_rects.len() - 1;
+
         for y in 0..stage.map.height {
             for x in 0..stage.map.width {
-                const RECTS: [Rect<u16>; 4] = [
-                    Rect { left: 240, top: 24, right: 241, bottom: 25 },
-                    Rect { left: 241, top: 24, right: 242, bottom: 25 },
-                    Rect { left: 242, top: 24, right: 243, bottom: 25 },
-                    Rect { left: 243, top: 24, right: 244, bottom: 25 },
-                ];
-
                 let attr = stage.map.get_attribute(x as _, y as _);
+                let class =
+                    consts.tile_classes.get(&attr).map_or(last_class, |&class| (class as usize).min(last_class));
 
-                let layer = match attr {
-                    0 => 0,
-                    0x01 | 0x02 | 0x40 | 0x44 | 0x51 | 0x52 | 0x55 | 0x56 | 0x60 | 0x71 | 0x72 | 0x75 | 0x76 | 0x80
-                    | 0x81 | 0x82 | 0x83 | 0xA0 | 0xA1 | 0xA2 | 0xA3 => 1,
-                    0x43 | 0x50 | 0x53 | 0x54 | 0x57 | 0x63 | 0x70 | 0x73 | 0x74 | 0x77 => 2,
-                    _ => 3,
-                };
-
-                batch.add_rect(x as _, y as _, &RECTS[layer]);
+                batch.add_rect(x as _, y as _, &consts.tile_rects[class]);
             }
         }
 
@@ -260,8 +250,7 @@ impl MapSystem {
         }
 
         if (self.tick & 8) != 0 {
-            const PLAYER_RECT: Rect<u16> = Rect { left: 0, top: 57, right: 1, bottom: 58 };
-
+            let player_rect = state.constants.map_system.player_rect;
             let batch = state.texture_set.get_or_load_batch(ctx, &state.constants, "TextBox")?;
             let x_offset = (state.canvas_size.0 - stage.map.width as f32) / 2.0;
             let y_offset = (state.canvas_size.1 - stage.map.height as f32) / 2.0;
@@ -275,7 +264,7 @@ impl MapSystem {
                 let plr_x = x_offset + (player.x / tile_div) as f32;
                 let plr_y = y_offset + (player.y / tile_div) as f32;
 
-                batch.add_rect(plr_x, plr_y, &PLAYER_RECT);
+                batch.add_rect(plr_x, plr_y, &player_rect);
             }
 
             batch.draw(ctx)?;
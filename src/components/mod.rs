@@ -2,6 +2,7 @@ pub mod background;
 pub mod boss_life_bar;
 pub mod compact_jukebox;
 pub mod credits;
+pub mod darkness;
 pub mod draw_common;
 pub mod fade;
 pub mod falling_island;
@@ -10,6 +11,7 @@ pub mod hud;
 pub mod inventory;
 pub mod map_system;
 pub mod nikumaru;
+pub mod npc_spawner;
 pub mod number_popup;
 pub mod replay;
 pub mod stage_select;
@@ -0,0 +1,86 @@
+use crate::engine_constants::NPCSpawnRule;
+use crate::framework::error::GameResult;
+use crate::game::npc::list::NPCList;
+use crate::game::npc::NPC;
+use crate::game::shared_game_state::SharedGameState;
+use crate::game::stage::Stage;
+use crate::util::rng::{XorShift, RNG};
+
+/// How many random tiles are tried before a spawn is given up, for areas that are mostly solid.
+const PLACEMENT_ATTEMPTS: usize = 8;
+
+/// Spawns the ambient NPCs configured for the current stage in `EngineConstants::npc_spawns`.
+pub struct NPCSpawner {
+    /// Ticks left until each of the stage's rules spawns again.
+    timers: Vec<u16>,
+}
+
+impl NPCSpawner {
+    pub fn new() -> NPCSpawner {
+        NPCSpawner { timers: Vec::new() }
+    }
+
+    fn next_delay(rng: &XorShift, rule: &NPCSpawnRule) -> u16 {
+        let (min, max) = rule.interval;
+        rng.range(min as i32..max.max(min) as i32) as u16
+    }
+
+    pub fn tick(
+        &mut self,
+        state: &mut SharedGameState,
+        npc_list: &NPCList,
+        stage: &Stage,
+        stage_id: usize,
+    ) -> GameResult {
+        let rules = match state.constants.npc_spawns.get(&stage_id) {
+            Some(rules) if !rules.is_empty() => rules,
+            _ => return Ok(()),
+        };
+
+        if self.timers.len() != rules.len() {
+            self.timers = rules.iter().map(|rule| NPCSpawner::next_delay(&state.game_rng, rule)).collect();
+        }
+
+        let tile_size = state.tile_size.as_int();
+        for (rule, timer) in rules.iter().zip(self.timers.iter_mut()) {
+            if *timer > 0 {
+                *timer -= 1;
+                continue;
+            }
+
+            *timer = NPCSpawner::next_delay(&state.game_rng, rule);
+
+            let alive = npc_list.iter_alive().filter(|npc| npc.npc_type == rule.npc_type).count();
+            if alive >= rule.max_alive as usize {
+                continue;
+            }
+
+            let (left, top, right, bottom) =
+                rule.area.unwrap_or((0, 0, stage.map.width.saturating_sub(1), stage.map.height.saturating_sub(1)));
+
+            for _ in 0..PLACEMENT_ATTEMPTS {
+                let x = state.game_rng.range(left as i32..right.max(left) as i32);
+                let y = state.game_rng.range(top as i32..bottom.max(top) as i32);
+
+                if x >= stage.map.width as i32 || y >= stage.map.height as i32 {
+                    continue;
+                }
+
+                if stage.map.get_attribute(x as usize, y as usize) != 0 {
+                    continue;
+                }
+
+                let mut npc = NPC::create(rule.npc_type, &state.npc_table);
+                npc.cond.set_alive(true);
+                npc.x = (x * tile_size + tile_size / 2) * 0x200;
+                npc.y = (y * tile_size + tile_size / 2) * 0x200;
+
+                // a full NPC list just means there's no room for ambience right now
+                let _ = npc_list.spawn(0x100, npc);
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -13,11 +13,15 @@ use crate::game::shared_game_state::{ReplayKind, ReplayState, SharedGameState};
 use crate::input::replay_player_controller::{KeyState, ReplayController};
 use crate::game::player::Player;
 use crate::graphics::font::Font;
+use crate::util::rng::XorShift;
+
+/// Version 1 widened the inputs of each tick from 16 to 32 bits to record the run key.
+const REPLAY_VERSION: u16 = 1;
 
 #[derive(Clone)]
 pub struct Replay {
     replay_version: u16,
-    keylist: Vec<u16>,
+    keylist: Vec<u32>,
     last_input: KeyState,
     rng_seed: u64,
     pub controller: ReplayController,
@@ -42,7 +46,7 @@ impl Replay {
 
     pub fn initialize_recording(&mut self, state: &mut SharedGameState) {
         if !self.is_active {
-            self.rng_seed = state.game_rng.dump_state();
+            self.record_rng(&state.game_rng);
             self.is_active = true;
         }
     }
@@ -73,22 +77,31 @@ impl Replay {
         if !self.is_active {
             state.replay_state = ReplayState::Playback(replay_kind);
             self.read_replay(state, ctx, replay_kind)?;
-            state.game_rng.load_state(self.rng_seed);
+            self.restore_rng(&mut state.game_rng);
             self.is_active = true;
         }
         Ok(())
     }
 
+    fn record_rng(&mut self, rng: &XorShift) {
+        self.rng_seed = rng.dump_state();
+    }
+
+    /// Puts the game RNG back to the state it was in when recording started.
+    fn restore_rng(&self, rng: &mut XorShift) {
+        rng.load_state(self.rng_seed);
+    }
+
     fn write_replay(&mut self, state: &mut SharedGameState, ctx: &mut Context, replay_kind: ReplayKind) -> GameResult {
         if let Ok(mut file) = filesystem::open_options(
             ctx,
             [state.get_rec_filename(), replay_kind.get_suffix()].join(""),
             OpenOptions::new().write(true).create(true),
         ) {
-            file.write_u16::<LE>(0)?; // Space for versioning replay files
+            file.write_u16::<LE>(REPLAY_VERSION)?;
             file.write_u64::<LE>(self.rng_seed)?;
             for input in &self.keylist {
-                file.write_u16::<LE>(*input)?;
+                file.write_u32::<LE>(*input)?;
             }
         }
         Ok(())
@@ -103,12 +116,13 @@ impl Replay {
             let mut data = Vec::new();
             file.read_to_end(&mut data)?;
 
-            let count = data.len() / 2;
+            let input_size = if self.replay_version == 0 { 2 } else { 4 };
+            let count = data.len() / input_size;
             let mut inputs = Vec::new();
             let mut f = Cursor::new(data);
 
             for _ in 0..count {
-                inputs.push(f.read_u16::<LE>()?);
+                inputs.push(if self.replay_version == 0 { f.read_u16::<LE>()? as u32 } else { f.read_u32::<LE>()? });
             }
 
             self.keylist = inputs;
@@ -122,19 +136,20 @@ impl GameEntity<(&mut Context, &mut Player)> for Replay {
         match state.replay_state {
             ReplayState::Recording => {
                 // This mimics the KeyState bitfield
-                let inputs = player.controller.move_left() as u16
-                    + ((player.controller.move_right() as u16) << 1)
-                    + ((player.controller.move_up() as u16) << 2)
-                    + ((player.controller.move_down() as u16) << 3)
-                    + ((player.controller.trigger_map() as u16) << 4)
-                    + ((player.controller.trigger_inventory() as u16) << 5)
-                    + (((player.controller.jump() || player.controller.trigger_menu_ok()) as u16) << 6)
-                    + (((player.controller.shoot() || player.controller.trigger_menu_back()) as u16) << 7)
-                    + ((player.controller.next_weapon() as u16) << 8)
-                    + ((player.controller.prev_weapon() as u16) << 9)
-                    + ((player.controller.trigger_menu_ok() as u16) << 11)
-                    + ((player.controller.skip() as u16) << 12)
-                    + ((player.controller.strafe() as u16) << 13);
+                let inputs = player.controller.move_left() as u32
+                    + ((player.controller.move_right() as u32) << 1)
+                    + ((player.controller.move_up() as u32) << 2)
+                    + ((player.controller.move_down() as u32) << 3)
+                    + ((player.controller.trigger_map() as u32) << 4)
+                    + ((player.controller.trigger_inventory() as u32) << 5)
+                    + (((player.controller.jump() || player.controller.trigger_menu_ok()) as u32) << 6)
+                    + (((player.controller.shoot() || player.controller.trigger_menu_back()) as u32) << 7)
+                    + ((player.controller.next_weapon() as u32) << 8)
+                    + ((player.controller.prev_weapon() as u32) << 9)
+                    + ((player.controller.trigger_menu_ok() as u32) << 11)
+                    + ((player.controller.skip() as u32) << 12)
+                    + ((player.controller.strafe() as u32) << 13)
+                    + ((player.controller.run() as u32) << 16);
 
                 self.keylist.push(inputs);
             }
@@ -186,3 +201,34 @@ impl GameEntity<(&mut Context, &mut Player)> for Replay {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Direction;
+    use crate::engine_constants::EngineConstants;
+    use crate::game::player::TargetPlayer;
+    use crate::game::weapon::bullet::BulletManager;
+
+    fn first_bullet_seed(game_rng: &XorShift) -> u32 {
+        let mut bullet_manager = BulletManager::new();
+        bullet_manager.reseed_from(game_rng);
+        bullet_manager.create_bullet(0, 0, 1, TargetPlayer::Player1, Direction::Left, &EngineConstants::defaults());
+
+        bullet_manager.bullets[0].rng.dump_state()
+    }
+
+    #[test]
+    fn playback_restores_bullet_seed() {
+        let recording_rng = XorShift::new(0x1234);
+        recording_rng.next_u32();
+        let mut replay = Replay::new();
+        replay.record_rng(&recording_rng);
+
+        let mut playback_rng = XorShift::new(0x5678);
+        assert_ne!(first_bullet_seed(&playback_rng), first_bullet_seed(&recording_rng));
+
+        replay.restore_rng(&mut playback_rng);
+        assert_eq!(first_bullet_seed(&playback_rng), first_bullet_seed(&recording_rng));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::common::{Color, Rect};
+use crate::components::draw_common::{textbox_origin, TextBoxPosition};
 use crate::engine_constants::AnimatedFace;
 use crate::entity::GameEntity;
 use crate::framework::context::Context;
@@ -63,16 +64,12 @@ impl GameEntity<()> for TextBoxes {
             return Ok(());
         }
 
-        let (off_left, off_top, off_right, off_bottom) =
-            crate::framework::graphics::screen_insets_scaled(ctx, state.scale);
+        let (off_left, _, off_right, off_bottom) = graphics::screen_insets_scaled(ctx, state.scale);
 
         let center = ((state.canvas_size.0 - off_left - off_right) / 2.0).floor();
-        let top_pos = if state.textscript_vm.flags.position_top() {
-            32.0 + off_top
-        } else {
-            state.canvas_size.1 as f32 - off_bottom - 66.0
-        };
-        let left_pos = off_left + center - 122.0;
+        let position =
+            if state.textscript_vm.flags.position_top() { TextBoxPosition::Top } else { TextBoxPosition::Bottom };
+        let (left_pos, top_pos) = textbox_origin(position, state, ctx);
 
         {
             let batch = state.texture_set.get_or_load_batch(ctx, &state.constants, "TextBox")?;
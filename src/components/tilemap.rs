@@ -1,17 +1,43 @@
-use crate::common::Rect;
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use crate::common::{Color, Rect};
+use crate::framework::backend::{BackendTexture, SpriteBatchCommand};
 use crate::framework::context::Context;
 use crate::framework::error::GameResult;
+use crate::framework::graphics;
 use crate::game::frame::Frame;
 use crate::game::shared_game_state::{SharedGameState, TileSize};
 use crate::game::stage::{BackgroundType, Stage, StageTexturePaths};
 
+/// Width and height (in tiles) of the blocks tile layers are pre-rendered into.
+pub const TILE_CHUNK_SIZE: usize = 16;
+
+/// Upper bound on cached chunks across all layers, once it's reached the rest is drawn tile by tile.
+const MAX_TILE_CHUNKS: usize = 64;
+
+/// Layer, tileset and attribute hash (see `Tilemap::draw`) and position of a chunk.
+type ChunkKey = (TileLayer, u64, usize, usize);
+
+struct TileChunk {
+    texture: Box<dyn BackendTexture>,
+    /// Tiles the chunk was rendered from, compared every frame to catch map changes.
+    tiles: Vec<u8>,
+    scale: f32,
+    generation: u32,
+    used: bool,
+}
+
 pub struct Tilemap {
     tick: u32,
     prev_tick: u32,
     pub no_water: bool,
+    chunks: RefCell<HashMap<ChunkKey, TileChunk>>,
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum TileLayer {
     Background,
     Middleground,
@@ -21,7 +47,7 @@ pub enum TileLayer {
 
 impl Tilemap {
     pub fn new() -> Self {
-        Tilemap { tick: 0, prev_tick: 0, no_water: false }
+        Tilemap { tick: 0, prev_tick: 0, no_water: false, chunks: RefCell::new(HashMap::new()) }
     }
 
     pub fn tick(&mut self) -> GameResult {
@@ -78,8 +104,6 @@ impl Tilemap {
         let halft = tile_size / 2;
         let halftf = tile_sizef / 2.0;
 
-        let batch = state.texture_set.get_or_load_batch(ctx, &state.constants, tex)?;
-        let mut rect = Rect::new(0, 0, tile_size as u16, tile_size as u16);
         let (mut frame_x, mut frame_y) = frame.xy_interpolated(state.frame_time);
 
         if let Some(pxpack_data) = &stage.data.pxpack_data {
@@ -100,66 +124,178 @@ impl Tilemap {
         let tile_end_y = ((frame_y as i32 + halft + state.canvas_size.1 as i32) / tile_size + 1)
             .clamp(0, layer_height as i32) as usize;
 
-        if layer == TileLayer::Snack {
-            rect = state.constants.world.snack_rect;
-        }
-
-        for y in tile_start_y..tile_end_y {
-            for x in tile_start_x..tile_end_x {
-                let tile = *stage.map.tiles.get((y * layer_width as usize) + x + layer_offset).unwrap();
-                match layer {
-                    _ if uses_layers => {
-                        if tile == 0 {
-                            continue;
-                        }
-
-                        let tile_size = tile_size as u16;
-                        rect.left = (tile as u16 % 16) * tile_size;
-                        rect.top = (tile as u16 / 16) * tile_size;
-                        rect.right = rect.left + tile_size;
-                        rect.bottom = rect.top + tile_size;
+        let snack_rect = state.constants.world.snack_rect;
+        let tile_at = |x: usize, y: usize| *stage.map.tiles.get((y * layer_width as usize) + x + layer_offset).unwrap();
+        let tile_animations = &state.constants.tile_animations;
+        let anim_frame = (self.tick / state.constants.tile_animation_frame_time.max(1) as u32) as usize;
+        let is_animated = |x: usize, y: usize| tile_animations.contains_key(&(tile_at(x, y) as u16));
+        let tile_rect = |x: usize, y: usize| -> Option<Rect<u16>> {
+            let tile = tile_at(x, y);
+            match layer {
+                _ if uses_layers => {
+                    if tile == 0 {
+                        return None;
                     }
-                    TileLayer::Background => {
-                        if stage.map.attrib[tile as usize] >= 0x20 {
-                            continue;
-                        }
+                }
+                TileLayer::Background => {
+                    if stage.map.attrib[tile as usize] >= 0x20 {
+                        return None;
+                    }
+                }
+                TileLayer::Foreground => {
+                    let attr = stage.map.attrib[tile as usize];
 
-                        let tile_size = tile_size as u16;
-                        rect.left = (tile as u16 % 16) * tile_size;
-                        rect.top = (tile as u16 / 16) * tile_size;
-                        rect.right = rect.left + tile_size;
-                        rect.bottom = rect.top + tile_size;
+                    if attr < 0x40 || attr >= 0x80 {
+                        return None;
+                    }
+                }
+                TileLayer::Snack => {
+                    if stage.map.attrib[tile as usize] != 0x43 {
+                        return None;
                     }
-                    TileLayer::Foreground => {
-                        let attr = stage.map.attrib[tile as usize];
 
-                        if attr < 0x40 || attr >= 0x80 {
-                            continue;
-                        }
+                    return Some(snack_rect);
+                }
+                _ => {}
+            }
 
-                        let tile_size = tile_size as u16;
-                        rect.left = (tile as u16 % 16) * tile_size;
-                        rect.top = (tile as u16 / 16) * tile_size;
-                        rect.right = rect.left + tile_size;
-                        rect.bottom = rect.top + tile_size;
+            let tile = match tile_animations.get(&(tile as u16)) {
+                Some(frames) if !frames.is_empty() => frames[anim_frame % frames.len()],
+                _ => tile as u16,
+            };
+            let tile_size = tile_size as u16;
+            let left = (tile % 16) * tile_size;
+            let top = (tile / 16) * tile_size;
+
+            Some(Rect::new(left, top, left + tile_size, top + tile_size))
+        };
+
+        // Static tiles are pre-rendered in chunks, animated tiles and water currents are always drawn below.
+        let chunk_sizef = TILE_CHUNK_SIZE as f32 * tile_sizef;
+        let chunk_px = (chunk_sizef * state.scale).ceil() as u16;
+        let chunk_tiles = |chunk_x: usize, chunk_y: usize| {
+            let (start_x, start_y) = (chunk_x * TILE_CHUNK_SIZE, chunk_y * TILE_CHUNK_SIZE);
+            let end_x = (start_x + TILE_CHUNK_SIZE).min(layer_width as usize);
+            let end_y = (start_y + TILE_CHUNK_SIZE).min(layer_height as usize);
+
+            (start_y..end_y).flat_map(move |y| (start_x..end_x).map(move |x| (x, y)))
+        };
+        let generation = state.texture_set.generation();
+        let scale = state.scale;
+        // swapping the tileset or the attributes (which decide what's drawn on which layer) invalidates chunks
+        let source = {
+            let mut hasher = DefaultHasher::new();
+            tex.hash(&mut hasher);
+            stage.map.attrib.hash(&mut hasher);
+            hasher.finish()
+        };
+        let mut chunks = self.chunks.borrow_mut();
+        let mut cached = true;
+
+        'chunks: for chunk_y in tile_start_y / TILE_CHUNK_SIZE..(tile_end_y + TILE_CHUNK_SIZE - 1) / TILE_CHUNK_SIZE {
+            for chunk_x in tile_start_x / TILE_CHUNK_SIZE..(tile_end_x + TILE_CHUNK_SIZE - 1) / TILE_CHUNK_SIZE {
+                let key = (layer, source, chunk_x, chunk_y);
+                let valid = |chunk: &TileChunk| chunk.scale == scale && chunk.generation == generation;
+                let dirty = match chunks.get(&key) {
+                    Some(chunk) => {
+                        !valid(chunk)
+                            || !chunk
+                                .tiles
+                                .iter()
+                                .copied()
+                                .eq(chunk_tiles(chunk_x, chunk_y).map(|(x, y)| tile_at(x, y)))
                     }
-                    TileLayer::Snack => {
-                        if stage.map.attrib[tile as usize] != 0x43 {
-                            continue;
+                    None => true,
+                };
+
+                if dirty {
+                    let texture = match chunks.remove(&key) {
+                        Some(chunk) if valid(&chunk) => chunk.texture,
+                        _ if chunks.len() >= MAX_TILE_CHUNKS => {
+                            cached = false;
+                            break 'chunks;
                         }
-                    }
-                    _ => {}
+                        _ => match graphics::create_texture_mutable(ctx, chunk_px, chunk_px) {
+                            Ok(texture) => texture,
+                            Err(_) => {
+                                cached = false;
+                                break 'chunks;
+                            }
+                        },
+                    };
+
+                    graphics::push_state(ctx)?;
+
+                    let result = graphics::set_render_target(ctx, Some(&texture)).and_then(|_| {
+                        graphics::clear(ctx, Color::new(0.0, 0.0, 0.0, 0.0));
+
+                        let batch = state.texture_set.get_or_load_batch(ctx, &state.constants, tex)?;
+                        for (x, y) in chunk_tiles(chunk_x, chunk_y) {
+                            if is_animated(x, y) {
+                                continue;
+                            }
+
+                            if let Some(rect) = tile_rect(x, y) {
+                                batch.add_rect(
+                                    (x - chunk_x * TILE_CHUNK_SIZE) as f32 * tile_sizef,
+                                    (y - chunk_y * TILE_CHUNK_SIZE) as f32 * tile_sizef,
+                                    &rect,
+                                );
+                            }
+                        }
+                        batch.draw(ctx)
+                    });
+
+                    graphics::pop_state(ctx)?;
+                    result?;
+
+                    let tiles = chunk_tiles(chunk_x, chunk_y).map(|(x, y)| tile_at(x, y)).collect();
+                    chunks.insert(key, TileChunk { texture, tiles, scale, generation, used: false });
                 }
 
-                batch.add_rect(
-                    (x as f32 * tile_sizef - halftf) - frame_x,
-                    (y as f32 * tile_sizef - halftf) - frame_y,
-                    &rect,
-                );
+                let chunk = chunks.get_mut(&key).unwrap();
+                let size = chunk_px as f32;
+                chunk.used = true;
+                chunk.texture.clear();
+                chunk.texture.add(SpriteBatchCommand::DrawRect(
+                    Rect::new(0.0, 0.0, size, size),
+                    Rect::new_size(
+                        (chunk_x as f32 * chunk_sizef - halftf - frame_x) * scale,
+                        (chunk_y as f32 * chunk_sizef - halftf - frame_y) * scale,
+                        size,
+                        size,
+                    ),
+                ));
+                chunk.texture.draw()?;
             }
         }
 
-        batch.draw(ctx)?;
+        // drop chunks of this layer that went off screen
+        chunks.retain(|key, chunk| key.0 != layer || std::mem::take(&mut chunk.used));
+        drop(chunks);
+
+        if !cached || !tile_animations.is_empty() {
+            // render targets aren't available, draw the tiles directly, otherwise only the animated ones
+            let batch = state.texture_set.get_or_load_batch(ctx, &state.constants, tex)?;
+
+            for y in tile_start_y..tile_end_y {
+                for x in tile_start_x..tile_end_x {
+                    if cached && !is_animated(x, y) {
+                        continue;
+                    }
+
+                    if let Some(rect) = tile_rect(x, y) {
+                        batch.add_rect(
+                            (x as f32 * tile_sizef - halftf) - frame_x,
+                            (y as f32 * tile_sizef - halftf) - frame_y,
+                            &rect,
+                        );
+                    }
+                }
+            }
+
+            batch.draw(ctx)?;
+        }
 
         if !self.no_water && layer == TileLayer::Foreground && stage.data.background_type == BackgroundType::Water {
             let batch = state.texture_set.get_or_load_batch(ctx, &state.constants, &textures.background)?;
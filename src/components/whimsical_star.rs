@@ -76,7 +76,7 @@ impl GameEntity<(&Player, &mut BulletManager)> for WhimsicalStar {
             return Ok(());
         }
 
-        self.star_count = player.stars;
+        self.star_count = player.stars.min(self.star.len() as u8);
 
         let mut prev_x = player.x;
         let mut prev_y = player.y;
@@ -12,6 +12,7 @@ use crate::framework::context::Context;
 use crate::framework::error::GameResult;
 use crate::framework::filesystem;
 use crate::framework::gamepad::{Axis, Button};
+use crate::game::caret::CaretType;
 use crate::game::player::ControlMode;
 use crate::game::scripting::tsc::text_script::TextScriptEncoding;
 use crate::game::settings::Settings;
@@ -25,6 +26,8 @@ mod npcs;
 #[derive(Debug, Copy, Clone)]
 pub struct PhysicsConsts {
     pub max_dash: i32,
+    /// Replaces `max_dash` while the player is running, the same as `max_dash` in vanilla since it has no running.
+    pub max_run: i32,
     pub max_move: i32,
     pub gravity_ground: i32,
     pub gravity_air: i32,
@@ -34,6 +37,26 @@ pub struct PhysicsConsts {
     pub jump: i32,
 }
 
+impl PhysicsConsts {
+    /// Mixes in `amount` out of `total` parts of `other`, `total` must not be 0.
+    pub fn blend(&self, other: &PhysicsConsts, amount: u8, total: u8) -> PhysicsConsts {
+        let (amount, total) = (amount.min(total) as i32, total as i32);
+        let mix = |a: i32, b: i32| a + (b - a) * amount / total;
+
+        PhysicsConsts {
+            max_dash: mix(self.max_dash, other.max_dash),
+            max_run: mix(self.max_run, other.max_run),
+            max_move: mix(self.max_move, other.max_move),
+            gravity_ground: mix(self.gravity_ground, other.gravity_ground),
+            gravity_air: mix(self.gravity_air, other.gravity_air),
+            dash_ground: mix(self.dash_ground, other.dash_ground),
+            dash_air: mix(self.dash_air, other.dash_air),
+            resist: mix(self.resist, other.resist),
+            jump: mix(self.jump, other.jump),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct BoosterConsts {
     pub fuel: u32,
@@ -42,18 +65,52 @@ pub struct BoosterConsts {
     pub b2_0_down: i32,
     pub b2_0_left: i32,
     pub b2_0_right: i32,
+    /// Caret spawned as the booster's exhaust.
+    pub exhaust_caret: CaretType,
+    /// Exhaust is spawned every `exhaust_interval` frames of fuel use, when `fuel % exhaust_interval` equals
+    /// the booster's phase. Booster 2.0 also spawns it whenever jump is pressed.
+    pub exhaust_interval: u32,
+    pub exhaust_phase_0_8: u32,
+    pub exhaust_phase_2_0: u32,
+}
+
+impl BoosterConsts {
+    pub fn spawns_exhaust(&self, fuel: u32, phase: u32) -> bool {
+        self.exhaust_interval != 0 && fuel % self.exhaust_interval == phase
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
 pub struct PlayerConsts {
     pub life: u16,
     pub max_life: u16,
+    /// Maximum number of Whimsical Star stars orbiting the player, only up to 3 of them are simulated.
+    pub max_stars: u8,
+    /// Upper limit of weapon ammo gained from `<AM+`, vanilla doesn't have one.
+    pub max_ammo: u16,
     pub control_mode: ControlMode,
     pub air_physics: PhysicsConsts,
     pub water_physics: PhysicsConsts,
+    /// Ticks the physics take to blend between `air_physics` and `water_physics` when the player crosses the water
+    /// surface, only used with `Settings::smooth_water_physics`. The original game switches right away.
+    pub water_transition_ticks: u8,
     pub frames_left: [Rect<u16>; 12],
     pub frames_right: [Rect<u16>; 12],
     pub frames_bubble: [Rect<u16>; 2],
+    /// Frames of invincibility after taking damage.
+    pub iframes: u8,
+    /// The player's sprite flickers in intervals of this many frames while invincible.
+    pub iframe_flicker_interval: u8,
+    pub iframe_flicker_mode: FlickerMode,
+}
+
+/// How the player's sprite flickers while invincible.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FlickerMode {
+    /// Don't draw the sprite at all, like the original game.
+    Hide,
+    /// Draw the sprite with given alpha.
+    Dim(u8),
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -65,6 +122,11 @@ pub struct GameConsts {
     pub new_game_event: u16,
     pub new_game_player_pos: (i16, i16),
     pub tile_offset_x: i32,
+    /// Bullets beyond this count aren't spawned, the original engine has a fixed pool of 64.
+    pub max_bullets: usize,
+    /// NPCs beyond this count aren't spawned, the original engine has a fixed pool of 512.
+    /// It can't be raised above the capacity of `NPCList`.
+    pub max_npcs: u16,
 }
 
 #[derive(Debug, Clone)]
@@ -97,6 +159,26 @@ pub struct TextureSizeTable {
     sizes: HashMap<String, (u16, u16)>,
 }
 
+/// An ambient NPC (bats, critters) spawned every now and then while the player is in a stage.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct NPCSpawnRule {
+    pub npc_type: u16,
+    /// Minimum and maximum number of ticks between spawns, the actual delay is picked randomly.
+    pub interval: (u16, u16),
+    /// Nothing is spawned while at least this many NPCs of the type are alive.
+    pub max_alive: u16,
+    /// Tiles the NPC can appear in as `(left, top, right, bottom)`, inclusive. The whole map if missing.
+    /// NPCs are only ever placed on tiles without any attributes, so they don't end up inside walls or water.
+    #[serde(default)]
+    pub area: Option<(u16, u16, u16, u16)>,
+}
+
+/// Contents of `npc_spawns.json`, rules keyed by stage id.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct NPCSpawnTable {
+    spawns: HashMap<usize, Vec<NPCSpawnRule>>,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct BulletData {
     pub damage: u8,
@@ -108,6 +190,9 @@ pub struct BulletData {
     pub block_hit_width: u16,
     pub block_hit_height: u16,
     pub display_bounds: Rect<u8>,
+    /// Rotate the sprite to match the direction the bullet is moving in, for bullets fired at angles
+    /// their frames weren't drawn for. Off for all vanilla bullets, which have directional frames.
+    pub rotate_with_velocity: bool,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -152,6 +237,10 @@ pub struct WeaponConsts {
     pub bullet_table: Vec<BulletData>,
     pub bullet_rects: BulletRects,
     pub level_table: [[u16; 3]; 14],
+    /// Maximum perpendicular velocity added to Machine Gun bullets.
+    pub machine_gun_spread: i32,
+    /// Maximum perpendicular velocity added to level 2 Bubbler bullets.
+    pub bubbler_spread: i32,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -160,6 +249,53 @@ pub struct WorldConsts {
     pub water_push_rect: Rect<u16>,
 }
 
+/// Tile collision tuning, in fixed point units (0x200 per pixel). Defaults match the original game.
+#[derive(Debug, Copy, Clone)]
+pub struct CollisionConsts {
+    /// How far past a tile's top or bottom edge the player's hitbox has to reach before the tile's sides
+    /// act as walls. Lower values let the player get pushed sideways by tiles it's only grazing.
+    pub player_wall_tolerance: i32,
+    /// Same as `player_wall_tolerance`, for NPCs and bullets.
+    pub npc_wall_tolerance: i32,
+    /// How far past a tile's left or right edge a hitbox has to reach before the tile acts as a ceiling or floor.
+    /// This is what makes the player bonk their head on ceiling corners.
+    pub ceiling_floor_tolerance: i32,
+    /// Horizontal speed the player is clamped to after being pushed out of a wall.
+    pub wall_push_out_speed: i32,
+    /// How far below a platform's top edge feet can be and still be snapped on top of it.
+    pub platform_snap_distance: i32,
+    /// Downward speed applied while walking down slopes, keeps the player from bouncing off them.
+    pub slope_snap_speed: i32,
+}
+
+/// Effects shown when NPCs and bosses get hurt or die. Defaults match the original game.
+#[derive(Debug, Copy, Clone)]
+pub struct NPCEffectConsts {
+    /// Frames an NPC shakes for after being hit.
+    pub shock_duration: u16,
+    /// Same as `shock_duration`, for boss parts.
+    pub boss_shock_duration: u16,
+    /// The hurt sound and particles are skipped while the remaining shock is at least this long,
+    /// so rapid fire doesn't spam them.
+    pub hurt_effect_threshold: u16,
+    pub hurt_caret: CaretType,
+    pub hurt_caret_count: usize,
+    /// Smoke puffs spawned when a killed NPC explodes, indexed by NPC size (1 to 3).
+    pub death_smoke_count: [usize; 3],
+    /// Smoke puffs spawned when NPCs are removed by a script or a boss dies, indexed by size.
+    pub vanish_smoke_count: [usize; 3],
+}
+
+impl NPCEffectConsts {
+    pub fn death_smoke_count(&self, size: u8) -> Option<usize> {
+        self.death_smoke_count.get((size as usize).checked_sub(1)?).copied()
+    }
+
+    pub fn vanish_smoke_count(&self, size: u8) -> Option<usize> {
+        self.vanish_smoke_count.get((size as usize).checked_sub(1)?).copied()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AnimatedFace {
     pub face_id: u16,
@@ -228,6 +364,35 @@ pub struct TitleConsts {
     pub cursor_sue: [Rect<u16>; 4],
 }
 
+#[derive(Debug, Clone)]
+pub struct MapSystemConsts {
+    /// 1x1 rects in TextBox used as map pixels, indexed by the tile class from `tile_classes`.
+    pub tile_rects: [Rect<u16>; 4],
+    /// Tile attribute -> map pixel class, attributes not listed use the last class.
+    pub tile_classes: HashMap<u8, u8>,
+    pub player_rect: Rect<u16>,
+}
+
+impl MapSystemConsts {
+    fn vanilla_tile_classes() -> HashMap<u8, u8> {
+        let mut classes = HashMap::new();
+        classes.insert(0x00, 0);
+
+        for attr in [
+            0x01, 0x02, 0x40, 0x44, 0x51, 0x52, 0x55, 0x56, 0x60, 0x71, 0x72, 0x75, 0x76, 0x80, 0x81, 0x82, 0x83, 0xA0,
+            0xA1, 0xA2, 0xA3,
+        ] {
+            classes.insert(attr, 1);
+        }
+
+        for attr in [0x43, 0x50, 0x53, 0x54, 0x57, 0x63, 0x70, 0x73, 0x74, 0x77] {
+            classes.insert(attr, 2);
+        }
+
+        classes
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct GamepadConsts {
     pub button_rects: HashMap<Button, [Rect<u16>; 4]>,
@@ -259,11 +424,14 @@ pub struct EngineConstants {
     pub booster: BoosterConsts,
     pub caret: CaretConsts,
     pub world: WorldConsts,
+    pub collision: CollisionConsts,
     pub npc: NPCConsts,
+    pub npc_effects: NPCEffectConsts,
     pub weapon: WeaponConsts,
     pub tex_sizes: CaseInsensitiveHashMap<(u16, u16)>,
     pub textscript: TextScriptConsts,
     pub title: TitleConsts,
+    pub map_system: MapSystemConsts,
     pub inventory_dim_color: Color,
     pub font_path: String,
     pub font_space_offset: f32,
@@ -272,12 +440,21 @@ pub struct EngineConstants {
     pub organya_paths: Vec<String>,
     pub credit_illustration_paths: Vec<String>,
     pub player_skin_paths: Vec<String>,
+    /// Alternate player sheets used while wearing equipment, keyed by the equip flags that have to be set.
+    /// The sheet replaces the player's skin texture, frames are picked the same way.
+    pub equip_appearances: HashMap<u16, String>,
     pub animated_face_table: Vec<AnimatedFace>,
     pub string_table: HashMap<String, String>,
     pub missile_flags: Vec<u16>,
+    /// Animated tiles: tile id -> sequence of tile ids shown in its place, advancing every
+    /// `tile_animation_frame_time` ticks. Animated tiles are never baked into cached tile chunks.
+    pub tile_animations: HashMap<u16, Vec<u16>>,
+    pub tile_animation_frame_time: u16,
     pub locales: Vec<Locale>,
     pub gamepad: GamepadConsts,
     pub stage_encoding: Option<TextScriptEncoding>,
+    /// Ambient NPC spawns keyed by stage id, empty unless a mod provides `npc_spawns.json`.
+    pub npc_spawns: HashMap<usize, Vec<NPCSpawnRule>>,
 }
 
 impl EngineConstants {
@@ -298,13 +475,18 @@ impl EngineConstants {
                 new_game_event: 200,
                 new_game_player_pos: (10, 8),
                 tile_offset_x: 0,
+                max_bullets: 64,
+                max_npcs: 512,
             },
             player: PlayerConsts {
                 life: 3,
                 max_life: 3,
+                max_stars: 3,
+                max_ammo: u16::MAX,
                 control_mode: ControlMode::Normal,
                 air_physics: PhysicsConsts {
                     max_dash: 0x32c,
+                    max_run: 0x32c,
                     max_move: 0x5ff,
                     gravity_air: 0x20,
                     gravity_ground: 0x50,
@@ -315,6 +497,7 @@ impl EngineConstants {
                 },
                 water_physics: PhysicsConsts {
                     max_dash: 0x196,
+                    max_run: 0x196,
                     max_move: 0x2ff,
                     gravity_air: 0x10,
                     gravity_ground: 0x28,
@@ -323,6 +506,7 @@ impl EngineConstants {
                     resist: 0x19,
                     jump: 0x280,
                 },
+                water_transition_ticks: 8,
                 frames_left: [
                     Rect { left: 0, top: 0, right: 16, bottom: 16 },
                     Rect { left: 16, top: 0, right: 32, bottom: 16 },
@@ -355,6 +539,9 @@ impl EngineConstants {
                     Rect { left: 56, top: 96, right: 80, bottom: 120 },
                     Rect { left: 80, top: 96, right: 104, bottom: 120 },
                 ],
+                iframes: 128,
+                iframe_flicker_interval: 2,
+                iframe_flicker_mode: FlickerMode::Hide,
             },
             booster: BoosterConsts {
                 fuel: 50,
@@ -363,6 +550,10 @@ impl EngineConstants {
                 b2_0_down: 0x5ff,
                 b2_0_left: -0x5ff,
                 b2_0_right: 0x5ff,
+                exhaust_caret: CaretType::Exhaust,
+                exhaust_interval: 3,
+                exhaust_phase_0_8: 0,
+                exhaust_phase_2_0: 1,
             },
             caret: CaretConsts {
                 offsets: [
@@ -486,7 +677,24 @@ impl EngineConstants {
                 snack_rect: Rect { left: 256, top: 48, right: 272, bottom: 64 },
                 water_push_rect: Rect { left: 224, top: 48, right: 240, bottom: 64 },
             },
+            collision: CollisionConsts {
+                player_wall_tolerance: 0x800,
+                npc_wall_tolerance: 0x600,
+                ceiling_floor_tolerance: 0x600,
+                wall_push_out_speed: 0x180,
+                platform_snap_distance: 0x400,
+                slope_snap_speed: 0x400,
+            },
             npc: serde_json::from_str("{}").unwrap(),
+            npc_effects: NPCEffectConsts {
+                shock_duration: 16,
+                boss_shock_duration: 8,
+                hurt_effect_threshold: 14,
+                hurt_caret: CaretType::HurtParticles,
+                hurt_caret_count: 3,
+                death_smoke_count: [3, 7, 12],
+                vanish_smoke_count: [4, 8, 16],
+            },
             weapon: WeaponConsts {
                 bullet_table: vec![
                     // Null
@@ -500,6 +708,7 @@ impl EngineConstants {
                         block_hit_width: 0,
                         block_hit_height: 0,
                         display_bounds: Rect { left: 0, top: 0, right: 0, bottom: 0 },
+                        rotate_with_velocity: false,
                     },
                     // Snake
                     BulletData {
@@ -512,6 +721,7 @@ impl EngineConstants {
                         block_hit_width: 2,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 6,
@@ -523,6 +733,7 @@ impl EngineConstants {
                         block_hit_width: 2,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 8,
@@ -534,6 +745,7 @@ impl EngineConstants {
                         block_hit_width: 2,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     // Polar Star
                     BulletData {
@@ -546,6 +758,7 @@ impl EngineConstants {
                         block_hit_width: 2,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 2,
@@ -557,6 +770,7 @@ impl EngineConstants {
                         block_hit_width: 2,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 4,
@@ -568,6 +782,7 @@ impl EngineConstants {
                         block_hit_width: 2,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     // Fireball
                     BulletData {
@@ -580,6 +795,7 @@ impl EngineConstants {
                         block_hit_width: 4,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 3,
@@ -591,6 +807,7 @@ impl EngineConstants {
                         block_hit_width: 4,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 3,
@@ -602,6 +819,7 @@ impl EngineConstants {
                         block_hit_width: 4,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     // Machine Gun
                     BulletData {
@@ -614,6 +832,7 @@ impl EngineConstants {
                         block_hit_width: 2,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 4,
@@ -625,6 +844,7 @@ impl EngineConstants {
                         block_hit_width: 2,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 6,
@@ -636,6 +856,7 @@ impl EngineConstants {
                         block_hit_width: 2,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     // Missile Launcher
                     BulletData {
@@ -648,6 +869,7 @@ impl EngineConstants {
                         block_hit_width: 2,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 0,
@@ -659,6 +881,7 @@ impl EngineConstants {
                         block_hit_width: 4,
                         block_hit_height: 4,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 0,
@@ -670,6 +893,7 @@ impl EngineConstants {
                         block_hit_width: 0,
                         block_hit_height: 0,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     // Missile Launcher explosion
                     BulletData {
@@ -682,6 +906,7 @@ impl EngineConstants {
                         block_hit_width: 0,
                         block_hit_height: 0,
                         display_bounds: Rect { left: 0, top: 0, right: 0, bottom: 0 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 1,
@@ -693,6 +918,7 @@ impl EngineConstants {
                         block_hit_width: 0,
                         block_hit_height: 0,
                         display_bounds: Rect { left: 0, top: 0, right: 0, bottom: 0 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 1,
@@ -704,6 +930,7 @@ impl EngineConstants {
                         block_hit_width: 0,
                         block_hit_height: 0,
                         display_bounds: Rect { left: 0, top: 0, right: 0, bottom: 0 },
+                        rotate_with_velocity: false,
                     },
                     // Bubbler
                     BulletData {
@@ -716,6 +943,7 @@ impl EngineConstants {
                         block_hit_width: 2,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 4, top: 4, right: 4, bottom: 4 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 2,
@@ -727,6 +955,7 @@ impl EngineConstants {
                         block_hit_width: 2,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 4, top: 4, right: 4, bottom: 4 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 2,
@@ -738,6 +967,7 @@ impl EngineConstants {
                         block_hit_width: 4,
                         block_hit_height: 4,
                         display_bounds: Rect { left: 4, top: 4, right: 4, bottom: 4 },
+                        rotate_with_velocity: false,
                     },
                     // Bubbler level 3 thorns
                     BulletData {
@@ -750,6 +980,7 @@ impl EngineConstants {
                         block_hit_width: 2,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 4, top: 4, right: 4, bottom: 4 },
+                        rotate_with_velocity: false,
                     },
                     // Blade slashes
                     BulletData {
@@ -762,6 +993,7 @@ impl EngineConstants {
                         block_hit_width: 8,
                         block_hit_height: 8,
                         display_bounds: Rect { left: 12, top: 12, right: 12, bottom: 12 },
+                        rotate_with_velocity: false,
                     },
                     // Falling spike
                     BulletData {
@@ -774,6 +1006,7 @@ impl EngineConstants {
                         block_hit_width: 8,
                         block_hit_height: 4,
                         display_bounds: Rect { left: 0, top: 0, right: 0, bottom: 0 },
+                        rotate_with_velocity: false,
                     },
                     // Blade
                     BulletData {
@@ -786,6 +1019,7 @@ impl EngineConstants {
                         block_hit_width: 4,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 6,
@@ -797,6 +1031,7 @@ impl EngineConstants {
                         block_hit_width: 4,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 12, top: 12, right: 12, bottom: 12 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 1,
@@ -808,6 +1043,7 @@ impl EngineConstants {
                         block_hit_width: 4,
                         block_hit_height: 4,
                         display_bounds: Rect { left: 12, top: 12, right: 12, bottom: 12 },
+                        rotate_with_velocity: false,
                     },
                     // Super Missile Launcher
                     BulletData {
@@ -820,6 +1056,7 @@ impl EngineConstants {
                         block_hit_width: 2,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 0,
@@ -831,6 +1068,7 @@ impl EngineConstants {
                         block_hit_width: 4,
                         block_hit_height: 4,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 0,
@@ -842,6 +1080,7 @@ impl EngineConstants {
                         block_hit_width: 0,
                         block_hit_height: 0,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     // Super Missile Launcher explosion
                     BulletData {
@@ -854,6 +1093,7 @@ impl EngineConstants {
                         block_hit_width: 0,
                         block_hit_height: 0,
                         display_bounds: Rect { left: 0, top: 0, right: 0, bottom: 0 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 2,
@@ -865,6 +1105,7 @@ impl EngineConstants {
                         block_hit_width: 0,
                         block_hit_height: 0,
                         display_bounds: Rect { left: 0, top: 0, right: 0, bottom: 0 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 2,
@@ -876,6 +1117,7 @@ impl EngineConstants {
                         block_hit_width: 0,
                         block_hit_height: 0,
                         display_bounds: Rect { left: 0, top: 0, right: 0, bottom: 0 },
+                        rotate_with_velocity: false,
                     },
                     // Nemesis
                     BulletData {
@@ -888,6 +1130,7 @@ impl EngineConstants {
                         block_hit_width: 3,
                         block_hit_height: 3,
                         display_bounds: Rect { left: 8, top: 8, right: 24, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 4,
@@ -899,6 +1142,7 @@ impl EngineConstants {
                         block_hit_width: 2,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 8, top: 8, right: 24, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 1,
@@ -910,6 +1154,7 @@ impl EngineConstants {
                         block_hit_width: 2,
                         block_hit_height: 2,
                         display_bounds: Rect { left: 8, top: 8, right: 24, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     // Spur
                     BulletData {
@@ -922,6 +1167,7 @@ impl EngineConstants {
                         block_hit_width: 3,
                         block_hit_height: 3,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 8,
@@ -933,6 +1179,7 @@ impl EngineConstants {
                         block_hit_width: 3,
                         block_hit_height: 3,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 12,
@@ -944,6 +1191,7 @@ impl EngineConstants {
                         block_hit_width: 3,
                         block_hit_height: 3,
                         display_bounds: Rect { left: 8, top: 8, right: 8, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     // Spur trail
                     BulletData {
@@ -956,6 +1204,7 @@ impl EngineConstants {
                         block_hit_width: 3,
                         block_hit_height: 3,
                         display_bounds: Rect { left: 4, top: 4, right: 4, bottom: 4 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 6,
@@ -967,6 +1216,7 @@ impl EngineConstants {
                         block_hit_width: 3,
                         block_hit_height: 3,
                         display_bounds: Rect { left: 4, top: 4, right: 4, bottom: 4 },
+                        rotate_with_velocity: false,
                     },
                     BulletData {
                         damage: 11,
@@ -978,6 +1228,7 @@ impl EngineConstants {
                         block_hit_width: 3,
                         block_hit_height: 3,
                         display_bounds: Rect { left: 4, top: 4, right: 4, bottom: 4 },
+                        rotate_with_velocity: false,
                     },
                     // Curly's Nemesis
                     BulletData {
@@ -990,6 +1241,7 @@ impl EngineConstants {
                         block_hit_width: 3,
                         block_hit_height: 3,
                         display_bounds: Rect { left: 8, top: 8, right: 24, bottom: 8 },
+                        rotate_with_velocity: false,
                     },
                     // EnemyClear?
                     BulletData {
@@ -1002,6 +1254,7 @@ impl EngineConstants {
                         block_hit_width: 0,
                         block_hit_height: 0,
                         display_bounds: Rect { left: 0, top: 0, right: 0, bottom: 0 },
+                        rotate_with_velocity: false,
                     },
                     // Whimsical Star
                     BulletData {
@@ -1014,6 +1267,7 @@ impl EngineConstants {
                         block_hit_width: 1,
                         block_hit_height: 1,
                         display_bounds: Rect { left: 1, top: 1, right: 1, bottom: 1 },
+                        rotate_with_velocity: false,
                     },
                 ],
                 bullet_rects: BulletRects {
@@ -1267,6 +1521,8 @@ impl EngineConstants {
                     [1, 1, 1],
                     [40, 60, 200],
                 ],
+                machine_gun_spread: 0xaa,
+                bubbler_spread: 0x100,
             },
             tex_sizes: case_insensitive_hashmap! {
                 "ArmsImage" => (256, 16),
@@ -1505,6 +1761,16 @@ impl EngineConstants {
                     Rect { left: 48, top: 16, right: 64, bottom: 32 },
                 ],
             },
+            map_system: MapSystemConsts {
+                tile_rects: [
+                    Rect { left: 240, top: 24, right: 241, bottom: 25 },
+                    Rect { left: 241, top: 24, right: 242, bottom: 25 },
+                    Rect { left: 242, top: 24, right: 243, bottom: 25 },
+                    Rect { left: 243, top: 24, right: 244, bottom: 25 },
+                ],
+                tile_classes: MapSystemConsts::vanilla_tile_classes(),
+                player_rect: Rect { left: 0, top: 57, right: 1, bottom: 58 },
+            },
             inventory_dim_color: Color::from_rgba(0, 0, 0, 0),
             font_path: "csfont.fnt".to_owned(),
             font_space_offset: 0.0,
@@ -1571,9 +1837,13 @@ impl EngineConstants {
                 "endpic/".to_owned(),          // NXEngine
             ],
             player_skin_paths: vec!["MyChar".to_owned()],
+            equip_appearances: HashMap::new(),
             animated_face_table: vec![AnimatedFace { face_id: 0, anim_id: 0, anim_frames: vec![(0, 0)] }],
             string_table: HashMap::new(),
             missile_flags: vec![200, 201, 202, 218, 550, 766, 880, 920, 1551],
+            // vanilla tilesets have no frame-swapped tiles, water and currents are drawn separately
+            tile_animations: HashMap::new(),
+            tile_animation_frame_time: 8,
             locales: Vec::new(),
             gamepad: {
                 let mut holder = GamepadConsts {
@@ -1613,6 +1883,7 @@ impl EngineConstants {
                 holder
             },
             stage_encoding: None,
+            npc_spawns: HashMap::new(),
         }
     }
 
@@ -1667,6 +1938,12 @@ impl EngineConstants {
         let _ = sound_manager.set_sample_params(2, typewriter_sample);
     }
 
+    /// Returns the file name (without extension or directory) of the song with given id, if there's one.
+    /// Loop points aren't stored here, Organya files carry their own and Ogg soundtracks use `_intro`/`_loop` parts.
+    pub fn music_path(&self, song_id: usize) -> Option<&str> {
+        self.music_table.get(song_id).map(String::as_str)
+    }
+
     pub fn is_base(&self) -> bool {
         !self.is_switch && !self.is_cs_plus && !self.is_demo
     }
@@ -1838,6 +2115,18 @@ impl EngineConstants {
         Ok(())
     }
 
+    pub fn load_npc_spawn_table(&mut self, ctx: &mut Context) -> GameResult {
+        self.npc_spawns.clear();
+
+        if let Ok(file) = filesystem::open_find(ctx, &self.base_paths, "npc_spawns.json") {
+            match serde_json::from_reader::<_, NPCSpawnTable>(file) {
+                Ok(table) => self.npc_spawns = table.spawns,
+                Err(err) => log::warn!("Failed to deserialize NPC spawn table: {}", err),
+            }
+        }
+        Ok(())
+    }
+
     /// Loads bullet.tbl and arms_level.tbl from CS+ files,
     /// even though they match vanilla 1:1, we should load them for completeness
     /// or if any crazy person uses it for a CS+ mod...
@@ -1865,6 +2154,7 @@ impl EngineConstants {
                         right: f.read_u32::<LE>()? as u8,
                         bottom: f.read_u32::<LE>()? as u8,
                     },
+                    rotate_with_velocity: false,
                 };
                 new_bullet_table.push(bullet);
             }
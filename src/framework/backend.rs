@@ -5,8 +5,8 @@ use imgui::DrawData;
 
 use crate::common::{Color, Rect};
 use crate::framework::context::Context;
-use crate::framework::error::GameResult;
-use crate::framework::graphics::{BlendMode, VSyncMode};
+use crate::framework::error::{GameError, GameResult};
+use crate::framework::graphics::{BlendMode, ColorSpace, TextureFormat, VSyncMode};
 use crate::game::Game;
 
 #[repr(C)]
@@ -25,6 +25,22 @@ pub enum BackendShader {
     Texture,
 }
 
+/// Snapshot of the renderer internals, shown by the live debugger.
+#[derive(Clone, Debug, Default)]
+pub struct RendererDebugInfo {
+    pub blend_mode: String,
+    /// `None` if clipping is disabled.
+    pub clip_rect: Option<Rect<isize>>,
+    /// Address of the texture being drawn into, `None` when drawing to the screen.
+    pub render_target: Option<usize>,
+    /// Offset of the viewport within the render target, in pixels.
+    pub viewport_offset: (isize, isize),
+    /// Number of states saved with `push_state`.
+    pub state_depth: usize,
+    /// Draw calls issued during the last presented frame.
+    pub draw_calls: usize,
+}
+
 pub trait Backend {
     fn create_event_loop(&self, ctx: &Context) -> GameResult<Box<dyn BackendEventLoop>>;
 
@@ -36,6 +52,40 @@ pub trait BackendEventLoop {
 
     fn new_renderer(&self, ctx: *mut Context) -> GameResult<Box<dyn BackendRenderer>>;
 
+    /// Returns the refresh rate of the display the game window is on, if the backend can tell.
+    fn display_refresh_rate(&self) -> Option<u32> {
+        None
+    }
+
+    /// Keeps the game window above all other windows, eg. when it's captured as a stream overlay.
+    fn set_always_on_top(&self, _always_on_top: bool) -> GameResult {
+        Err(GameError::WindowError("Pinning the window on top is not supported by this backend.".to_string()))
+    }
+
+    /// Moves the mouse cursor to given position in window pixels.
+    fn warp_mouse(&self, _x: i32, _y: i32) -> GameResult {
+        Err(GameError::WindowError("Moving the mouse cursor is not supported by this backend.".to_string()))
+    }
+
+    /// Hides the cursor and confines it to the window while still reporting motion.
+    fn set_relative_mouse_mode(&self, _enabled: bool) -> GameResult {
+        Err(GameError::WindowError("Relative mouse mode is not supported by this backend.".to_string()))
+    }
+
+    /// Returns the text on the system clipboard, `None` if it's empty or doesn't hold text.
+    fn get_clipboard(&self) -> GameResult<Option<String>> {
+        Err(GameError::WindowError("Clipboard access is not supported by this backend.".to_string()))
+    }
+
+    fn set_clipboard(&self, _text: &str) -> GameResult {
+        Err(GameError::WindowError("Clipboard access is not supported by this backend.".to_string()))
+    }
+
+    /// Sets the opacity of the whole game window, clamped to `[0.0-1.0]`.
+    fn set_window_opacity(&self, _opacity: f32) -> GameResult {
+        Err(GameError::WindowError("Window opacity is not supported by this backend.".to_string()))
+    }
+
     fn as_any(&self) -> &dyn Any;
 }
 
@@ -50,6 +100,16 @@ pub trait BackendRenderer {
         Ok(())
     }
 
+    /// Removes or restores the window decorations, returns the window size afterwards.
+    fn set_borderless(&mut self, _borderless: bool) -> GameResult<(f32, f32)> {
+        Err(GameError::RenderError("Borderless windows are not supported by this backend.".to_string()))
+    }
+
+    /// Resizes the window, returns the window size afterwards.
+    fn set_window_size(&mut self, _width: u32, _height: u32) -> GameResult<(f32, f32)> {
+        Err(GameError::RenderError("Resizing the window is not supported by this backend.".to_string()))
+    }
+
     fn prepare_draw(&mut self, _width: f32, _height: f32) -> GameResult {
         Ok(())
     }
@@ -58,10 +118,28 @@ pub trait BackendRenderer {
 
     fn create_texture(&mut self, width: u16, height: u16, data: &[u8]) -> GameResult<Box<dyn BackendTexture>>;
 
+    /// Like `create_texture`, but also keeps a CPU-side copy of `data`, as much memory as the texture itself.
+    /// Needed by `alpha_at`, HSV shifted drawing and `into_render_target`, backends that don't use it
+    /// create a regular texture.
+    fn create_texture_keep_pixels(
+        &mut self,
+        width: u16,
+        height: u16,
+        data: &[u8],
+    ) -> GameResult<Box<dyn BackendTexture>> {
+        self.create_texture(width, height, data)
+    }
+
     fn set_blend_mode(&mut self, blend: BlendMode) -> GameResult;
 
     fn set_render_target(&mut self, texture: Option<&Box<dyn BackendTexture>>) -> GameResult;
 
+    /// Draws a single pixel of the texture somewhere offscreen so the driver uploads it before it's first used.
+    /// This is only a heuristic against hitches on first draw, some drivers defer the upload anyway.
+    fn warm_texture(&mut self, _texture: &Box<dyn BackendTexture>) -> GameResult {
+        Ok(())
+    }
+
     fn draw_rect(&mut self, rect: Rect, color: Color) -> GameResult;
 
     fn draw_outline_rect(&mut self, rect: Rect, line_width: usize, color: Color) -> GameResult;
@@ -76,10 +154,76 @@ pub trait BackendRenderer {
 
     fn render_imgui(&mut self, draw_data: &DrawData) -> GameResult;
 
+    /// Reads back the current contents of the screen as tightly packed RGBA pixels, top row first.
+    fn read_screen_pixels(&mut self) -> GameResult<(u32, u32, Vec<u8>)> {
+        Err(GameError::RenderError("Reading back the screen is not supported by this backend.".to_string()))
+    }
+
+    /// Multiplies the color and alpha of every sprite drawn to the screen until the end of the frame by `color`,
+    /// meant for fading the whole screen. Per-command tints are multiplied with it. It's reset to white after
+    /// presenting. Draws into render targets aren't tinted, so cached content isn't tinted twice.
+    fn set_global_tint(&mut self, _color: Color) -> GameResult {
+        Err(GameError::RenderError("Global tint is not supported by this backend.".to_string()))
+    }
+
+    /// Scales the destination of every sprite drawn to the screen by `factor` around `center` (in screen pixels),
+    /// values above 1.0 zoom in. Draws into render targets aren't zoomed. Unlike the window scale this is an in-world
+    /// effect for cutscenes, and unlike the global tint it persists across frames until it's set back to 1.0.
+    fn set_zoom(&mut self, _factor: f32, _center: (f32, f32)) -> GameResult {
+        Err(GameError::RenderError("Zooming is not supported by this backend.".to_string()))
+    }
+
+    /// Sets the color space blending happens in. Backends only blend in `sRGB` unless they override this.
+    fn set_color_space(&mut self, color_space: ColorSpace) -> GameResult {
+        match color_space {
+            ColorSpace::Srgb => Ok(()),
+            ColorSpace::Linear => {
+                Err(GameError::RenderError("Linear color blending is not supported by this backend.".to_string()))
+            }
+        }
+    }
+
+    fn supported_texture_formats(&self) -> Vec<TextureFormat> {
+        vec![TextureFormat::Rgba32]
+    }
+
+    /// Sets the format of textures created afterwards, it has to be one of `supported_texture_formats`.
+    fn set_texture_format(&mut self, format: TextureFormat) -> GameResult {
+        match format {
+            TextureFormat::Rgba32 => Ok(()),
+            _ => Err(GameError::RenderError(format!("Texture format {:?} is not supported by this backend.", format))),
+        }
+    }
+
+    /// Sets a lookup table remapping the colors of everything drawn, `None` disables it.
+    /// The LUT is 256 pixels wide, each input channel value picks the output from its column.
+    fn set_color_lut(&mut self, _lut: Option<Box<dyn BackendTexture>>) -> GameResult {
+        Err(GameError::RenderError("Color LUTs are not supported by this backend.".to_string()))
+    }
+
+    /// Saves the current blend mode, clip rect, render target, viewport offset, global tint and zoom on a stack.
+    fn push_state(&mut self) -> GameResult {
+        Err(GameError::RenderError("Saving renderer state is not supported by this backend.".to_string()))
+    }
+
+    /// Restores the state saved by the matching `push_state` call.
+    fn pop_state(&mut self) -> GameResult {
+        Err(GameError::RenderError("Saving renderer state is not supported by this backend.".to_string()))
+    }
+
     fn supports_vertex_draw(&self) -> bool {
         false
     }
 
+    /// Returns the current renderer state for debugging, `None` if the backend doesn't expose it.
+    fn debug_info(&mut self) -> Option<RendererDebugInfo> {
+        None
+    }
+
+    /// Outlines the destination of every sprite drawn afterwards, meant for checking sprite placement.
+    #[cfg(feature = "debug-outlines")]
+    fn set_sprite_outlines(&mut self, _enabled: bool) {}
+
     fn draw_triangle_list(
         &mut self,
         vertices: &[VertexData],
@@ -87,6 +231,54 @@ pub trait BackendRenderer {
         shader: BackendShader,
     ) -> GameResult;
 
+    /// Draws an additive radial gradient, used to briefly brighten the area around explosions.
+    /// `intensity` (`[0.0-1.0]`) scales the brightness at the center, callers are expected to decay it
+    /// over a few frames.
+    fn draw_flash(&mut self, center: (f32, f32), radius: f32, color: Color, intensity: f32) -> GameResult {
+        const SEGMENTS: usize = 24;
+
+        let intensity = intensity.clamp(0.0, 1.0);
+        if !self.supports_vertex_draw() || intensity <= 0.0 || radius <= 0.0 {
+            return Ok(());
+        }
+
+        // Additive blending ignores alpha on some backends, so the falloff is baked into the color itself.
+        let factor = intensity * color.a;
+        let inner = Color::new(color.r * factor, color.g * factor, color.b * factor, 1.0).to_rgba();
+        let outer = (0, 0, 0, 0);
+
+        let mut vertices = Vec::with_capacity(SEGMENTS * 3);
+        for i in 0..SEGMENTS {
+            let angle1 = i as f32 / SEGMENTS as f32 * std::f32::consts::TAU;
+            let angle2 = (i + 1) as f32 / SEGMENTS as f32 * std::f32::consts::TAU;
+
+            vertices.push(VertexData { position: center, color: inner, uv: (0.0, 0.0) });
+            vertices.push(VertexData {
+                position: (center.0 + angle1.cos() * radius, center.1 + angle1.sin() * radius),
+                color: outer,
+                uv: (0.0, 0.0),
+            });
+            vertices.push(VertexData {
+                position: (center.0 + angle2.cos() * radius, center.1 + angle2.sin() * radius),
+                color: outer,
+                uv: (0.0, 0.0),
+            });
+        }
+
+        // the caller's blend mode is put back afterwards, backends without a state stack fall back to Alpha
+        let saved = self.push_state().is_ok();
+        let result = self
+            .set_blend_mode(BlendMode::Add)
+            .and_then(|_| self.draw_triangle_list(&vertices, None, BackendShader::Fill));
+
+        if saved {
+            self.pop_state()?;
+        } else {
+            self.set_blend_mode(BlendMode::Alpha)?;
+        }
+        result
+    }
+
     fn as_any(&self) -> &dyn Any;
 }
 
@@ -95,10 +287,33 @@ pub trait BackendTexture {
 
     fn add(&mut self, command: SpriteBatchCommand);
 
+    /// Queues a command with a draw-order key, only taken into account once `set_sort_by_z` enabled sorting.
+    fn add_z(&mut self, _z: i16, command: SpriteBatchCommand) {
+        self.add(command);
+    }
+
+    /// When enabled, queued commands are stable sorted by their z (`0` for plain `add`) before drawing.
+    /// Off by default, backends that don't support it draw in insertion order.
+    fn set_sort_by_z(&mut self, _enabled: bool) {}
+
     fn clear(&mut self);
 
     fn draw(&mut self) -> GameResult;
 
+    /// Returns the alpha value of a texture pixel, read from a CPU-side copy instead of the GPU.
+    /// `None` if the coordinates are out of bounds or the texture wasn't made with `create_texture_keep_pixels`.
+    fn alpha_at(&self, _x: u16, _y: u16) -> Option<u8> {
+        None
+    }
+
+    /// Turns the texture into one that can be rendered to, keeping its contents. This recreates the texture
+    /// and uploads its data again, so it's slow, and the CPU-side copy (see `alpha_at`) is dropped since
+    /// rendering makes it stale. Only textures made with `create_texture_keep_pixels` can be converted, for
+    /// game textures use `TextureSet::make_render_target`.
+    fn into_render_target(&mut self) -> GameResult {
+        Err(GameError::RenderError("Render target conversion is not supported by this backend.".to_string()))
+    }
+
     fn as_any(&self) -> &dyn Any;
 }
 
@@ -135,7 +350,84 @@ pub fn init_backend(headless: bool, size_hint: (u16, u16)) -> GameResult<Box<dyn
 
 pub enum SpriteBatchCommand {
     DrawRect(Rect<f32>, Rect<f32>),
+    /// Draws the rect mirrored horizontally and/or vertically.
     DrawRectFlip(Rect<f32>, Rect<f32>, bool, bool),
+    /// Draws the rect with its colors and alpha multiplied by given color.
     DrawRectTinted(Rect<f32>, Rect<f32>, Color),
+    /// Flip and tint combined, drawn in a single pass (e.g. enemies facing left while flashing on hit).
     DrawRectFlipTinted(Rect<f32>, Rect<f32>, bool, bool, Color),
+    /// Like `DrawRect`, but the destination is never snapped to whole pixels, even by backends that round it otherwise.
+    DrawRectF(Rect<f32>, Rect<f32>),
+    /// Draws the rect with its colors shifted in HSV space: hue shift in degrees, saturation and value multipliers.
+    /// Backends without a way to do this at draw time may fall back to an untinted `DrawRect`.
+    DrawRectHsv(Rect<f32>, Rect<f32>, f32, f32, f32),
+    /// Draws the source rect mapped onto an arbitrary quad, corners are given clockwise starting from top-left.
+    /// The quad is split into two triangles along the top-left/bottom-right diagonal and each one is mapped
+    /// affinely, so quads that aren't parallelograms show a visible seam instead of perspective correct texturing.
+    DrawQuad(Rect<f32>, [(f32, f32); 4]),
+    /// Combined flip and rotation: angle in degrees (clockwise), rotation center relative to the top-left corner
+    /// of the destination (`None` rotates around its middle), then horizontal/vertical flip.
+    DrawRectEx(Rect<f32>, Rect<f32>, f32, Option<(f32, f32)>, bool, bool),
+}
+
+impl SpriteBatchCommand {
+    /// Area of the screen the command draws to, for `DrawQuad` and rotated rects it's the unrotated destination.
+    pub fn dest_bounds(&self) -> Rect<f32> {
+        match self {
+            SpriteBatchCommand::DrawRect(_, dest)
+            | SpriteBatchCommand::DrawRectFlip(_, dest, _, _)
+            | SpriteBatchCommand::DrawRectTinted(_, dest, _)
+            | SpriteBatchCommand::DrawRectFlipTinted(_, dest, _, _, _)
+            | SpriteBatchCommand::DrawRectF(_, dest)
+            | SpriteBatchCommand::DrawRectHsv(_, dest, _, _, _)
+            | SpriteBatchCommand::DrawRectEx(_, dest, _, _, _, _) => *dest,
+            SpriteBatchCommand::DrawQuad(_, corners) => {
+                let mut bounds = Rect::new(corners[0].0, corners[0].1, corners[0].0, corners[0].1);
+                for &(x, y) in &corners[1..] {
+                    bounds.left = bounds.left.min(x);
+                    bounds.top = bounds.top.min(y);
+                    bounds.right = bounds.right.max(x);
+                    bounds.bottom = bounds.bottom.max(y);
+                }
+                bounds
+            }
+        }
+    }
+
+    /// Returns the command with its destination scaled by `factor` about `center`. Plain rects are turned into
+    /// `DrawRectF` so fractional zoom levels don't make sprites jitter when snapped to whole pixels.
+    pub fn zoomed(&self, factor: f32, center: (f32, f32)) -> SpriteBatchCommand {
+        let (cx, cy) = center;
+        let point = |x: f32, y: f32| (cx + (x - cx) * factor, cy + (y - cy) * factor);
+        let rect = |dest: &Rect<f32>| {
+            let (left, top) = point(dest.left, dest.top);
+            let (right, bottom) = point(dest.right, dest.bottom);
+            Rect::new(left, top, right, bottom)
+        };
+
+        match self {
+            SpriteBatchCommand::DrawRect(src, dest) | SpriteBatchCommand::DrawRectF(src, dest) => {
+                SpriteBatchCommand::DrawRectF(*src, rect(dest))
+            }
+            SpriteBatchCommand::DrawRectFlip(src, dest, flip_x, flip_y) => {
+                SpriteBatchCommand::DrawRectFlip(*src, rect(dest), *flip_x, *flip_y)
+            }
+            SpriteBatchCommand::DrawRectTinted(src, dest, color) => {
+                SpriteBatchCommand::DrawRectTinted(*src, rect(dest), *color)
+            }
+            SpriteBatchCommand::DrawRectFlipTinted(src, dest, flip_x, flip_y, color) => {
+                SpriteBatchCommand::DrawRectFlipTinted(*src, rect(dest), *flip_x, *flip_y, *color)
+            }
+            SpriteBatchCommand::DrawRectHsv(src, dest, hue_shift, sat_mul, val_mul) => {
+                SpriteBatchCommand::DrawRectHsv(*src, rect(dest), *hue_shift, *sat_mul, *val_mul)
+            }
+            SpriteBatchCommand::DrawQuad(src, corners) => {
+                SpriteBatchCommand::DrawQuad(*src, corners.map(|(x, y)| point(x, y)))
+            }
+            SpriteBatchCommand::DrawRectEx(src, dest, angle, rot_center, flip_x, flip_y) => {
+                let rot_center = rot_center.map(|(x, y)| (x * factor, y * factor));
+                SpriteBatchCommand::DrawRectEx(*src, rect(dest), *angle, rot_center, *flip_x, *flip_y)
+            }
+        }
+    }
 }
@@ -2,7 +2,6 @@ use std::any::Any;
 use std::cell::{RefCell, UnsafeCell};
 use std::ffi::c_void;
 use std::io::Read;
-use std::mem;
 use std::rc::Rc;
 use std::sync::Arc;
 use std::vec::Vec;
@@ -18,6 +17,7 @@ use crate::common::Rect;
 use crate::framework::backend::{Backend, BackendEventLoop, BackendRenderer, BackendTexture, SpriteBatchCommand};
 use crate::framework::context::Context;
 use crate::framework::error::GameResult;
+use crate::framework::event::{self, GameEvent};
 use crate::framework::filesystem;
 use crate::framework::gl;
 use crate::framework::keyboard::ScanCode;
@@ -194,7 +194,8 @@ impl BackendEventLoop for GlutinEventLoop {
                 Event::WindowEvent { event: WindowEvent::CloseRequested, window_id }
                     if window_id == window.window().id() =>
                 {
-                    state_ref.shutdown();
+                    event::push_event(ctx, GameEvent::Quit);
+                    state_ref.request_quit();
                 }
                 Event::Resumed => {
                     {
@@ -236,6 +237,7 @@ impl BackendEventLoop for GlutinEventLoop {
 
                         ctx.real_screen_size = (size.width, size.height);
                         ctx.screen_size = get_scaled_size(size.width.max(1), size.height.max(1));
+                        event::push_event(ctx, GameEvent::Resize(ctx.screen_size.0, ctx.screen_size.1));
                         state_ref.handle_resize(ctx).unwrap();
                     }
                 }
@@ -243,9 +245,11 @@ impl BackendEventLoop for GlutinEventLoop {
                     if window_id == window.window().id() =>
                 {
                     let mut controls = &mut state_ref.touch_controls;
-                    let scale = state_ref.scale as f64;
-                    let loc_x = (touch.location.x * ctx.screen_size.0 as f64 / ctx.real_screen_size.0 as f64) / scale;
-                    let loc_y = (touch.location.y * ctx.screen_size.1 as f64 / ctx.real_screen_size.1 as f64) / scale;
+                    let (loc_x, loc_y) = state_ref.screen_to_canvas(
+                        (touch.location.x * ctx.screen_size.0 as f64 / ctx.real_screen_size.0 as f64) as f32,
+                        (touch.location.y * ctx.screen_size.1 as f64 / ctx.real_screen_size.1 as f64) as f32,
+                    );
+                    let (loc_x, loc_y) = (loc_x as f64, loc_y as f64);
 
                     match touch.phase {
                         TouchPhase::Started | TouchPhase::Moved => {
@@ -284,6 +288,12 @@ impl BackendEventLoop for GlutinEventLoop {
                                 ElementState::Released => false,
                             };
 
+                            if key_state != ctx.keyboard_context.is_key_pressed(drs_scan) {
+                                let event =
+                                    if key_state { GameEvent::KeyDown(drs_scan) } else { GameEvent::KeyUp(drs_scan) };
+                                event::push_event(ctx, event);
+                            }
+
                             ctx.keyboard_context.set_key(drs_scan, key_state);
                         }
                     }
@@ -352,8 +362,7 @@ impl BackendEventLoop for GlutinEventLoop {
                     }
 
                     if state_ref.next_scene.is_some() {
-                        mem::swap(&mut game.scene, &mut state_ref.next_scene);
-                        state_ref.next_scene = None;
+                        game.set_scene(state_ref.next_scene.take());
                         game.scene.as_mut().unwrap().init(state_ref, ctx).unwrap();
                         game.loops = 0;
                         state_ref.frame_time = 0.0;
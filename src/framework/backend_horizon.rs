@@ -328,7 +328,8 @@ impl HorizonEventLoop {
                     // connected
                     log::info!("Gamepad {} connected", id);
 
-                    let axis_sensitivity = state.settings.get_gamepad_axis_sensitivity(id as u32);
+                    let slot = ctx.gamepad_context.next_free_slot();
+                    let axis_sensitivity = state.settings.get_gamepad_axis_sensitivity(slot as u32);
                     ctx.gamepad_context.add_gamepad(HorizonGamepad::new(id as u32), axis_sensitivity);
 
                     ctx.gamepad_context.set_gamepad_type(id as u32, GamepadType::NintendoSwitchJoyConPair);
@@ -403,8 +404,7 @@ impl BackendEventLoop for HorizonEventLoop {
             }
 
             if state_ref.next_scene.is_some() {
-                mem::swap(&mut game.scene, &mut state_ref.next_scene);
-                state_ref.next_scene = None;
+                game.set_scene(state_ref.next_scene.take());
                 game.scene.as_mut().unwrap().init(state_ref, ctx).unwrap();
                 game.loops = 0;
                 state_ref.frame_time = 0.0;
@@ -782,6 +782,53 @@ impl BackendTexture for Deko3DTexture {
                 ];
                 self.vertices.extend_from_slice(&vertices);
             }
+            SpriteBatchCommand::DrawRectF(src, dest) => {
+                // vertex positions are floats already, no special handling needed
+                self.add(SpriteBatchCommand::DrawRect(src, dest));
+            }
+            SpriteBatchCommand::DrawRectHsv(src, dest, _, _, _) => {
+                // no HSV support in the default shaders yet
+                self.add(SpriteBatchCommand::DrawRect(src, dest));
+            }
+            SpriteBatchCommand::DrawQuad(src, corners) => {
+                let uv = [
+                    (src.left * tex_scale_x, src.top * tex_scale_y),
+                    (src.right * tex_scale_x, src.top * tex_scale_y),
+                    (src.right * tex_scale_x, src.bottom * tex_scale_y),
+                    (src.left * tex_scale_x, src.bottom * tex_scale_y),
+                ];
+
+                for i in [0, 1, 2, 0, 2, 3] {
+                    self.vertices.push(VertexData { position: corners[i], uv: uv[i], color: (255, 255, 255, 255) });
+                }
+            }
+            SpriteBatchCommand::DrawRectEx(mut src, dest, angle, center, flip_x, flip_y) => {
+                if flip_x {
+                    std::mem::swap(&mut src.left, &mut src.right);
+                }
+
+                if flip_y {
+                    std::mem::swap(&mut src.top, &mut src.bottom);
+                }
+
+                let (cx, cy) = center.unwrap_or((dest.width() / 2.0, dest.height() / 2.0));
+                let (cx, cy) = (dest.left + cx, dest.top + cy);
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let rotate = |x: f32, y: f32| {
+                    let (dx, dy) = (x - cx, y - cy);
+                    (cx + dx * cos - dy * sin, cy + dx * sin + dy * cos)
+                };
+
+                self.add(SpriteBatchCommand::DrawQuad(
+                    src,
+                    [
+                        rotate(dest.left, dest.top),
+                        rotate(dest.right, dest.top),
+                        rotate(dest.right, dest.bottom),
+                        rotate(dest.left, dest.bottom),
+                    ],
+                ));
+            }
         }
     }
 
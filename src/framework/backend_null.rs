@@ -1,6 +1,5 @@
 use std::any::Any;
 use std::cell::RefCell;
-use std::mem;
 
 use imgui::{DrawData, TextureId, Ui};
 
@@ -49,8 +48,7 @@ impl BackendEventLoop for NullEventLoop {
             }
 
             if state_ref.next_scene.is_some() {
-                mem::swap(&mut game.scene, &mut state_ref.next_scene);
-                state_ref.next_scene = None;
+                game.set_scene(state_ref.next_scene.take());
                 game.scene.as_mut().unwrap().init(state_ref, ctx).unwrap();
                 game.loops = 0;
                 state_ref.frame_time = 0.0;
@@ -74,7 +72,8 @@ impl BackendEventLoop for NullEventLoop {
     }
 }
 
-pub struct NullTexture(u16, u16);
+/// Width, height and the pixel data, only kept for textures made with `create_texture_keep_pixels`.
+pub struct NullTexture(u16, u16, Vec<u8>);
 
 impl BackendTexture for NullTexture {
     fn dimensions(&self) -> (u16, u16) {
@@ -89,6 +88,14 @@ impl BackendTexture for NullTexture {
         Ok(())
     }
 
+    fn alpha_at(&self, x: u16, y: u16) -> Option<u8> {
+        if x >= self.0 || y >= self.1 {
+            return None;
+        }
+
+        self.2.get((y as usize * self.0 as usize + x as usize) * 4 + 3).copied()
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -108,11 +115,20 @@ impl BackendRenderer for NullRenderer {
     }
 
     fn create_texture_mutable(&mut self, width: u16, height: u16) -> GameResult<Box<dyn BackendTexture>> {
-        Ok(Box::new(NullTexture(width, height)))
+        Ok(Box::new(NullTexture(width, height, Vec::new())))
     }
 
     fn create_texture(&mut self, width: u16, height: u16, _data: &[u8]) -> GameResult<Box<dyn BackendTexture>> {
-        Ok(Box::new(NullTexture(width, height)))
+        Ok(Box::new(NullTexture(width, height, Vec::new())))
+    }
+
+    fn create_texture_keep_pixels(
+        &mut self,
+        width: u16,
+        height: u16,
+        data: &[u8],
+    ) -> GameResult<Box<dyn BackendTexture>> {
+        Ok(Box::new(NullTexture(width, height, data[..(width as usize * height as usize * 4)].to_vec())))
     }
 
     fn set_blend_mode(&mut self, _blend: BlendMode) -> GameResult {
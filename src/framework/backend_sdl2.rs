@@ -4,6 +4,7 @@ use std::cell::{RefCell, UnsafeCell};
 use std::ffi::c_void;
 use std::io::Read;
 use std::ops::Deref;
+use std::path::{Path, PathBuf};
 use std::ptr::{null, null_mut};
 use std::rc::Rc;
 use std::time::{Duration, Instant};
@@ -15,9 +16,10 @@ use imgui::{ConfigFlags, DrawCmd, DrawData, DrawIdx, DrawVert, Key, MouseCursor,
 use sdl2::controller::GameController;
 use sdl2::event::{Event, WindowEvent};
 use sdl2::keyboard::Scancode;
+use sdl2::messagebox::{show_simple_message_box, MessageBoxFlag};
 use sdl2::mouse::{Cursor, SystemCursor};
 use sdl2::pixels::PixelFormatEnum;
-use sdl2::render::{Texture, TextureCreator, TextureQuery, WindowCanvas};
+use sdl2::render::{Texture, TextureAccess, TextureCreator, TextureQuery, WindowCanvas};
 use sdl2::rwops::RWops;
 use sdl2::surface::Surface;
 use sdl2::video::GLProfile;
@@ -27,19 +29,21 @@ use sdl2::{controller, keyboard, pixels, EventPump, GameControllerSubsystem, Sdl
 
 use crate::common::{Color, Rect};
 use crate::framework::backend::{
-    Backend, BackendEventLoop, BackendGamepad, BackendRenderer, BackendShader, BackendTexture, SpriteBatchCommand,
-    VertexData,
+    Backend, BackendEventLoop, BackendGamepad, BackendRenderer, BackendShader, BackendTexture, RendererDebugInfo,
+    SpriteBatchCommand, VertexData,
 };
 use crate::framework::context::Context;
 use crate::framework::error::{GameError, GameResult};
+use crate::framework::event::{self, GameEvent};
+use crate::framework::event_trace::{is_input_event, EventPlayback, EventRecorder};
 use crate::framework::filesystem;
 use crate::framework::gamepad::{Axis, Button, GamepadType};
-use crate::framework::graphics::BlendMode;
+use crate::framework::graphics::{BlendMode, TextureFormat};
 use crate::framework::keyboard::ScanCode;
 #[cfg(feature = "render-opengl")]
 use crate::framework::render_opengl::{GLContext, OpenGLRenderer};
 use crate::framework::ui::init_imgui;
-use crate::game::shared_game_state::WindowMode;
+use crate::game::shared_game_state::{SharedGameState, WindowMode};
 use crate::game::Game;
 use crate::game::GAME_SUSPENDED;
 
@@ -147,6 +151,8 @@ struct SDL2EventLoop {
     event_pump: EventPump,
     refs: Rc<RefCell<SDL2Context>>,
     opengl_available: RefCell<bool>,
+    recorder: Option<EventRecorder>,
+    playback: Option<EventPlayback>,
 }
 
 struct SDL2Context {
@@ -155,7 +161,78 @@ struct SDL2Context {
     gl_context: Option<sdl2::video::GLContext>,
     blend_mode: sdl2::render::BlendMode,
     fullscreen_type: sdl2::video::FullscreenType,
+    always_on_top: bool,
+    opacity: f32,
     game_controller: GameControllerSubsystem,
+    state_stack: Vec<SDL2RenderState>,
+    texture_format: TextureFormat,
+    /// Multiplied into the color of every sprite drawn to the screen, reset after each frame is presented.
+    /// Render targets are left untinted, their contents get tinted once they're drawn to the screen.
+    global_tint: Color,
+    /// Zoom factor and center applied to destinations of sprites drawn to the screen, kept until changed.
+    /// Like the global tint it's skipped for render targets, the center is in screen coordinates.
+    zoom: (f32, (f32, f32)),
+    /// Draw calls issued since the last present, and the total of the previous frame.
+    draw_calls: usize,
+    last_draw_calls: usize,
+    #[cfg(feature = "debug-outlines")]
+    sprite_outlines: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct SDL2RenderState {
+    blend_mode: sdl2::render::BlendMode,
+    clip_rect: Option<sdl2::rect::Rect>,
+    target: *mut sdl2::sys::SDL_Texture,
+    viewport: sdl2::rect::Rect,
+    global_tint: Color,
+    zoom: (f32, (f32, f32)),
+}
+
+impl SDL2Context {
+    fn current_state(&mut self) -> SDL2RenderState {
+        let blend_mode = self.blend_mode;
+        let global_tint = self.global_tint;
+        let zoom = self.zoom;
+        let canvas = self.window.canvas();
+
+        SDL2RenderState {
+            blend_mode,
+            clip_rect: canvas.clip_rect(),
+            target: unsafe { sdl2::sys::SDL_GetRenderTarget(canvas.raw()) },
+            viewport: canvas.viewport(),
+            global_tint,
+            zoom,
+        }
+    }
+
+    fn restore_state(&mut self, state: SDL2RenderState) -> GameResult {
+        self.blend_mode = state.blend_mode;
+        self.global_tint = state.global_tint;
+        self.zoom = state.zoom;
+        let canvas = self.window.canvas();
+
+        // SDL resets the viewport and clip rect when switching targets, so the target goes first.
+        unsafe {
+            set_raw_target(canvas.raw(), state.target)?;
+        }
+        canvas.set_viewport(Some(state.viewport));
+        canvas.set_clip_rect(state.clip_rect);
+
+        Ok(())
+    }
+
+    fn push_state(&mut self) {
+        let state = self.current_state();
+        self.state_stack.push(state);
+    }
+
+    fn pop_state(&mut self) -> GameResult {
+        match self.state_stack.pop() {
+            Some(state) => self.restore_state(state),
+            None => Err(GameError::RenderError("pop_state called without a matching push_state.".to_string())),
+        }
+    }
 }
 
 impl SDL2EventLoop {
@@ -195,7 +272,7 @@ impl SDL2EventLoop {
         
         let opengl_available = if let Ok(v) = std::env::var("CAVESTORY_NO_OPENGL") { v != "1" } else { true };
 
-        let event_loop = SDL2EventLoop {
+        let mut event_loop = SDL2EventLoop {
             event_pump,
             refs: Rc::new(RefCell::new(SDL2Context {
                 video,
@@ -203,13 +280,103 @@ impl SDL2EventLoop {
                 gl_context: None,
                 blend_mode: sdl2::render::BlendMode::Blend,
                 fullscreen_type: sdl2::video::FullscreenType::Off,
+                always_on_top: false,
+                opacity: 1.0,
                 game_controller,
+                state_stack: Vec::new(),
+                texture_format: TextureFormat::Rgba32,
+                global_tint: Color::new(1.0, 1.0, 1.0, 1.0),
+                zoom: (1.0, (0.0, 0.0)),
+                draw_calls: 0,
+                last_draw_calls: 0,
+                #[cfg(feature = "debug-outlines")]
+                sprite_outlines: false,
             })),
             opengl_available: RefCell::new(opengl_available),
+            recorder: None,
+            playback: None,
         };
 
+        if let Ok(path) = std::env::var("CAVESTORY_REPLAY_EVENTS") {
+            event_loop.replay(Path::new(&path))?;
+        } else if let Ok(path) = std::env::var("CAVESTORY_RECORD_EVENTS") {
+            event_loop.record(Path::new(&path))?;
+        }
+
         Ok(Box::new(event_loop))
     }
+
+    /// Starts writing every input event handled by the event loop to given file.
+    pub fn record(&mut self, path: &Path) -> GameResult {
+        log::info!("Recording input events to {}", path.display());
+        self.recorder = Some(EventRecorder::create(path)?);
+        Ok(())
+    }
+
+    /// Feeds the input events from a trace written by `record` to the game instead of the real input,
+    /// each one in the same event loop iteration it was recorded in. Window events still come from the window.
+    pub fn replay(&mut self, path: &Path) -> GameResult {
+        log::info!("Replaying input events from {}", path.display());
+        self.playback = Some(EventPlayback::open(path)?);
+        Ok(())
+    }
+
+    fn next_events(&mut self, frame: u32) -> Vec<Event> {
+        let playback = match &mut self.playback {
+            Some(playback) => playback,
+            None => return self.event_pump.poll_iter().collect(),
+        };
+
+        // the real input is dropped, the trace's window events are dropped in favor of the real ones
+        let mut events: Vec<Event> = self.event_pump.poll_iter().filter(|event| !is_input_event(event)).collect();
+
+        match playback.events_for_frame(frame) {
+            Ok(recorded) => events.extend(recorded.into_iter().filter(is_input_event)),
+            Err(err) => {
+                log::error!("Failed to read the event trace, stopping playback: {}", err);
+                self.playback = None;
+                return events;
+            }
+        }
+
+        if playback.is_finished() {
+            log::info!("Event trace playback finished.");
+            self.playback = None;
+        }
+
+        events
+    }
+
+    /// Logs an error that stopped the game loop and shows it to the user, since most of them won't see the log.
+    fn report_fatal_error(&mut self, err: &GameError) {
+        log::error!("Fatal error: {}", err);
+
+        if let Some(recorder) = &mut self.recorder {
+            let _ = recorder.flush();
+        }
+
+        let refs = self.refs.borrow();
+        let message = format!("The game has stopped because of an error:\n\n{}", err);
+        if let Err(e) = show_simple_message_box(MessageBoxFlag::ERROR, "doukutsu-rs", &message, refs.window.window()) {
+            log::error!("Failed to show the error dialog: {}", e);
+        }
+    }
+}
+
+/// Updates the screen size after the window changed size and lets the game and imgui know about it.
+fn handle_window_resize(state: &mut SharedGameState, ctx: &mut Context, width: f32, height: f32) {
+    ctx.screen_size = (width.max(1.0), height.max(1.0));
+    event::push_event(ctx, GameEvent::Resize(ctx.screen_size.0, ctx.screen_size.1));
+
+    if let Some(renderer) = &ctx.renderer {
+        if let Ok(imgui) = renderer.imgui() {
+            imgui.io_mut().display_size = [ctx.screen_size.0, ctx.screen_size.1];
+        }
+    }
+
+    if let Err(e) = state.handle_resize(ctx) {
+        log::error!("Failed to handle window resize: {}", e);
+    }
 }
 
 impl BackendEventLoop for SDL2EventLoop {
@@ -229,6 +396,9 @@ impl BackendEventLoop for SDL2EventLoop {
             let _ = state.handle_resize(ctx);
         }
 
+        ctx.clipboard = self.get_clipboard().unwrap_or(None);
+
+        let mut frame = 0u32;
         loop {
             #[cfg(target_os = "macos")]
             unsafe {
@@ -254,12 +424,24 @@ impl BackendEventLoop for SDL2EventLoop {
                 }
             }
 
-            for event in self.event_pump.poll_iter() {
+            for event in self.next_events(frame) {
+                if let Some(recorder) = &mut self.recorder {
+                    if let Err(err) = recorder.record(frame, &event) {
+                        log::error!("Failed to record input event, stopping recording: {}", err);
+                        self.recorder = None;
+                    }
+                }
+
+                for observer in &mut ctx.event_observers {
+                    observer(&event);
+                }
+
                 imgui_sdl2.handle_event(imgui, &event);
 
                 match event {
                     Event::Quit { .. } => {
-                        state.shutdown();
+                        event::push_event(ctx, GameEvent::Quit);
+                        state.request_quit();
                     }
                     Event::Window { win_event, .. } => match win_event {
                         WindowEvent::FocusGained | WindowEvent::Shown => {
@@ -281,15 +463,18 @@ impl BackendEventLoop for SDL2EventLoop {
                                 state.sound_manager.pause();
                             }
                         }
-                        WindowEvent::SizeChanged(width, height) => {
-                            ctx.screen_size = (width.max(1) as f32, height.max(1) as f32);
+                        WindowEvent::Minimized => {
+                            ctx.window_minimized = true;
+                        }
+                        WindowEvent::Restored | WindowEvent::Maximized if ctx.window_minimized => {
+                            ctx.window_minimized = false;
 
-                            if let Some(renderer) = &ctx.renderer {
-                                if let Ok(imgui) = renderer.imgui() {
-                                    imgui.io_mut().display_size = [ctx.screen_size.0, ctx.screen_size.1];
-                                }
-                            }
-                            state.handle_resize(ctx).unwrap();
+                            // the drawable may have been resized while minimized without a SizeChanged event
+                            let (width, height) = self.refs.deref().borrow().window.window().size();
+                            handle_window_resize(state, ctx, width as f32, height as f32);
+                        }
+                        WindowEvent::SizeChanged(width, height) => {
+                            handle_window_resize(state, ctx, width as f32, height as f32);
                         }
                         _ => {}
                     },
@@ -323,25 +508,38 @@ impl BackendEventLoop for SDL2EventLoop {
 
                                     state.settings.window_mode = new_mode;
                                 }
+
+                                event::push_event(ctx, GameEvent::KeyDown(drs_scan));
                             }
                             ctx.keyboard_context.set_key(drs_scan, true);
                         }
                     }
                     Event::KeyUp { scancode: Some(scancode), .. } => {
                         if let Some(drs_scan) = conv_scancode(scancode) {
+                            event::push_event(ctx, GameEvent::KeyUp(drs_scan));
                             ctx.keyboard_context.set_key(drs_scan, false);
                         }
                     }
-                    Event::JoyDeviceAdded { which, .. } => {
+                    Event::MouseMotion { x, y, .. } => {
+                        let (x, y) = state.screen_to_canvas(x as f32, y as f32);
+                        event::push_event(ctx, GameEvent::MouseMove(x, y));
+                    }
+                    Event::DropFile { filename, .. } => {
+                        event::push_event(ctx, GameEvent::FileDrop(PathBuf::from(filename)));
+                    }
+                    Event::ClipboardUpdate { .. } => {
+                        ctx.clipboard = self.get_clipboard().unwrap_or(None);
+                    }
+                    Event::ControllerDeviceAdded { which, .. } => {
                         let game_controller = &self.refs.borrow().game_controller;
 
-                        if game_controller.is_game_controller(which) {
-                            let controller = game_controller.open(which).unwrap();
+                        if let Ok(controller) = game_controller.open(which) {
                             let id = controller.instance_id();
+                            let slot = ctx.gamepad_context.next_free_slot();
 
-                            log::info!("Connected gamepad: {} (ID: {})", controller.name(), id);
+                            log::info!("Connected gamepad: {} (ID: {}, player slot: {})", controller.name(), id, slot);
 
-                            let axis_sensitivity = state.settings.get_gamepad_axis_sensitivity(which);
+                            let axis_sensitivity = state.settings.get_gamepad_axis_sensitivity(slot as u32);
                             ctx.gamepad_context.add_gamepad(SDL2Gamepad::new(controller), axis_sensitivity);
 
                             unsafe {
@@ -365,11 +563,19 @@ impl BackendEventLoop for SDL2EventLoop {
                     }
                     Event::ControllerButtonDown { which, button, .. } => {
                         if let Some(drs_button) = conv_gamepad_button(button) {
+                            event::push_event(
+                                ctx,
+                                GameEvent::ControllerButton { id: which, button: drs_button, pressed: true },
+                            );
                             ctx.gamepad_context.set_button(which, drs_button, true);
                         }
                     }
                     Event::ControllerButtonUp { which, button, .. } => {
                         if let Some(drs_button) = conv_gamepad_button(button) {
+                            event::push_event(
+                                ctx,
+                                GameEvent::ControllerButton { id: which, button: drs_button, pressed: false },
+                            );
                             ctx.gamepad_context.set_button(which, drs_button, false);
                         }
                     }
@@ -377,8 +583,13 @@ impl BackendEventLoop for SDL2EventLoop {
                 }
             }
 
+            frame = frame.wrapping_add(1);
+
             if state.shutdown {
                 log::info!("Shutting down...");
+                if let Some(recorder) = &mut self.recorder {
+                    let _ = recorder.flush();
+                }
                 break;
             }
 
@@ -407,24 +618,69 @@ impl BackendEventLoop for SDL2EventLoop {
 
                     refs.fullscreen_type = fullscreen_type;
                 }
+
+                let (always_on_top, opacity) = {
+                    let refs = self.refs.borrow();
+                    (refs.always_on_top, refs.opacity)
+                };
+
+                if state.settings.window_always_on_top != always_on_top {
+                    if let Err(err) = self.set_always_on_top(state.settings.window_always_on_top) {
+                        log::warn!("Failed to pin the window on top: {}", err);
+                    }
+                }
+
+                #[allow(clippy::float_cmp)]
+                if state.settings.window_opacity.clamp(0.0, 1.0) != opacity {
+                    let _ = self.set_window_opacity(state.settings.window_opacity);
+                }
             }
 
-            game.update(ctx).unwrap();
+            if let Some((x, y)) = ctx.mouse_warp.take() {
+                let _ = self.warp_mouse(x, y);
+            }
+
+            let mouse = self.refs.borrow().window.window().subsystem().sdl().mouse();
+            if ctx.relative_mouse_mode != mouse.relative_mouse_mode() {
+                let _ = self.set_relative_mouse_mode(ctx.relative_mouse_mode);
+            }
+
+            if let Some(text) = ctx.clipboard_request.take() {
+                if let Err(err) = self.set_clipboard(&text) {
+                    log::warn!("Failed to set clipboard contents: {}", err);
+                }
+            }
+
+            if let Err(err) = game.update(ctx) {
+                self.report_fatal_error(&err);
+                break;
+            }
 
             if let Some(_) = &state.next_scene {
-                game.scene = mem::take(&mut state.next_scene);
-                game.scene.as_mut().unwrap().init(state, ctx).unwrap();
+                game.set_scene(state.next_scene.take());
+                if let Err(err) = game.scene.as_mut().unwrap().init(state, ctx) {
+                    self.report_fatal_error(&err);
+                    break;
+                }
                 game.loops = 0;
                 state.frame_time = 0.0;
             }
 
+            if ctx.window_minimized {
+                std::thread::sleep(Duration::from_millis(5));
+                continue;
+            }
+
             imgui_sdl2.prepare_frame(
                 imgui.io_mut(),
                 self.refs.deref().borrow().window.window(),
                 &self.event_pump.mouse_state(),
             );
 
-            game.draw(ctx).unwrap();
+            if let Err(err) = game.draw(ctx) {
+                self.report_fatal_error(&err);
+                break;
+            }
         }
     }
 
@@ -494,6 +750,76 @@ impl BackendEventLoop for SDL2EventLoop {
         SDL2Renderer::new(self.refs.clone())
     }
 
+    fn display_refresh_rate(&self) -> Option<u32> {
+        let refs = self.refs.borrow();
+        let index = refs.window.window().display_index().ok()?;
+        let mode = refs.video.current_display_mode(index).ok()?;
+
+        // SDL reports 0 when the rate is unspecified
+        if mode.refresh_rate > 0 {
+            Some(mode.refresh_rate as u32)
+        } else {
+            None
+        }
+    }
+
+    fn set_always_on_top(&self, always_on_top: bool) -> GameResult {
+        let mut refs = self.refs.borrow_mut();
+        refs.always_on_top = always_on_top;
+
+        let value = if always_on_top { sdl2::sys::SDL_bool::SDL_TRUE } else { sdl2::sys::SDL_bool::SDL_FALSE };
+        unsafe {
+            sdl2::sys::SDL_SetWindowAlwaysOnTop(refs.window.window().raw(), value);
+        }
+
+        Ok(())
+    }
+
+    fn warp_mouse(&self, x: i32, y: i32) -> GameResult {
+        let refs = self.refs.borrow();
+        let window = refs.window.window();
+        window.subsystem().sdl().mouse().warp_mouse_in_window(window, x, y);
+
+        Ok(())
+    }
+
+    fn set_relative_mouse_mode(&self, enabled: bool) -> GameResult {
+        let refs = self.refs.borrow();
+        refs.window.window().subsystem().sdl().mouse().set_relative_mouse_mode(enabled);
+
+        Ok(())
+    }
+
+    fn get_clipboard(&self) -> GameResult<Option<String>> {
+        let refs = self.refs.borrow();
+        let clipboard = refs.window.window().subsystem().clipboard();
+
+        if !clipboard.has_clipboard_text() {
+            return Ok(None);
+        }
+
+        let text = clipboard.clipboard_text().map_err(GameError::WindowError)?;
+        Ok(if text.is_empty() { None } else { Some(text) })
+    }
+
+    fn set_clipboard(&self, text: &str) -> GameResult {
+        let refs = self.refs.borrow();
+        refs.window.window().subsystem().clipboard().set_clipboard_text(text).map_err(GameError::WindowError)
+    }
+
+    fn set_window_opacity(&self, opacity: f32) -> GameResult {
+        let mut refs = self.refs.borrow_mut();
+        let opacity = opacity.clamp(0.0, 1.0);
+        refs.opacity = opacity;
+
+        // not every platform can do this (Wayland, most of mobile), it's not worth failing over
+        if let Err(err) = refs.window.window_mut().set_opacity(opacity) {
+            log::warn!("Window opacity is not supported here: {}", err);
+        }
+
+        Ok(())
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -544,6 +870,11 @@ struct SDL2Renderer {
     imgui: Rc<RefCell<imgui::Context>>,
     #[allow(unused)] // the rendering pipeline uses pointers to SDL_Texture, and we manually manage the lifetimes
     imgui_font_tex: SDL2Texture,
+    /// Only lets axis-aligned rectangles through when rendering imgui, for auditing which parts of the UI
+    /// still need arbitrary geometry. Enabled with `CAVESTORY_STRICT_RECT_UI=1`.
+    strict_rect_ui: bool,
+    strict_rect_ui_warned: bool,
+    color_lut_warned: bool,
 }
 
 impl SDL2Renderer {
@@ -587,23 +918,106 @@ impl SDL2Renderer {
                 width: font_tex.width as u16,
                 height: font_tex.height as u16,
                 commands: vec![],
+                z_order: vec![],
+                sort_by_z: false,
+                pixels: Vec::new(),
+                hsv_variants: Vec::new(),
+                hsv_fallback_warned: false,
+                current_blend: Some(sdl2::render::BlendMode::Blend),
             }
         };
         imgui.fonts().tex_id = TextureId::new(imgui_font_tex.texture.as_ref().unwrap().raw() as usize);
 
+        let strict_rect_ui = std::env::var("CAVESTORY_STRICT_RECT_UI").map_or(false, |v| v == "1");
+
         Ok(Box::new(SDL2Renderer {
             refs,
             imgui: Rc::new(RefCell::new(imgui)),
             imgui_font_tex,
+            strict_rect_ui,
+            strict_rect_ui_warned: false,
+            color_lut_warned: false,
+        }))
+    }
+
+    fn create_static_texture(
+        &mut self,
+        width: u16,
+        height: u16,
+        data: &[u8],
+        keep_pixels: bool,
+    ) -> GameResult<Box<dyn BackendTexture>> {
+        let mut refs = self.refs.borrow_mut();
+        let format = refs.texture_format;
+        let [r, g, b, a] = format.channel_offsets();
+
+        let mut texture = refs
+            .window
+            .texture_creator()
+            .create_texture_streaming(to_sdl_pixel_format(format), width as u32, height as u32)
+            .map_err(|e| GameError::RenderError(e.to_string()))?;
+
+        texture.set_blend_mode(sdl2::render::BlendMode::Blend);
+        if format == TextureFormat::Rgba32 {
+            // the source is tightly packed RGBA already, SDL can copy it as is
+            let len = width as usize * height as usize * 4;
+            texture
+                .update(None, &data[..len], width as usize * 4)
+                .map_err(|e| GameError::RenderError(e.to_string()))?;
+        } else {
+            texture
+                .with_lock(None, |buffer: &mut [u8], pitch: usize| {
+                    for y in 0..(height as usize) {
+                        for x in 0..(width as usize) {
+                            let offset = y * pitch + x * 4;
+                            let data_offset = (y * width as usize + x) * 4;
+
+                            buffer[offset + r] = data[data_offset];
+                            buffer[offset + g] = data[data_offset + 1];
+                            buffer[offset + b] = data[data_offset + 2];
+                            buffer[offset + a] = data[data_offset + 3];
+                        }
+                    }
+                })
+                .map_err(|e| GameError::RenderError(e.to_string()))?;
+        }
+
+        Ok(Box::new(SDL2Texture {
+            refs: self.refs.clone(),
+            texture: Some(texture),
+            width,
+            height,
+            commands: vec![],
+            z_order: vec![],
+            sort_by_z: false,
+            pixels: if keep_pixels { data[..(width as usize * height as usize * 4)].to_vec() } else { Vec::new() },
+            hsv_variants: Vec::new(),
+            hsv_fallback_warned: false,
+            current_blend: Some(sdl2::render::BlendMode::Blend),
         }))
     }
 }
 
+/// Colors are passed to SDL as raw `sRGB` bytes, SDL's renderer has no way to blend in linear space.
 fn to_sdl(color: Color) -> pixels::Color {
     let (r, g, b, a) = color.to_rgba();
     pixels::Color::RGBA(r, g, b, a)
 }
 
+/// Multiplies two colors component-wise, alpha included.
+fn modulate(color: Color, tint: Color) -> Color {
+    Color::new(color.r * tint.r, color.g * tint.g, color.b * tint.b, color.a * tint.a)
+}
+
+fn to_sdl_pixel_format(format: TextureFormat) -> PixelFormatEnum {
+    match format {
+        TextureFormat::Rgba32 => PixelFormatEnum::RGBA32,
+        TextureFormat::Bgra32 => PixelFormatEnum::BGRA32,
+        TextureFormat::Argb32 => PixelFormatEnum::ARGB32,
+        TextureFormat::Abgr32 => PixelFormatEnum::ABGR32,
+    }
+}
+
 unsafe fn set_raw_target(
     renderer: *mut sdl2::sys::SDL_Renderer,
     raw_texture: *mut sdl2::sys::SDL_Texture,
@@ -615,6 +1029,37 @@ unsafe fn set_raw_target(
     }
 }
 
+/// Checks whether every pair of triangles in the index list forms an axis-aligned rectangle,
+/// which is how imgui emits rects, images and glyphs.
+fn is_rect_geometry(vertices: &[DrawVert], indices: &[DrawIdx]) -> bool {
+    if indices.len() % 6 != 0 {
+        return false;
+    }
+
+    indices.chunks_exact(6).all(|quad| {
+        let mut xs = [0.0f32; 6];
+        let mut ys = [0.0f32; 6];
+
+        for (i, &idx) in quad.iter().enumerate() {
+            match vertices.get(idx as usize) {
+                Some(vert) => {
+                    xs[i] = vert.pos[0];
+                    ys[i] = vert.pos[1];
+                }
+                None => return false,
+            }
+        }
+
+        let distinct = |values: &[f32; 6]| {
+            let first = values[0];
+            let other = values.iter().copied().find(|&v| v != first);
+            other.map_or(false, |other| values.iter().all(|&v| v == first || v == other))
+        };
+
+        distinct(&xs) && distinct(&ys)
+    })
+}
+
 fn min3(x: f32, y: f32, z: f32) -> f32 {
     if x < y && x < z {
         x
@@ -654,6 +1099,9 @@ impl BackendRenderer for SDL2Renderer {
         let canvas = refs.window.canvas();
 
         canvas.present();
+        refs.global_tint = Color::new(1.0, 1.0, 1.0, 1.0);
+        refs.last_draw_calls = refs.draw_calls;
+        refs.draw_calls = 0;
 
         Ok(())
     }
@@ -676,36 +1124,95 @@ impl BackendRenderer for SDL2Renderer {
             .create_texture_target(PixelFormatEnum::RGBA32, width as u32, height as u32)
             .map_err(|e| GameError::RenderError(e.to_string()))?;
 
-        Ok(Box::new(SDL2Texture { refs: self.refs.clone(), texture: Some(texture), width, height, commands: vec![] }))
+        Ok(Box::new(SDL2Texture {
+            refs: self.refs.clone(),
+            texture: Some(texture),
+            width,
+            height,
+            commands: vec![],
+            z_order: vec![],
+            sort_by_z: false,
+            pixels: Vec::new(),
+            hsv_variants: Vec::new(),
+            hsv_fallback_warned: false,
+            current_blend: None,
+        }))
     }
 
     fn create_texture(&mut self, width: u16, height: u16, data: &[u8]) -> GameResult<Box<dyn BackendTexture>> {
+        self.create_static_texture(width, height, data, false)
+    }
+
+    fn create_texture_keep_pixels(
+        &mut self,
+        width: u16,
+        height: u16,
+        data: &[u8],
+    ) -> GameResult<Box<dyn BackendTexture>> {
+        self.create_static_texture(width, height, data, true)
+    }
+
+    #[cfg(feature = "debug-outlines")]
+    fn set_sprite_outlines(&mut self, enabled: bool) {
+        self.refs.borrow_mut().sprite_outlines = enabled;
+    }
+
+    fn set_global_tint(&mut self, color: Color) -> GameResult {
+        self.refs.borrow_mut().global_tint = color;
+        Ok(())
+    }
+
+    fn set_zoom(&mut self, factor: f32, center: (f32, f32)) -> GameResult {
+        self.refs.borrow_mut().zoom = (factor, center);
+        Ok(())
+    }
+
+    fn supported_texture_formats(&self) -> Vec<TextureFormat> {
         let mut refs = self.refs.borrow_mut();
+        let native = refs.window.canvas().info().texture_formats;
 
-        let mut texture = refs
-            .window
-            .texture_creator()
-            .create_texture_streaming(PixelFormatEnum::RGBA32, width as u32, height as u32)
-            .map_err(|e| GameError::RenderError(e.to_string()))?;
+        // RGBA32 is always accepted, SDL converts it if the driver can't use it directly.
+        let mut formats = vec![TextureFormat::Rgba32];
+        for format in [TextureFormat::Bgra32, TextureFormat::Argb32, TextureFormat::Abgr32] {
+            if native.contains(&to_sdl_pixel_format(format)) {
+                formats.push(format);
+            }
+        }
 
-        texture.set_blend_mode(sdl2::render::BlendMode::Blend);
-        texture
-            .with_lock(None, |buffer: &mut [u8], pitch: usize| {
-                for y in 0..(height as usize) {
-                    for x in 0..(width as usize) {
-                        let offset = y * pitch + x * 4;
-                        let data_offset = (y * width as usize + x) * 4;
-
-                        buffer[offset] = data[data_offset];
-                        buffer[offset + 1] = data[data_offset + 1];
-                        buffer[offset + 2] = data[data_offset + 2];
-                        buffer[offset + 3] = data[data_offset + 3];
-                    }
-                }
-            })
-            .map_err(|e| GameError::RenderError(e.to_string()))?;
+        formats
+    }
+
+    fn set_texture_format(&mut self, format: TextureFormat) -> GameResult {
+        if !self.supported_texture_formats().contains(&format) {
+            let msg = format!("Texture format {:?} is not supported by the renderer.", format);
+            return Err(GameError::RenderError(msg));
+        }
+
+        self.refs.borrow_mut().texture_format = format;
+        Ok(())
+    }
+
+    fn set_window_size(&mut self, width: u32, height: u32) -> GameResult<(f32, f32)> {
+        let mut refs = self.refs.borrow_mut();
+        let window = refs.window.window_mut();
+
+        let (min_width, min_height) = window.minimum_size();
+        window
+            .set_size(width.max(min_width).max(1), height.max(min_height).max(1))
+            .map_err(|e| GameError::WindowError(e.to_string()))?;
+        let (width, height) = window.size();
 
-        Ok(Box::new(SDL2Texture { refs: self.refs.clone(), texture: Some(texture), width, height, commands: vec![] }))
+        Ok((width.max(1) as f32, height.max(1) as f32))
+    }
+
+    fn set_borderless(&mut self, borderless: bool) -> GameResult<(f32, f32)> {
+        let mut refs = self.refs.borrow_mut();
+        let window = refs.window.window_mut();
+
+        window.set_bordered(!borderless);
+        let (width, height) = window.size();
+
+        Ok((width.max(1) as f32, height.max(1) as f32))
     }
 
     fn set_blend_mode(&mut self, blend: BlendMode) -> GameResult {
@@ -747,8 +1254,44 @@ impl BackendRenderer for SDL2Renderer {
         Ok(())
     }
 
+    fn warm_texture(&mut self, texture: &Box<dyn BackendTexture>) -> GameResult {
+        let sdl2_texture = texture
+            .as_any()
+            .downcast_ref::<SDL2Texture>()
+            .ok_or(GameError::RenderError("This texture was not created by SDL2 backend.".to_string()))?;
+
+        let texture = match &sdl2_texture.texture {
+            Some(texture) => texture,
+            None => return Ok(()),
+        };
+
+        let mut refs = self.refs.borrow_mut();
+        let target = refs
+            .window
+            .texture_creator()
+            .create_texture_target(PixelFormatEnum::RGBA32, 1, 1)
+            .map_err(|e| GameError::RenderError(e.to_string()))?;
+
+        let state = refs.current_state();
+        let canvas = refs.window.canvas();
+        let pixel = sdl2::rect::Rect::new(0, 0, 1, 1);
+
+        unsafe {
+            set_raw_target(canvas.raw(), target.raw())?;
+        }
+        let result = canvas.copy(texture, Some(pixel), Some(pixel)).map_err(|e| GameError::RenderError(e.to_string()));
+        refs.restore_state(state)?;
+
+        unsafe {
+            target.destroy();
+        }
+
+        result
+    }
+
     fn draw_rect(&mut self, rect: Rect<isize>, color: Color) -> GameResult<()> {
         let mut refs = self.refs.borrow_mut();
+        refs.draw_calls += 1;
         let blend = refs.blend_mode;
         let canvas = refs.window.canvas();
 
@@ -770,6 +1313,7 @@ impl BackendRenderer for SDL2Renderer {
 
     fn draw_outline_rect(&mut self, rect: Rect<isize>, line_width: usize, color: Color) -> GameResult<()> {
         let mut refs = self.refs.borrow_mut();
+        refs.draw_calls += 1;
         let blend = refs.blend_mode;
         let canvas = refs.window.canvas();
 
@@ -833,6 +1377,26 @@ impl BackendRenderer for SDL2Renderer {
         Ok(())
     }
 
+    fn set_color_lut(&mut self, lut: Option<Box<dyn BackendTexture>>) -> GameResult {
+        // the canvas renderer has no way of remapping colors per pixel, so the LUT is ignored
+        if lut.is_some() && !self.color_lut_warned {
+            log::warn!("Color LUTs are not supported by the SDL2 canvas renderer, ignoring.");
+            self.color_lut_warned = true;
+        }
+
+        Ok(())
+    }
+
+    fn push_state(&mut self) -> GameResult {
+        self.refs.borrow_mut().push_state();
+
+        Ok(())
+    }
+
+    fn pop_state(&mut self) -> GameResult {
+        self.refs.borrow_mut().pop_state()
+    }
+
     fn imgui(&self) -> GameResult<&mut imgui::Context> {
         unsafe { Ok(&mut *self.imgui.as_ptr()) }
     }
@@ -871,6 +1435,23 @@ impl BackendRenderer for SDL2Renderer {
                         let vtx_buffer = draw_list.vtx_buffer();
                         let idx_buffer = draw_list.idx_buffer();
 
+                        // The rect check only runs for the audit mode, normally each command goes straight to
+                        // SDL_RenderGeometryRaw in one call, so there's no per-quad work to skip here.
+                        if self.strict_rect_ui
+                            && !is_rect_geometry(
+                                &vtx_buffer[cmd_params.vtx_offset..],
+                                &idx_buffer[cmd_params.idx_offset..cmd_params.idx_offset + count],
+                            )
+                        {
+                            if !self.strict_rect_ui_warned {
+                                log::warn!("Strict rect UI: skipping non-rectangle imgui geometry ({} indices).", count);
+                                self.strict_rect_ui_warned = true;
+                            }
+
+                            canvas.set_clip_rect(None);
+                            continue;
+                        }
+
                         let tex_ptr = cmd_params.texture_id.id() as *mut sdl2::sys::SDL_Texture;
 
                         unsafe {
@@ -903,10 +1484,37 @@ impl BackendRenderer for SDL2Renderer {
         Ok(())
     }
 
+    fn read_screen_pixels(&mut self) -> GameResult<(u32, u32, Vec<u8>)> {
+        let mut refs = self.refs.borrow_mut();
+        let canvas = refs.window.canvas();
+
+        let (width, height) = canvas.output_size().map_err(|e| GameError::RenderError(e.to_string()))?;
+        let pixels =
+            canvas.read_pixels(None, PixelFormatEnum::RGBA32).map_err(|e| GameError::RenderError(e.to_string()))?;
+
+        Ok((width, height, pixels))
+    }
+
     fn supports_vertex_draw(&self) -> bool {
         true
     }
 
+    fn debug_info(&mut self) -> Option<RendererDebugInfo> {
+        let mut refs = self.refs.borrow_mut();
+        let state = refs.current_state();
+
+        Some(RendererDebugInfo {
+            blend_mode: format!("{:?}", state.blend_mode),
+            clip_rect: state
+                .clip_rect
+                .map(|r| Rect::new(r.x() as isize, r.y() as isize, r.right() as isize, r.bottom() as isize)),
+            render_target: if state.target.is_null() { None } else { Some(state.target as usize) },
+            viewport_offset: (state.viewport.x() as isize, state.viewport.y() as isize),
+            state_depth: refs.state_stack.len(),
+            draw_calls: refs.last_draw_calls,
+        })
+    }
+
     fn draw_triangle_list(
         &mut self,
         vertices: &[VertexData],
@@ -914,6 +1522,7 @@ impl BackendRenderer for SDL2Renderer {
         shader: BackendShader,
     ) -> GameResult<()> {
         let mut refs = self.refs.borrow_mut();
+        refs.draw_calls += 1;
         if shader == BackendShader::Fill {
             texture = None;
         } else if let BackendShader::WaterFill(..) = shader {
@@ -932,6 +1541,9 @@ impl BackendRenderer for SDL2Renderer {
             null_mut::<sdl2_sys::SDL_Texture>()
         };
 
+        let blend = refs.blend_mode;
+        refs.window.canvas().set_blend_mode(blend);
+
         unsafe {
             // potential danger: we assume that the layout of VertexData is the same as SDL_Vertex
             sdl2_sys::SDL_RenderGeometry(
@@ -958,6 +1570,158 @@ struct SDL2Texture {
     width: u16,
     height: u16,
     commands: Vec<SpriteBatchCommand>,
+    /// Draw-order key for each entry of `commands`.
+    z_order: Vec<i16>,
+    sort_by_z: bool,
+    /// CPU-side copy of the texture data, only kept for textures made with `create_texture_keep_pixels`.
+    /// Used to answer `alpha_at`, for HSV variants and by `into_render_target`.
+    pixels: Vec<u8>,
+    /// SDL can't hue shift at blit time, so `DrawRectHsv` is served from recolored copies of the whole texture.
+    /// Parameters are quantized (see `hsv_key`) and at most `MAX_HSV_VARIANTS` copies, each costing as much
+    /// memory as the texture itself, are kept. Ordered from least to most recently used.
+    hsv_variants: Vec<((u8, u8, u8), Texture)>,
+    /// Set once the missing pixel copy for `DrawRectHsv` has been reported.
+    hsv_fallback_warned: bool,
+    /// Blend mode last applied to `texture`, so it's only set again when it changes. `None` if it isn't known.
+    current_blend: Option<sdl2::render::BlendMode>,
+}
+
+const HSV_HUE_STEPS: f32 = 24.0;
+const HSV_MUL_STEPS: f32 = 8.0;
+/// Least recently used variants above this count are destroyed, unless the batch being drawn needs them.
+const MAX_HSV_VARIANTS: usize = 4;
+
+/// Quantizes HSV draw parameters: hue to 15 degree steps, saturation and value multipliers to 1/8 steps (up to 2x).
+fn hsv_key(hue_shift: f32, sat_mul: f32, val_mul: f32) -> (u8, u8, u8) {
+    let hue = (hue_shift.rem_euclid(360.0) / 360.0 * HSV_HUE_STEPS).round() as u8 % HSV_HUE_STEPS as u8;
+    let sat = (sat_mul.clamp(0.0, 2.0) * HSV_MUL_STEPS).round() as u8;
+    let val = (val_mul.clamp(0.0, 2.0) * HSV_MUL_STEPS).round() as u8;
+
+    (hue, sat, val)
+}
+
+fn shift_hsv(r: u8, g: u8, b: u8, hue_shift: f32, sat_mul: f32, val_mul: f32) -> (u8, u8, u8) {
+    let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let mut hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let sat = if max == 0.0 { 0.0 } else { (delta / max * sat_mul).min(1.0) };
+    let val = (max * val_mul).min(1.0);
+
+    hue = (hue + hue_shift).rem_euclid(360.0);
+
+    let c = val * sat;
+    let x = c * (1.0 - ((hue / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = val - c;
+    let (r, g, b) = match (hue / 60.0) as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+
+    (((r + m) * 255.0).round() as u8, ((g + m) * 255.0).round() as u8, ((b + m) * 255.0).round() as u8)
+}
+
+impl SDL2Texture {
+    fn ensure_hsv_variants(&mut self) -> GameResult {
+        // without the original data `DrawRectHsv` falls back to drawing the texture unshifted
+        if self.pixels.is_empty() {
+            if !self.hsv_fallback_warned
+                && self.commands.iter().any(|c| matches!(c, SpriteBatchCommand::DrawRectHsv(..)))
+            {
+                log::warn!(
+                    "HSV draw on a texture without a pixel copy, drawing it unshifted. \
+                     Load it through TextureSet::set_keep_pixels to enable recoloring."
+                );
+                self.hsv_fallback_warned = true;
+            }
+
+            return Ok(());
+        }
+
+        let mut wanted = Vec::new();
+        for command in &self.commands {
+            if let SpriteBatchCommand::DrawRectHsv(_, _, hue_shift, sat_mul, val_mul) = command {
+                let key = hsv_key(*hue_shift, *sat_mul, *val_mul);
+                if !wanted.contains(&key) {
+                    wanted.push(key);
+                }
+            }
+        }
+
+        for &key in &wanted {
+            if let Some(pos) = self.hsv_variants.iter().position(|(k, _)| *k == key) {
+                let variant = self.hsv_variants.remove(pos);
+                self.hsv_variants.push(variant);
+                continue;
+            }
+
+            let hue_shift = key.0 as f32 * 360.0 / HSV_HUE_STEPS;
+            let sat_mul = key.1 as f32 / HSV_MUL_STEPS;
+            let val_mul = key.2 as f32 / HSV_MUL_STEPS;
+            let (width, height) = (self.width as usize, self.height as usize);
+            let pixels = &self.pixels;
+
+            let mut refs = self.refs.borrow_mut();
+            let format = refs.texture_format;
+            let [r_off, g_off, b_off, a_off] = format.channel_offsets();
+            let mut texture = refs
+                .window
+                .texture_creator()
+                .create_texture_streaming(to_sdl_pixel_format(format), width as u32, height as u32)
+                .map_err(|e| GameError::RenderError(e.to_string()))?;
+
+            texture
+                .with_lock(None, |buffer: &mut [u8], pitch: usize| {
+                    for y in 0..height {
+                        for x in 0..width {
+                            let offset = y * pitch + x * 4;
+                            let data_offset = (y * width + x) * 4;
+
+                            let (r, g, b) = shift_hsv(
+                                pixels[data_offset],
+                                pixels[data_offset + 1],
+                                pixels[data_offset + 2],
+                                hue_shift,
+                                sat_mul,
+                                val_mul,
+                            );
+
+                            buffer[offset + r_off] = r;
+                            buffer[offset + g_off] = g;
+                            buffer[offset + b_off] = b;
+                            buffer[offset + a_off] = pixels[data_offset + 3];
+                        }
+                    }
+                })
+                .map_err(|e| GameError::RenderError(e.to_string()))?;
+
+            self.hsv_variants.push((key, texture));
+        }
+
+        // everything this batch uses was just moved to the back
+        while self.hsv_variants.len() > MAX_HSV_VARIANTS.max(wanted.len()) {
+            let (_, texture) = self.hsv_variants.remove(0);
+            unsafe {
+                texture.destroy();
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl BackendTexture for SDL2Texture {
@@ -966,26 +1730,63 @@ impl BackendTexture for SDL2Texture {
     }
 
     fn add(&mut self, command: SpriteBatchCommand) {
+        self.add_z(0, command);
+    }
+
+    fn add_z(&mut self, z: i16, command: SpriteBatchCommand) {
         self.commands.push(command);
+        self.z_order.push(z);
+    }
+
+    fn set_sort_by_z(&mut self, enabled: bool) {
+        self.sort_by_z = enabled;
     }
 
     fn clear(&mut self) {
         self.commands.clear();
+        self.z_order.clear();
     }
 
     fn draw(&mut self) -> GameResult {
+        self.ensure_hsv_variants()?;
+
+        if self.sort_by_z && self.z_order.windows(2).any(|w| w[0] > w[1]) {
+            let mut sorted: Vec<_> = self.z_order.drain(..).zip(self.commands.drain(..)).collect();
+            // sort_by_key is stable, equal z keeps insertion order
+            sorted.sort_by_key(|(z, _)| *z);
+            (self.z_order, self.commands) = sorted.into_iter().unzip();
+        }
+
         match &mut self.texture {
             None => Ok(()),
             Some(texture) => {
                 let mut refs = self.refs.borrow_mut();
                 let blend = refs.blend_mode;
+                let on_screen = refs.current_state().target.is_null();
+                let tint = if on_screen { refs.global_tint } else { Color::new(1.0, 1.0, 1.0, 1.0) };
+                let (tint_r, tint_g, tint_b, tint_a) = tint.to_rgba();
+                let (zoom, zoom_center) = if on_screen { refs.zoom } else { (1.0, (0.0, 0.0)) };
+                refs.draw_calls += self.commands.len();
                 let canvas = refs.window.canvas();
+
+                if self.current_blend != Some(blend) {
+                    texture.set_blend_mode(blend);
+                    self.current_blend = Some(blend);
+                }
+
                 for command in &self.commands {
+                    let zoomed;
+                    let command = if zoom != 1.0 {
+                        zoomed = command.zoomed(zoom, zoom_center);
+                        &zoomed
+                    } else {
+                        command
+                    };
+
                     match command {
                         SpriteBatchCommand::DrawRect(src, dest) => {
-                            texture.set_color_mod(255, 255, 255);
-                            texture.set_alpha_mod(255);
-                            texture.set_blend_mode(blend);
+                            texture.set_color_mod(tint_r, tint_g, tint_b);
+                            texture.set_alpha_mod(tint_a);
 
                             canvas
                                 .copy(
@@ -1006,10 +1807,9 @@ impl BackendTexture for SDL2Texture {
                                 .map_err(|e| GameError::RenderError(e.to_string()))?;
                         }
                         SpriteBatchCommand::DrawRectTinted(src, dest, color) => {
-                            let (r, g, b, a) = color.to_rgba();
+                            let (r, g, b, a) = modulate(*color, tint).to_rgba();
                             texture.set_color_mod(r, g, b);
                             texture.set_alpha_mod(a);
-                            texture.set_blend_mode(blend);
 
                             canvas
                                 .copy(
@@ -1030,9 +1830,8 @@ impl BackendTexture for SDL2Texture {
                                 .map_err(|e| GameError::RenderError(e.to_string()))?;
                         }
                         SpriteBatchCommand::DrawRectFlip(src, dest, flip_x, flip_y) => {
-                            texture.set_color_mod(255, 255, 255);
-                            texture.set_alpha_mod(255);
-                            texture.set_blend_mode(blend);
+                            texture.set_color_mod(tint_r, tint_g, tint_b);
+                            texture.set_alpha_mod(tint_a);
 
                             canvas
                                 .copy_ex(
@@ -1057,10 +1856,9 @@ impl BackendTexture for SDL2Texture {
                                 .map_err(|e| GameError::RenderError(e.to_string()))?;
                         }
                         SpriteBatchCommand::DrawRectFlipTinted(src, dest, flip_x, flip_y, color) => {
-                            let (r, g, b, a) = color.to_rgba();
+                            let (r, g, b, a) = modulate(*color, tint).to_rgba();
                             texture.set_color_mod(r, g, b);
                             texture.set_alpha_mod(a);
-                            texture.set_blend_mode(blend);
 
                             canvas
                                 .copy_ex(
@@ -1084,14 +1882,197 @@ impl BackendTexture for SDL2Texture {
                                 )
                                 .map_err(|e| GameError::RenderError(e.to_string()))?;
                         }
+                        SpriteBatchCommand::DrawRectF(src, dest) => {
+                            texture.set_color_mod(tint_r, tint_g, tint_b);
+                            texture.set_alpha_mod(tint_a);
+
+                            canvas
+                                .copy_f(
+                                    texture,
+                                    Some(sdl2::rect::Rect::new(
+                                        src.left.round() as i32,
+                                        src.top.round() as i32,
+                                        src.width().round() as u32,
+                                        src.height().round() as u32,
+                                    )),
+                                    Some(sdl2::rect::FRect::new(dest.left, dest.top, dest.width(), dest.height())),
+                                )
+                                .map_err(|e| GameError::RenderError(e.to_string()))?;
+                        }
+                        SpriteBatchCommand::DrawRectEx(src, dest, angle, center, flip_x, flip_y) => {
+                            texture.set_color_mod(tint_r, tint_g, tint_b);
+                            texture.set_alpha_mod(tint_a);
+
+                            canvas
+                                .copy_ex(
+                                    texture,
+                                    Some(sdl2::rect::Rect::new(
+                                        src.left.round() as i32,
+                                        src.top.round() as i32,
+                                        src.width().round() as u32,
+                                        src.height().round() as u32,
+                                    )),
+                                    Some(sdl2::rect::Rect::new(
+                                        dest.left.round() as i32,
+                                        dest.top.round() as i32,
+                                        dest.width().round() as u32,
+                                        dest.height().round() as u32,
+                                    )),
+                                    *angle as f64,
+                                    center.map(|(x, y)| sdl2::rect::Point::new(x.round() as i32, y.round() as i32)),
+                                    *flip_x,
+                                    *flip_y,
+                                )
+                                .map_err(|e| GameError::RenderError(e.to_string()))?;
+                        }
+                        SpriteBatchCommand::DrawQuad(src, corners) => {
+                            texture.set_color_mod(255, 255, 255);
+                            texture.set_alpha_mod(255);
+
+                            let (tex_scale_x, tex_scale_y) = (1.0 / self.width as f32, 1.0 / self.height as f32);
+                            let uv = [
+                                (src.left * tex_scale_x, src.top * tex_scale_y),
+                                (src.right * tex_scale_x, src.top * tex_scale_y),
+                                (src.right * tex_scale_x, src.bottom * tex_scale_y),
+                                (src.left * tex_scale_x, src.bottom * tex_scale_y),
+                            ];
+                            let vertices = [0, 1, 2, 0, 2, 3].map(|i| VertexData {
+                                position: corners[i],
+                                color: (tint_r, tint_g, tint_b, tint_a),
+                                uv: uv[i],
+                            });
+
+                            let result = unsafe {
+                                // same layout assumption as in draw_triangle_list
+                                sdl2_sys::SDL_RenderGeometry(
+                                    canvas.raw(),
+                                    texture.raw(),
+                                    vertices.as_ptr() as *const sdl2_sys::SDL_Vertex,
+                                    vertices.len() as i32,
+                                    null(),
+                                    0,
+                                )
+                            };
+
+                            if result != 0 {
+                                return Err(GameError::RenderError(sdl2::get_error()));
+                            }
+                        }
+                        SpriteBatchCommand::DrawRectHsv(src, dest, hue_shift, sat_mul, val_mul) => {
+                            let key = hsv_key(*hue_shift, *sat_mul, *val_mul);
+                            let texture = match self.hsv_variants.iter_mut().find(|(k, _)| *k == key) {
+                                Some((_, variant)) => {
+                                    variant.set_blend_mode(blend);
+                                    variant
+                                }
+                                None => &mut *texture,
+                            };
+                            texture.set_color_mod(tint_r, tint_g, tint_b);
+                            texture.set_alpha_mod(tint_a);
+
+                            canvas
+                                .copy(
+                                    texture,
+                                    Some(sdl2::rect::Rect::new(
+                                        src.left.round() as i32,
+                                        src.top.round() as i32,
+                                        src.width().round() as u32,
+                                        src.height().round() as u32,
+                                    )),
+                                    Some(sdl2::rect::Rect::new(
+                                        dest.left.round() as i32,
+                                        dest.top.round() as i32,
+                                        dest.width().round() as u32,
+                                        dest.height().round() as u32,
+                                    )),
+                                )
+                                .map_err(|e| GameError::RenderError(e.to_string()))?;
+                        }
                     }
                 }
 
+                #[cfg(feature = "debug-outlines")]
+                if refs.sprite_outlines {
+                    let canvas = refs.window.canvas();
+                    let prev_blend = canvas.blend_mode();
+                    canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+                    canvas.set_draw_color(sdl2::pixels::Color::RGBA(255, 0, 255, 128));
+
+                    for command in &self.commands {
+                        let dest = command.dest_bounds();
+                        canvas
+                            .draw_rect(sdl2::rect::Rect::new(
+                                dest.left.round() as i32,
+                                dest.top.round() as i32,
+                                dest.width().round().max(1.0) as u32,
+                                dest.height().round().max(1.0) as u32,
+                            ))
+                            .map_err(|e| GameError::RenderError(e.to_string()))?;
+                    }
+
+                    canvas.set_blend_mode(prev_blend);
+                }
+
                 Ok(())
             }
         }
     }
 
+    fn alpha_at(&self, x: u16, y: u16) -> Option<u8> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.pixels.get((y as usize * self.width as usize + x as usize) * 4 + 3).copied()
+    }
+
+    fn into_render_target(&mut self) -> GameResult {
+        match &self.texture {
+            Some(texture) if texture.query().access == TextureAccess::Target => return Ok(()),
+            Some(_) => {}
+            None => return Err(GameError::RenderError("Texture has already been destroyed.".to_string())),
+        }
+
+        if self.pixels.is_empty() {
+            return Err(GameError::RenderError(
+                "Texture data wasn't kept for reading back, see TextureSet::set_keep_pixels.".to_string(),
+            ));
+        }
+
+        let mut target = self
+            .refs
+            .borrow_mut()
+            .window
+            .texture_creator()
+            .create_texture_target(PixelFormatEnum::RGBA32, self.width as u32, self.height as u32)
+            .map_err(|e| GameError::RenderError(e.to_string()))?;
+
+        target.set_blend_mode(sdl2::render::BlendMode::Blend);
+        if let Err(e) = target.update(None, &self.pixels, self.width as usize * 4) {
+            unsafe {
+                target.destroy();
+            }
+            return Err(GameError::RenderError(e.to_string()));
+        }
+
+        if let Some(texture) = self.texture.replace(target) {
+            unsafe {
+                texture.destroy();
+            }
+        }
+        self.current_blend = Some(sdl2::render::BlendMode::Blend);
+
+        // both are derived from the original data, which rendering is going to change
+        self.pixels = Vec::new();
+        for (_, texture) in self.hsv_variants.drain(..) {
+            unsafe {
+                texture.destroy();
+            }
+        }
+
+        Ok(())
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -1107,6 +2088,12 @@ impl Drop for SDL2Texture {
                 texture.destroy();
             }
         }
+
+        for (_, texture) in self.hsv_variants.drain(..) {
+            unsafe {
+                texture.destroy();
+            }
+        }
     }
 }
 
@@ -1465,3 +2452,52 @@ impl ImguiSdl2 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pop_state_restores_prior_state() {
+        sdl2::hint::set("SDL_VIDEODRIVER", "dummy");
+        let sdl = sdl2::init().unwrap();
+        let video = sdl.video().unwrap();
+        let game_controller = sdl.game_controller().unwrap();
+        let window = video.window("test", 320, 240).hidden().build().unwrap();
+        let canvas = window.into_canvas().software().build().unwrap();
+        let texture_creator = canvas.texture_creator();
+
+        let mut ctx = SDL2Context {
+            video,
+            window: WindowOrCanvas::Canvas(canvas, texture_creator),
+            gl_context: None,
+            blend_mode: sdl2::render::BlendMode::Blend,
+            fullscreen_type: sdl2::video::FullscreenType::Off,
+            always_on_top: false,
+            opacity: 1.0,
+            game_controller,
+            state_stack: Vec::new(),
+            texture_format: TextureFormat::Rgba32,
+            global_tint: Color::new(1.0, 1.0, 1.0, 1.0),
+            zoom: (1.0, (0.0, 0.0)),
+            draw_calls: 0,
+            last_draw_calls: 0,
+            #[cfg(feature = "debug-outlines")]
+            sprite_outlines: false,
+        };
+
+        let prior = ctx.current_state();
+        ctx.push_state();
+
+        ctx.blend_mode = sdl2::render::BlendMode::Add;
+        ctx.global_tint = Color::new(0.5, 0.5, 0.5, 1.0);
+        ctx.zoom = (2.0, (160.0, 120.0));
+        ctx.window.canvas().set_viewport(Some(sdl2::rect::Rect::new(16, 8, 100, 100)));
+        ctx.window.canvas().set_clip_rect(Some(sdl2::rect::Rect::new(4, 4, 32, 32)));
+        assert_ne!(ctx.current_state(), prior);
+
+        ctx.pop_state().unwrap();
+        assert_eq!(ctx.current_state(), prior);
+        assert!(ctx.pop_state().is_err());
+    }
+}
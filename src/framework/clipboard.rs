@@ -0,0 +1,13 @@
+use crate::framework::context::Context;
+
+/// Returns the text on the system clipboard, `None` if it's empty, doesn't hold text or the backend has no clipboard.
+pub fn get_clipboard(ctx: &Context) -> Option<String> {
+    ctx.clipboard.clone()
+}
+
+/// Puts given text on the system clipboard, eg. to share a seed or a save code. The clipboard is updated on the next
+/// iteration of the event loop, `get_clipboard` returns the new text right away.
+pub fn set_clipboard(ctx: &mut Context, text: &str) {
+    ctx.clipboard = if text.is_empty() { None } else { Some(text.to_owned()) };
+    ctx.clipboard_request = Some(text.to_owned());
+}
@@ -1,8 +1,9 @@
 use crate::framework::backend::{init_backend, BackendRenderer};
 use crate::framework::error::GameResult;
+use crate::framework::event::GameEvent;
 use crate::framework::filesystem::Filesystem;
 use crate::framework::gamepad::GamepadContext;
-use crate::framework::graphics::VSyncMode;
+use crate::framework::graphics::{LetterboxStyle, Pacing, VSyncMode};
 use crate::framework::keyboard::KeyboardContext;
 use crate::game::Game;
 
@@ -17,6 +18,23 @@ pub struct Context {
     pub(crate) screen_size: (f32, f32),
     pub(crate) screen_insets: (f32, f32, f32, f32),
     pub(crate) vsync_mode: VSyncMode,
+    pub(crate) pacing: Pacing,
+    pub(crate) letterbox: LetterboxStyle,
+    pub(crate) dirty_rect_mode: bool,
+    pub(crate) events: Vec<GameEvent>,
+    pub(crate) display_refresh_rate: Option<u32>,
+    pub(crate) window_minimized: bool,
+    /// Set by `mouse::warp_mouse`, the event loop moves the cursor on its next iteration.
+    pub(crate) mouse_warp: Option<(i32, i32)>,
+    pub(crate) relative_mouse_mode: bool,
+    /// Copy of the clipboard text, kept up to date by the event loop.
+    pub(crate) clipboard: Option<String>,
+    /// Set by `clipboard::set_clipboard`, the event loop puts it on the clipboard on its next iteration.
+    pub(crate) clipboard_request: Option<String>,
+    #[cfg(feature = "backend-sdl")]
+    pub(crate) event_observers: Vec<Box<dyn FnMut(&sdl2::event::Event)>>,
+    #[cfg(feature = "debug-outlines")]
+    pub(crate) sprite_outlines: bool,
 }
 
 impl Context {
@@ -32,6 +50,24 @@ impl Context {
             screen_size: (320.0, 240.0),
             screen_insets: (0.0, 0.0, 0.0, 0.0),
             vsync_mode: VSyncMode::Uncapped,
+            pacing: if std::env::var("CAVESTORY_HYBRID_PACING").map_or(false, |v| v == "1") {
+                Pacing::Hybrid
+            } else {
+                Pacing::SleepOnly
+            },
+            letterbox: LetterboxStyle::default(),
+            dirty_rect_mode: false,
+            events: Vec::new(),
+            display_refresh_rate: None,
+            window_minimized: false,
+            mouse_warp: None,
+            relative_mouse_mode: false,
+            clipboard: None,
+            clipboard_request: None,
+            #[cfg(feature = "backend-sdl")]
+            event_observers: Vec::new(),
+            #[cfg(feature = "debug-outlines")]
+            sprite_outlines: false,
         }
     }
 
@@ -39,9 +75,22 @@ impl Context {
         let backend = init_backend(self.headless, self.size_hint)?;
         let mut event_loop = backend.create_event_loop(self)?;
         self.renderer = Some(event_loop.new_renderer(self as *mut Context)?);
+        self.display_refresh_rate = event_loop.display_refresh_rate();
+
+        match self.display_refresh_rate {
+            Some(60) | None => {}
+            Some(rate) => log::info!("Display refresh rate is {}Hz, game logic still runs at a fixed tick rate.", rate),
+        }
 
         event_loop.run(game, self);
 
         Ok(())
     }
+
+    /// Registers a callback invoked with every SDL event before the game handles it, for platform integrations
+    /// that need to see raw events. Observers run on the game loop thread, so they must be cheap and never block.
+    #[cfg(feature = "backend-sdl")]
+    pub fn add_event_observer(&mut self, observer: Box<dyn FnMut(&sdl2::event::Event)>) {
+        self.event_observers.push(observer);
+    }
 }
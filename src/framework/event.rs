@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+
+use crate::framework::context::Context;
+use crate::framework::gamepad::Button;
+use crate::framework::keyboard::ScanCode;
+
+/// Backend-independent input and window events. Event loops queue them while polling the platform,
+/// the game drains the queue at the beginning of every update and hands them to the current scene.
+///
+/// Keyboard and gamepad contexts are still updated immediately, so code that only cares about
+/// the current state can keep polling them instead.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    KeyDown(ScanCode),
+    KeyUp(ScanCode),
+    /// Mouse position in canvas coordinates, see `SharedGameState::screen_to_canvas`.
+    MouseMove(f32, f32),
+    /// New window size.
+    Resize(f32, f32),
+    /// The window was asked to close.
+    Quit,
+    ControllerButton { id: u32, button: Button, pressed: bool },
+    FileDrop(PathBuf),
+}
+
+/// Adds an event to the queue.
+pub fn push_event(ctx: &mut Context, event: GameEvent) {
+    ctx.events.push(event);
+}
+
+/// Takes all queued events, leaving the queue empty.
+pub fn drain_events(ctx: &mut Context) -> Vec<GameEvent> {
+    std::mem::take(&mut ctx.events)
+}
@@ -0,0 +1,355 @@
+//! Recording and playback of SDL2 input events, so users can attach a reproducible trace to bug reports.
+//!
+//! The trace format is a `DRSE` magic and a version number, followed by records made of the event loop iteration
+//! the event arrived in, a tag byte and the event data. Only events the game reacts to are recorded.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
+use sdl2::controller;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Mod, Scancode};
+use sdl2::mouse::MouseState;
+
+use crate::framework::error::{GameError, GameResult};
+
+const MAGIC: [u8; 4] = *b"DRSE";
+const VERSION: u16 = 1;
+
+const TAG_QUIT: u8 = 0;
+const TAG_RESIZE: u8 = 1;
+const TAG_FOCUS_GAINED: u8 = 2;
+const TAG_FOCUS_LOST: u8 = 3;
+const TAG_KEY_DOWN: u8 = 4;
+const TAG_KEY_UP: u8 = 5;
+const TAG_MOUSE_MOTION: u8 = 6;
+const TAG_CONTROLLER_AXIS: u8 = 7;
+const TAG_CONTROLLER_BUTTON_DOWN: u8 = 8;
+const TAG_CONTROLLER_BUTTON_UP: u8 = 9;
+
+pub struct EventRecorder {
+    writer: BufWriter<File>,
+}
+
+impl EventRecorder {
+    pub fn create(path: &Path) -> GameResult<EventRecorder> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(&MAGIC)?;
+        writer.write_u16::<LE>(VERSION)?;
+
+        Ok(EventRecorder { writer })
+    }
+
+    /// Appends the event to the trace, events the game doesn't handle are skipped.
+    pub fn record(&mut self, frame: u32, event: &Event) -> GameResult {
+        let w = &mut self.writer;
+
+        match event {
+            Event::Quit { .. } => {
+                w.write_u32::<LE>(frame)?;
+                w.write_u8(TAG_QUIT)?;
+            }
+            Event::Window { win_event: WindowEvent::SizeChanged(width, height), .. } => {
+                w.write_u32::<LE>(frame)?;
+                w.write_u8(TAG_RESIZE)?;
+                w.write_i32::<LE>(*width)?;
+                w.write_i32::<LE>(*height)?;
+            }
+            Event::Window { win_event: WindowEvent::FocusGained, .. } => {
+                w.write_u32::<LE>(frame)?;
+                w.write_u8(TAG_FOCUS_GAINED)?;
+            }
+            Event::Window { win_event: WindowEvent::FocusLost, .. } => {
+                w.write_u32::<LE>(frame)?;
+                w.write_u8(TAG_FOCUS_LOST)?;
+            }
+            Event::KeyDown { scancode: Some(scancode), keymod, repeat, .. } => {
+                w.write_u32::<LE>(frame)?;
+                w.write_u8(TAG_KEY_DOWN)?;
+                w.write_i32::<LE>(*scancode as i32)?;
+                w.write_u16::<LE>(keymod.bits())?;
+                w.write_u8(*repeat as u8)?;
+            }
+            Event::KeyUp { scancode: Some(scancode), keymod, .. } => {
+                w.write_u32::<LE>(frame)?;
+                w.write_u8(TAG_KEY_UP)?;
+                w.write_i32::<LE>(*scancode as i32)?;
+                w.write_u16::<LE>(keymod.bits())?;
+            }
+            Event::MouseMotion { x, y, .. } => {
+                w.write_u32::<LE>(frame)?;
+                w.write_u8(TAG_MOUSE_MOTION)?;
+                w.write_i32::<LE>(*x)?;
+                w.write_i32::<LE>(*y)?;
+            }
+            Event::ControllerAxisMotion { which, axis, value, .. } => {
+                w.write_u32::<LE>(frame)?;
+                w.write_u8(TAG_CONTROLLER_AXIS)?;
+                w.write_u32::<LE>(*which)?;
+                write_name(w, &axis.string())?;
+                w.write_i16::<LE>(*value)?;
+            }
+            Event::ControllerButtonDown { which, button, .. } | Event::ControllerButtonUp { which, button, .. } => {
+                let tag = if let Event::ControllerButtonDown { .. } = event {
+                    TAG_CONTROLLER_BUTTON_DOWN
+                } else {
+                    TAG_CONTROLLER_BUTTON_UP
+                };
+
+                w.write_u32::<LE>(frame)?;
+                w.write_u8(tag)?;
+                w.write_u32::<LE>(*which)?;
+                write_name(w, &button.string())?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> GameResult {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+pub struct EventPlayback {
+    reader: BufReader<File>,
+    next: Option<(u32, Option<Event>)>,
+}
+
+impl EventPlayback {
+    pub fn open(path: &Path) -> GameResult<EventPlayback> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(GameError::ResourceLoadError("Not an event trace file.".to_owned()));
+        }
+
+        let version = reader.read_u16::<LE>()?;
+        if version != VERSION {
+            return Err(GameError::ResourceLoadError(format!("Unsupported event trace version: {}", version)));
+        }
+
+        let mut playback = EventPlayback { reader, next: None };
+        playback.next = playback.read_record()?;
+
+        Ok(playback)
+    }
+
+    /// Returns the events recorded during given event loop iteration.
+    pub fn events_for_frame(&mut self, frame: u32) -> GameResult<Vec<Event>> {
+        let mut events = Vec::new();
+
+        while let Some((event_frame, event)) = self.next.take() {
+            if event_frame > frame {
+                self.next = Some((event_frame, event));
+                break;
+            }
+
+            events.extend(event);
+            self.next = self.read_record()?;
+        }
+
+        Ok(events)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next.is_none()
+    }
+
+    /// Reads the next record, the event is `None` if it refers to something this SDL version doesn't know.
+    fn read_record(&mut self) -> GameResult<Option<(u32, Option<Event>)>> {
+        let r = &mut self.reader;
+
+        let frame = match r.read_u32::<LE>() {
+            Ok(frame) => frame,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let event = match r.read_u8()? {
+            TAG_QUIT => Some(Event::Quit { timestamp: 0 }),
+            TAG_RESIZE => {
+                let width = r.read_i32::<LE>()?;
+                let height = r.read_i32::<LE>()?;
+                Some(Event::Window { timestamp: 0, window_id: 0, win_event: WindowEvent::SizeChanged(width, height) })
+            }
+            TAG_FOCUS_GAINED => Some(Event::Window { timestamp: 0, window_id: 0, win_event: WindowEvent::FocusGained }),
+            TAG_FOCUS_LOST => Some(Event::Window { timestamp: 0, window_id: 0, win_event: WindowEvent::FocusLost }),
+            TAG_KEY_DOWN => {
+                let scancode = Scancode::from_i32(r.read_i32::<LE>()?);
+                let keymod = Mod::from_bits_truncate(r.read_u16::<LE>()?);
+                let repeat = r.read_u8()? != 0;
+                scancode.map(|scancode| Event::KeyDown {
+                    timestamp: 0,
+                    window_id: 0,
+                    keycode: None,
+                    scancode: Some(scancode),
+                    keymod,
+                    repeat,
+                })
+            }
+            TAG_KEY_UP => {
+                let scancode = Scancode::from_i32(r.read_i32::<LE>()?);
+                let keymod = Mod::from_bits_truncate(r.read_u16::<LE>()?);
+                scancode.map(|scancode| Event::KeyUp {
+                    timestamp: 0,
+                    window_id: 0,
+                    keycode: None,
+                    scancode: Some(scancode),
+                    keymod,
+                    repeat: false,
+                })
+            }
+            TAG_MOUSE_MOTION => {
+                let x = r.read_i32::<LE>()?;
+                let y = r.read_i32::<LE>()?;
+                Some(Event::MouseMotion {
+                    timestamp: 0,
+                    window_id: 0,
+                    which: 0,
+                    mousestate: MouseState::from_sdl_state(0),
+                    x,
+                    y,
+                    xrel: 0,
+                    yrel: 0,
+                })
+            }
+            TAG_CONTROLLER_AXIS => {
+                let which = r.read_u32::<LE>()?;
+                let axis = controller::Axis::from_string(&read_name(r)?);
+                let value = r.read_i16::<LE>()?;
+                axis.map(|axis| Event::ControllerAxisMotion { timestamp: 0, which, axis, value })
+            }
+            TAG_CONTROLLER_BUTTON_DOWN => {
+                let which = r.read_u32::<LE>()?;
+                let button = controller::Button::from_string(&read_name(r)?);
+                button.map(|button| Event::ControllerButtonDown { timestamp: 0, which, button })
+            }
+            TAG_CONTROLLER_BUTTON_UP => {
+                let which = r.read_u32::<LE>()?;
+                let button = controller::Button::from_string(&read_name(r)?);
+                button.map(|button| Event::ControllerButtonUp { timestamp: 0, which, button })
+            }
+            tag => {
+                return Err(GameError::ResourceLoadError(format!("Unknown event trace record: {}", tag)));
+            }
+        };
+
+        Ok(Some((frame, event)))
+    }
+}
+
+/// Whether the event comes from a keyboard, mouse, touch screen or controller. During playback these are taken
+/// from the trace, everything else, like window events, from the real window.
+pub fn is_input_event(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::KeyDown { .. }
+            | Event::KeyUp { .. }
+            | Event::TextEditing { .. }
+            | Event::TextInput { .. }
+            | Event::MouseMotion { .. }
+            | Event::MouseButtonDown { .. }
+            | Event::MouseButtonUp { .. }
+            | Event::MouseWheel { .. }
+            | Event::JoyAxisMotion { .. }
+            | Event::JoyBallMotion { .. }
+            | Event::JoyHatMotion { .. }
+            | Event::JoyButtonDown { .. }
+            | Event::JoyButtonUp { .. }
+            | Event::ControllerAxisMotion { .. }
+            | Event::ControllerButtonDown { .. }
+            | Event::ControllerButtonUp { .. }
+            | Event::FingerDown { .. }
+            | Event::FingerUp { .. }
+            | Event::FingerMotion { .. }
+    )
+}
+
+fn write_name<W: Write>(w: &mut W, name: &str) -> GameResult {
+    let bytes = &name.as_bytes()[..name.len().min(u8::MAX as usize)];
+    w.write_u8(bytes.len() as u8)?;
+    w.write_all(bytes)?;
+    Ok(())
+}
+
+fn read_name<R: Read>(r: &mut R) -> GameResult<String> {
+    let len = r.read_u8()?;
+    let mut buf = vec![0u8; len as usize];
+    r.read_exact(&mut buf)?;
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let path = std::env::temp_dir().join(format!("drs-event-trace-{}.bin", std::process::id()));
+
+        {
+            let mut recorder = EventRecorder::create(&path).unwrap();
+            let key = |repeat| Event::KeyDown {
+                timestamp: 0,
+                window_id: 0,
+                keycode: None,
+                scancode: Some(Scancode::Z),
+                keymod: Mod::LSHIFTMOD,
+                repeat,
+            };
+
+            recorder.record(0, &key(false)).unwrap();
+            recorder.record(0, &Event::Window { timestamp: 0, window_id: 0, win_event: WindowEvent::Exposed }).unwrap();
+            recorder.record(3, &key(true)).unwrap();
+            recorder
+                .record(3, &Event::ControllerButtonDown { timestamp: 0, which: 1, button: controller::Button::A })
+                .unwrap();
+            recorder.record(7, &Event::Quit { timestamp: 0 }).unwrap();
+            recorder.flush().unwrap();
+        }
+
+        let mut playback = EventPlayback::open(&path).unwrap();
+
+        let frame0 = playback.events_for_frame(0).unwrap();
+        assert_eq!(frame0.len(), 1);
+        match &frame0[0] {
+            Event::KeyDown { scancode, keymod, repeat, .. } => {
+                assert_eq!(*scancode, Some(Scancode::Z));
+                assert_eq!(*keymod, Mod::LSHIFTMOD);
+                assert!(!repeat);
+            }
+            event => panic!("unexpected event: {:?}", event),
+        }
+
+        assert!(playback.events_for_frame(1).unwrap().is_empty());
+
+        let frame3 = playback.events_for_frame(3).unwrap();
+        assert_eq!(frame3.len(), 2);
+        assert!(matches!(frame3[1], Event::ControllerButtonDown { which: 1, button: controller::Button::A, .. }));
+
+        assert!(!playback.is_finished());
+        assert!(matches!(playback.events_for_frame(10).unwrap()[..], [Event::Quit { .. }]));
+        assert!(playback.is_finished());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_input_events() {
+        assert!(is_input_event(&Event::ControllerButtonUp { timestamp: 0, which: 0, button: controller::Button::B }));
+        assert!(is_input_event(&Event::TextInput { timestamp: 0, window_id: 0, text: "z".to_owned() }));
+        assert!(!is_input_event(&Event::Quit { timestamp: 0 }));
+        assert!(!is_input_event(&Event::Window {
+            timestamp: 0,
+            window_id: 0,
+            win_event: WindowEvent::SizeChanged(640, 480)
+        }));
+    }
+}
@@ -221,7 +221,9 @@ impl GamepadData {
 }
 
 pub struct GamepadContext {
-    gamepads: Vec<GamepadData>,
+    /// Connected gamepads, indexed by player slot. Slots of disconnected gamepads are kept empty,
+    /// so other players don't get their controller swapped.
+    gamepads: Vec<Option<GamepadData>>,
 }
 
 impl GamepadContext {
@@ -230,27 +232,50 @@ impl GamepadContext {
     }
 
     fn get_gamepad(&self, gamepad_id: u32) -> Option<&GamepadData> {
-        self.gamepads.iter().find(|gamepad| gamepad.controller.instance_id() == gamepad_id)
+        self.gamepads.iter().flatten().find(|gamepad| gamepad.controller.instance_id() == gamepad_id)
     }
 
     fn get_gamepad_by_index(&self, gamepad_index: usize) -> Option<&GamepadData> {
-        self.gamepads.get(gamepad_index)
+        self.gamepads.get(gamepad_index).and_then(|gamepad| gamepad.as_ref())
     }
 
     fn get_gamepad_mut(&mut self, gamepad_id: u32) -> Option<&mut GamepadData> {
-        self.gamepads.iter_mut().find(|gamepad| gamepad.controller.instance_id() == gamepad_id)
+        self.gamepads.iter_mut().flatten().find(|gamepad| gamepad.controller.instance_id() == gamepad_id)
     }
 
     fn get_gamepad_by_index_mut(&mut self, gamepad_index: usize) -> Option<&mut GamepadData> {
-        self.gamepads.get_mut(gamepad_index)
+        self.gamepads.get_mut(gamepad_index).and_then(|gamepad| gamepad.as_mut())
     }
 
-    pub(crate) fn add_gamepad(&mut self, game_controller: Box<dyn BackendGamepad>, axis_sensitivity: f64) {
-        self.gamepads.push(GamepadData::new(game_controller, axis_sensitivity));
+    /// Returns the player slot the next connected gamepad will be assigned to.
+    pub(crate) fn next_free_slot(&self) -> usize {
+        self.gamepads.iter().position(|gamepad| gamepad.is_none()).unwrap_or(self.gamepads.len())
+    }
+
+    /// Assigns the gamepad to the first free player slot and returns it.
+    pub(crate) fn add_gamepad(&mut self, game_controller: Box<dyn BackendGamepad>, axis_sensitivity: f64) -> usize {
+        let slot = self.next_free_slot();
+        let data = Some(GamepadData::new(game_controller, axis_sensitivity));
+
+        if slot == self.gamepads.len() {
+            self.gamepads.push(data);
+        } else {
+            self.gamepads[slot] = data;
+        }
+
+        slot
     }
 
     pub(crate) fn remove_gamepad(&mut self, gamepad_id: u32) {
-        self.gamepads.retain(|data| data.controller.instance_id() != gamepad_id);
+        for slot in self.gamepads.iter_mut() {
+            if slot.as_ref().map_or(false, |data| data.controller.instance_id() == gamepad_id) {
+                *slot = None;
+            }
+        }
+
+        while let Some(None) = self.gamepads.last() {
+            self.gamepads.pop();
+        }
     }
 
     pub(crate) fn set_gamepad_type(&mut self, gamepad_id: u32, controller_type: GamepadType) {
@@ -338,10 +363,18 @@ impl GamepadContext {
         }
     }
 
-    pub(crate) fn get_gamepads(&self) -> &Vec<GamepadData> {
+    pub(crate) fn get_gamepads(&self) -> &Vec<Option<GamepadData>> {
         &self.gamepads
     }
 
+    pub(crate) fn connected_controllers(&self) -> Vec<(usize, String)> {
+        self.gamepads
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, gamepad)| gamepad.as_ref().map(|gamepad| (slot, gamepad.get_gamepad_name())))
+            .collect()
+    }
+
     pub(crate) fn pressed_buttons(&self, gamepad_index: u32) -> HashSet<Button> {
         if let Some(gamepad) = self.get_gamepad_by_index(gamepad_index as usize) {
             return gamepad.pressed_buttons_set.clone();
@@ -382,7 +415,7 @@ impl GamepadContext {
         hi_freq: u16,
         ticks: u32,
     ) -> GameResult {
-        for gamepad in self.gamepads.iter_mut() {
+        for gamepad in self.gamepads.iter_mut().flatten() {
             gamepad.set_rumble(state, low_freq, hi_freq, ticks)?;
         }
 
@@ -396,8 +429,8 @@ impl Default for GamepadContext {
     }
 }
 
-pub fn add_gamepad(context: &mut Context, game_controller: Box<dyn BackendGamepad>, axis_sensitivity: f64) {
-    context.gamepad_context.add_gamepad(game_controller, axis_sensitivity);
+pub fn add_gamepad(context: &mut Context, game_controller: Box<dyn BackendGamepad>, axis_sensitivity: f64) -> usize {
+    context.gamepad_context.add_gamepad(game_controller, axis_sensitivity)
 }
 
 pub fn remove_gamepad(context: &mut Context, gamepad_id: u32) {
@@ -424,10 +457,15 @@ pub fn is_axis_active(ctx: &Context, gamepad_index: u32, axis: Axis, direction:
     ctx.gamepad_context.is_axis_active(gamepad_index, axis, direction)
 }
 
-pub fn get_gamepads(ctx: &Context) -> &Vec<GamepadData> {
+pub fn get_gamepads(ctx: &Context) -> &Vec<Option<GamepadData>> {
     ctx.gamepad_context.get_gamepads()
 }
 
+/// Returns `(player slot, name)` of every connected gamepad.
+pub fn connected_controllers(ctx: &Context) -> Vec<(usize, String)> {
+    ctx.gamepad_context.connected_controllers()
+}
+
 pub fn pressed_buttons(ctx: &Context, gamepad_index: u32) -> HashSet<Button> {
     ctx.gamepad_context.pressed_buttons(gamepad_index)
 }
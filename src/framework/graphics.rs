@@ -1,5 +1,5 @@
 use crate::common::{Color, Rect};
-use crate::framework::backend::{BackendShader, BackendTexture, VertexData};
+use crate::framework::backend::{BackendShader, BackendTexture, RendererDebugInfo, SpriteBatchCommand, VertexData};
 use crate::framework::context::Context;
 use crate::framework::error::{GameError, GameResult};
 
@@ -37,12 +37,118 @@ pub enum VSyncMode {
     VRRTickSync3x,
 }
 
+/// How the game waits for the next frame when the frame rate is limited by the game rather than V-Sync.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pacing {
+    /// Sleep in short intervals, cheap but only as precise as the OS scheduler (~15ms on Windows). The default.
+    SleepOnly,
+    /// Sleep until shortly before the deadline, then busy-wait the rest. More precise, but keeps a core busy
+    /// for up to a millisecond every frame. Opt-in with `set_pacing` or `CAVESTORY_HYBRID_PACING=1`.
+    Hybrid,
+}
+
+/// Color space blending is performed in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Colors are blended as they're stored, without any conversion. This is what the original game does,
+    /// additive lighting comes out darker and more saturated than it physically should.
+    Srgb,
+    /// Colors are converted to linear light before blending and back to `sRGB` when presented.
+    Linear,
+}
+
+/// Memory layout of textures uploaded to the GPU, named after the byte order regardless of endianness.
+/// Texture data is always passed around as RGBA, picking the driver's native layout avoids a conversion on upload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureFormat {
+    Rgba32,
+    /// `ARGB8888` on little endian machines.
+    Bgra32,
+    /// `BGRA8888` on little endian machines.
+    Argb32,
+    /// `RGBA8888` on little endian machines.
+    Abgr32,
+}
+
+impl TextureFormat {
+    /// Byte offsets of the red, green, blue and alpha channels within a pixel.
+    pub fn channel_offsets(self) -> [usize; 4] {
+        match self {
+            TextureFormat::Rgba32 => [0, 1, 2, 3],
+            TextureFormat::Bgra32 => [2, 1, 0, 3],
+            TextureFormat::Argb32 => [1, 2, 3, 0],
+            TextureFormat::Abgr32 => [3, 2, 1, 0],
+        }
+    }
+}
+
+/// What's shown in the areas of the screen the game doesn't draw over.
+pub enum LetterboxStyle {
+    /// Fill with a solid color.
+    Color(Color),
+    /// Tile the texture across the whole screen.
+    Texture(Box<dyn BackendTexture>),
+}
+
+impl Default for LetterboxStyle {
+    fn default() -> Self {
+        LetterboxStyle::Color(Color::new(0.0, 0.0, 0.0, 1.0))
+    }
+}
+
 pub fn clear(ctx: &mut Context, color: Color) {
     if let Some(renderer) = &mut ctx.renderer {
         renderer.clear(color)
     }
 }
 
+/// When enabled, frames are only redrawn and presented if something could have changed since the last one
+/// (a game tick ran, the scene changed or the window was resized), which saves power on static screens.
+pub fn set_dirty_rect_mode(ctx: &mut Context, enabled: bool) {
+    ctx.dirty_rect_mode = enabled;
+}
+
+pub fn set_letterbox(ctx: &mut Context, style: LetterboxStyle) {
+    ctx.letterbox = style;
+}
+
+/// Clears the screen with the current `LetterboxStyle`, meant to be called before drawing the frame.
+pub fn clear_letterbox(ctx: &mut Context) -> GameResult {
+    let renderer = match &mut ctx.renderer {
+        Some(renderer) => renderer,
+        None => return Ok(()),
+    };
+
+    match &mut ctx.letterbox {
+        LetterboxStyle::Color(color) => renderer.clear(*color),
+        LetterboxStyle::Texture(texture) => {
+            renderer.clear(Color::new(0.0, 0.0, 0.0, 1.0));
+
+            let (width, height) = texture.dimensions();
+            if width == 0 || height == 0 {
+                return Ok(());
+            }
+
+            let (width, height) = (width as f32, height as f32);
+            let src = Rect::new(0.0, 0.0, width, height);
+
+            texture.clear();
+            let mut y = 0.0;
+            while y < ctx.screen_size.1 {
+                let mut x = 0.0;
+                while x < ctx.screen_size.0 {
+                    texture.add(SpriteBatchCommand::DrawRect(src, Rect::new_size(x, y, width, height)));
+                    x += width;
+                }
+                y += height;
+            }
+            texture.draw()?;
+        }
+    }
+
+    Ok(())
+}
+
 pub fn present(ctx: &mut Context) -> GameResult {
     if let Some(renderer) = &mut ctx.renderer {
         renderer.present()?;
@@ -60,6 +166,68 @@ pub fn set_vsync_mode(ctx: &mut Context, mode: VSyncMode) -> GameResult {
     Ok(())
 }
 
+pub fn set_pacing(ctx: &mut Context, pacing: Pacing) {
+    ctx.pacing = pacing;
+}
+
+/// Toggles window decorations. Combined with a window sized to the desktop this gives borderless fullscreen.
+/// `ctx.screen_size` is updated right away, callers still have to let the game state handle the resize.
+pub fn set_borderless(ctx: &mut Context, borderless: bool) -> GameResult {
+    if let Some(renderer) = &mut ctx.renderer {
+        let size = renderer.set_borderless(borderless)?;
+        ctx.screen_size = size;
+
+        if let Ok(imgui) = renderer.imgui() {
+            imgui.io_mut().display_size = [size.0, size.1];
+        }
+
+        return Ok(());
+    }
+
+    Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
+}
+
+/// Toggles outlining the destination rect of every drawn sprite. Only the SDL2 canvas renderer draws them.
+#[cfg(feature = "debug-outlines")]
+pub fn set_sprite_outlines(ctx: &mut Context, enabled: bool) {
+    ctx.sprite_outlines = enabled;
+
+    if let Some(renderer) = &mut ctx.renderer {
+        renderer.set_sprite_outlines(enabled);
+    }
+}
+
+#[cfg(feature = "debug-outlines")]
+pub fn sprite_outlines(ctx: &Context) -> bool {
+    ctx.sprite_outlines
+}
+
+/// Refresh rate of the display the game was started on, `None` if the backend couldn't determine it.
+pub fn display_refresh_rate(ctx: &Context) -> Option<u32> {
+    ctx.display_refresh_rate
+}
+
+/// Whether the window is minimized, the game keeps updating but nothing is drawn until it's restored.
+pub fn window_minimized(ctx: &Context) -> bool {
+    ctx.window_minimized
+}
+
+/// Resizes the window, `ctx.screen_size` is updated right away. See `SharedGameState::set_window_size`.
+pub fn set_window_size(ctx: &mut Context, width: u32, height: u32) -> GameResult {
+    if let Some(renderer) = &mut ctx.renderer {
+        let size = renderer.set_window_size(width, height)?;
+        ctx.screen_size = size;
+
+        if let Ok(imgui) = renderer.imgui() {
+            imgui.io_mut().display_size = [size.0, size.1];
+        }
+
+        return Ok(());
+    }
+
+    Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
+}
+
 #[allow(unused)]
 pub fn renderer_initialized(ctx: &mut Context) -> bool {
     ctx.renderer.is_some()
@@ -81,6 +249,20 @@ pub fn create_texture(ctx: &mut Context, width: u16, height: u16, data: &[u8]) -
     Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
 }
 
+/// See `BackendRenderer::create_texture_keep_pixels`.
+pub fn create_texture_keep_pixels(
+    ctx: &mut Context,
+    width: u16,
+    height: u16,
+    data: &[u8],
+) -> GameResult<Box<dyn BackendTexture>> {
+    if let Some(renderer) = &mut ctx.renderer {
+        return renderer.create_texture_keep_pixels(width, height, data);
+    }
+
+    Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
+}
+
 pub fn screen_size(ctx: &mut Context) -> (f32, f32) {
     ctx.screen_size
 }
@@ -102,6 +284,14 @@ pub fn set_render_target(ctx: &mut Context, texture: Option<&Box<dyn BackendText
     Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
 }
 
+pub fn warm_texture(ctx: &mut Context, texture: &Box<dyn BackendTexture>) -> GameResult {
+    if let Some(renderer) = &mut ctx.renderer {
+        return renderer.warm_texture(texture);
+    }
+
+    Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
+}
+
 pub fn set_blend_mode(ctx: &mut Context, blend: BlendMode) -> GameResult {
     if let Some(renderer) = &mut ctx.renderer {
         return renderer.set_blend_mode(blend);
@@ -127,6 +317,14 @@ pub fn draw_outline_rect(ctx: &mut Context, rect: Rect, line_width: usize, color
     Ok(())
 }
 
+pub fn draw_flash(ctx: &mut Context, center: (f32, f32), radius: f32, color: Color, intensity: f32) -> GameResult {
+    if let Some(renderer) = &mut ctx.renderer {
+        return renderer.draw_flash(center, radius, color, intensity);
+    }
+
+    Ok(())
+}
+
 pub fn set_clip_rect(ctx: &mut Context, rect: Option<Rect>) -> GameResult {
     if let Some(renderer) = &mut ctx.renderer {
         return renderer.set_clip_rect(rect);
@@ -135,6 +333,173 @@ pub fn set_clip_rect(ctx: &mut Context, rect: Option<Rect>) -> GameResult {
     Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
 }
 
+/// Remaps the colors of the whole screen through given LUT texture, see `BackendRenderer::set_color_lut`.
+pub fn set_color_lut(ctx: &mut Context, lut: Option<Box<dyn BackendTexture>>) -> GameResult {
+    if let Some(renderer) = &mut ctx.renderer {
+        return renderer.set_color_lut(lut);
+    }
+
+    Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
+}
+
+/// Tints every sprite drawn for the rest of the frame, see `BackendRenderer::set_global_tint`.
+pub fn set_global_tint(ctx: &mut Context, color: Color) -> GameResult {
+    if let Some(renderer) = &mut ctx.renderer {
+        return renderer.set_global_tint(color);
+    }
+
+    Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
+}
+
+pub fn set_zoom(ctx: &mut Context, factor: f32, center: (f32, f32)) -> GameResult {
+    if let Some(renderer) = &mut ctx.renderer {
+        return renderer.set_zoom(factor, center);
+    }
+
+    Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
+}
+
+/// Returns a snapshot of the renderer state, `None` if the backend doesn't provide one or isn't initialized.
+pub fn renderer_debug_info(ctx: &mut Context) -> Option<RendererDebugInfo> {
+    ctx.renderer.as_mut().and_then(|renderer| renderer.debug_info())
+}
+
+pub fn set_color_space(ctx: &mut Context, color_space: ColorSpace) -> GameResult {
+    if let Some(renderer) = &mut ctx.renderer {
+        return renderer.set_color_space(color_space);
+    }
+
+    Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
+}
+
+/// Returns the texture formats the renderer can upload textures in, `Rgba32` is always included.
+pub fn supported_texture_formats(ctx: &Context) -> Vec<TextureFormat> {
+    if let Some(renderer) = &ctx.renderer {
+        return renderer.supported_texture_formats();
+    }
+
+    vec![TextureFormat::Rgba32]
+}
+
+/// Changes the format of textures created from now on, existing textures are kept as they are.
+pub fn set_texture_format(ctx: &mut Context, format: TextureFormat) -> GameResult {
+    if let Some(renderer) = &mut ctx.renderer {
+        return renderer.set_texture_format(format);
+    }
+
+    Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
+}
+
+pub fn push_state(ctx: &mut Context) -> GameResult {
+    if let Some(renderer) = &mut ctx.renderer {
+        return renderer.push_state();
+    }
+
+    Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
+}
+
+pub fn pop_state(ctx: &mut Context) -> GameResult {
+    if let Some(renderer) = &mut ctx.renderer {
+        return renderer.pop_state();
+    }
+
+    Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
+}
+
+/// Fills `texture`, which has to be a render target, with `color`. Contents of newly created render targets are
+/// undefined, so this should be done before drawing into one. The render target, blend mode and clip rect in use
+/// before the call are restored afterwards through `push_state`/`pop_state`.
+pub fn clear_target(ctx: &mut Context, texture: &Box<dyn BackendTexture>, color: Color) -> GameResult {
+    push_state(ctx)?;
+
+    let result = set_render_target(ctx, Some(texture)).map(|_| clear(ctx, color));
+
+    // the previous state has to come back even if switching targets failed
+    pop_state(ctx)?;
+    result
+}
+
+/// Shifts the contents of `target` by (`dx`, `dy`) pixels and calls `fill` for every newly exposed strip,
+/// so a scrolling layer kept in a render target only has its edges redrawn instead of the whole thing.
+///
+/// A texture can't be drawn onto itself, so `scratch` has to be a render target of the same size. While
+/// `fill` runs `target` is the render target and the clip rect is set to the strip, which `fill` must cover
+/// completely as it still holds stale pixels. Queued commands of both textures are discarded. The render
+/// target, blend mode and clip rect in use before the call are restored through `push_state`/`pop_state`.
+pub fn scroll_and_fill(
+    ctx: &mut Context,
+    target: &mut Box<dyn BackendTexture>,
+    scratch: &mut Box<dyn BackendTexture>,
+    dx: isize,
+    dy: isize,
+    fill: impl FnMut(&mut Context, Rect) -> GameResult,
+) -> GameResult {
+    push_state(ctx)?;
+
+    let result = scroll_and_fill_unsaved(ctx, target, scratch, dx, dy, fill);
+
+    pop_state(ctx)?;
+    result
+}
+
+fn scroll_and_fill_unsaved(
+    ctx: &mut Context,
+    target: &mut Box<dyn BackendTexture>,
+    scratch: &mut Box<dyn BackendTexture>,
+    dx: isize,
+    dy: isize,
+    mut fill: impl FnMut(&mut Context, Rect) -> GameResult,
+) -> GameResult {
+    let (width, height) = target.dimensions();
+    let (width, height) = (width as isize, height as isize);
+
+    // a clip rect left by the caller would cut into the copies
+    set_clip_rect(ctx, None)?;
+
+    if dx.abs() >= width || dy.abs() >= height {
+        set_render_target(ctx, Some(&*target))?;
+        return fill(ctx, Rect::new(0, 0, width, height));
+    }
+
+    if dx != 0 || dy != 0 {
+        let full = Rect::new(0.0, 0.0, width as f32, height as f32);
+        let shifted = Rect::new_size(dx as f32, dy as f32, width as f32, height as f32);
+
+        set_blend_mode(ctx, BlendMode::None)?;
+
+        set_render_target(ctx, Some(&*scratch))?;
+        target.clear();
+        target.add(SpriteBatchCommand::DrawRect(full, full));
+        target.draw()?;
+
+        set_render_target(ctx, Some(&*target))?;
+        scratch.clear();
+        scratch.add(SpriteBatchCommand::DrawRect(full, shifted));
+        scratch.draw()?;
+
+        set_blend_mode(ctx, BlendMode::Alpha)?;
+    }
+
+    set_render_target(ctx, Some(&*target))?;
+
+    // the exposed column spans the full height, the exposed row only the part next to it
+    if dx != 0 {
+        let rect = if dx > 0 { Rect::new(0, 0, dx, height) } else { Rect::new(width + dx, 0, width, height) };
+
+        set_clip_rect(ctx, Some(rect))?;
+        fill(ctx, rect)?;
+    }
+
+    if dy != 0 {
+        let (left, right) = if dx > 0 { (dx, width) } else { (0, width + dx) };
+        let rect = if dy > 0 { Rect::new(left, 0, right, dy) } else { Rect::new(left, height + dy, right, height) };
+
+        set_clip_rect(ctx, Some(rect))?;
+        fill(ctx, rect)?;
+    }
+
+    Ok(())
+}
 
 pub fn imgui_context(ctx: &Context) -> GameResult<&mut imgui::Context> {
     if let Some(renderer) = ctx.renderer.as_ref() {
@@ -168,6 +533,60 @@ pub fn render_imgui(ctx: &mut Context, draw_data: &imgui::DrawData) -> GameResul
     Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
 }
 
+/// Captures what has been drawn to the screen so far in this frame, as `(width, height, RGBA pixels)`.
+pub fn take_screenshot(ctx: &mut Context) -> GameResult<(u32, u32, Vec<u8>)> {
+    if let Some(renderer) = &mut ctx.renderer {
+        return renderer.read_screen_pixels();
+    }
+
+    Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
+}
+
+/// Compares the screen against a reference image of the same size, tightly packed RGBA top row first. Fails with
+/// the first pixel that differs by more than `tolerance` in any channel, meant for rendering tests.
+#[cfg(test)]
+pub fn assert_frame_matches(ctx: &mut Context, reference_rgba: &[u8], tolerance: u8) -> GameResult {
+    let (width, _, pixels) = take_screenshot(ctx)?;
+
+    compare_rgba(&pixels, reference_rgba, width, tolerance).map_err(GameError::RenderError)
+}
+
+#[cfg(test)]
+fn compare_rgba(actual: &[u8], reference: &[u8], width: u32, tolerance: u8) -> Result<(), String> {
+    if actual.len() != reference.len() {
+        return Err(format!("Frame is {} bytes, reference is {} bytes.", actual.len(), reference.len()));
+    }
+
+    let mismatch = actual
+        .chunks_exact(4)
+        .zip(reference.chunks_exact(4))
+        .position(|(a, b)| a.iter().zip(b).any(|(&a, &b)| a.abs_diff(b) > tolerance));
+
+    match mismatch {
+        Some(idx) => {
+            let (x, y) = (idx as u32 % width.max(1), idx as u32 / width.max(1));
+            let (a, b) = (&actual[idx * 4..idx * 4 + 4], &reference[idx * 4..idx * 4 + 4]);
+            Err(format!("Pixel ({}, {}) is {:?}, expected {:?}.", x, y, a, b))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Downscales what has been drawn so far into a new texture of given size, meant for save slot previews.
+/// It has to be called after the frame is drawn but before it's presented. It reads the whole screen back from
+/// the GPU and resizes it on the CPU, which stalls the pipeline, so it shouldn't be called every frame.
+pub fn capture_thumbnail(ctx: &mut Context, dest_size: (u16, u16)) -> GameResult<Box<dyn BackendTexture>> {
+    let (width, height, pixels) = take_screenshot(ctx)?;
+    let image = image::RgbaImage::from_raw(width, height, pixels)
+        .ok_or_else(|| GameError::RenderError("Screen readback returned an invalid image.".to_string()))?;
+
+    let (dest_width, dest_height) = (dest_size.0.max(1), dest_size.1.max(1));
+    let thumbnail =
+        image::imageops::resize(&image, dest_width as u32, dest_height as u32, image::imageops::FilterType::Triangle);
+
+    create_texture(ctx, dest_width, dest_height, thumbnail.as_raw())
+}
+
 pub fn prepare_draw(ctx: &mut Context) -> GameResult {
     if let Some(renderer) = &mut ctx.renderer {
         return renderer.prepare_draw(ctx.screen_size.0, ctx.screen_size.1);
@@ -196,3 +615,25 @@ pub fn draw_triangle_list(
 
     Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_comparison() {
+        let reference = [0, 0, 0, 255, 100, 150, 200, 255, 10, 10, 10, 255, 255, 255, 255, 255];
+        let mut actual = reference;
+        actual[5] = 153;
+
+        assert!(compare_rgba(&actual, &reference, 2, 3).is_ok());
+        assert_eq!(
+            compare_rgba(&actual, &reference, 2, 2),
+            Err("Pixel (1, 0) is [100, 153, 200, 255], expected [100, 150, 200, 255].".to_string())
+        );
+
+        actual[13] = 0;
+        assert!(compare_rgba(&actual, &reference, 2, 3).unwrap_err().starts_with("Pixel (1, 1)"));
+        assert!(compare_rgba(&actual[..8], &reference, 2, 3).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use serde::{Deserialize, Serialize};
 
@@ -236,6 +236,8 @@ pub struct KeyboardContext {
     pressed_keys_set: HashSet<ScanCode>,
     last_pressed: Option<ScanCode>,
     current_pressed: Option<ScanCode>,
+    /// Number of game ticks each currently pressed key has been held for.
+    held_frames: HashMap<ScanCode, u32>,
 }
 
 impl KeyboardContext {
@@ -245,6 +247,7 @@ impl KeyboardContext {
             pressed_keys_set: HashSet::with_capacity(256),
             last_pressed: None,
             current_pressed: None,
+            held_frames: HashMap::with_capacity(16),
         }
     }
 
@@ -306,6 +309,26 @@ impl KeyboardContext {
         }
     }
 
+    /// Advances hold timers of pressed keys, called by the game loop before every scene tick, so the timing
+    /// doesn't depend on the frame rate.
+    pub(crate) fn update_held(&mut self) {
+        let pressed = &self.pressed_keys_set;
+        self.held_frames.retain(|key, _| pressed.contains(key));
+
+        for key in pressed {
+            *self.held_frames.entry(*key).or_insert(0) += 1;
+        }
+    }
+
+    /// Returns true on the first tick a key is held, then after `initial_delay` ticks every `repeat_every` ticks.
+    pub(crate) fn is_key_pressed_repeating(&self, key: ScanCode, initial_delay: u32, repeat_every: u32) -> bool {
+        match self.held_frames.get(&key) {
+            Some(&1) => true,
+            Some(&held) if held > initial_delay => (held - initial_delay - 1) % repeat_every.max(1) == 0,
+            _ => false,
+        }
+    }
+
     pub(crate) fn pressed_keys(&self) -> &HashSet<ScanCode> {
         &self.pressed_keys_set
     }
@@ -326,6 +349,12 @@ pub fn is_key_pressed(ctx: &Context, key: ScanCode) -> bool {
     ctx.keyboard_context.is_key_pressed(key)
 }
 
+/// Checks if a key was just pressed or is being held long enough to repeat, using the usual
+/// key-repeat timing: `initial_delay` ticks before the first repeat, then one every `repeat_every` ticks.
+pub fn pressed_repeating(ctx: &Context, key: ScanCode, initial_delay: u32, repeat_every: u32) -> bool {
+    ctx.keyboard_context.is_key_pressed_repeating(key, initial_delay, repeat_every)
+}
+
 /// Checks if the last keystroke sent by the system is repeated,
 /// like when a key is held down for a period of time.
 pub fn is_key_repeated(ctx: &Context) -> bool {
@@ -8,14 +8,19 @@ pub mod backend_horizon;
 pub mod backend_null;
 #[cfg(feature = "backend-sdl")]
 pub mod backend_sdl2;
+pub mod clipboard;
 pub mod context;
 pub mod error;
+pub mod event;
+#[cfg(feature = "backend-sdl")]
+pub mod event_trace;
 pub mod filesystem;
 pub mod gamepad;
 #[cfg(feature = "render-opengl")]
 mod gl;
 pub mod graphics;
 pub mod keyboard;
+pub mod mouse;
 #[cfg(feature = "render-opengl")]
 pub mod render_opengl;
 pub mod ui;
@@ -0,0 +1,16 @@
+use crate::framework::context::Context;
+
+/// Moves the mouse cursor to given position in window pixels, see `SharedGameState::canvas_to_screen` for
+/// converting from canvas coordinates. The cursor is moved on the next iteration of the event loop.
+pub fn warp_mouse(ctx: &mut Context, x: i32, y: i32) {
+    ctx.mouse_warp = Some((x, y));
+}
+
+/// Hides the cursor and keeps it in the window, mouse motion is still reported. Useful for mouse aiming.
+pub fn set_relative_mouse_mode(ctx: &mut Context, enabled: bool) {
+    ctx.relative_mouse_mode = enabled;
+}
+
+pub fn relative_mouse_mode(ctx: &Context) -> bool {
+    ctx.relative_mouse_mode
+}
@@ -38,15 +38,14 @@ pub struct OpenGLTexture {
     shader: RenderShader,
     vbo: GLuint,
     vertices: Vec<VertexData>,
+    /// Draw-order key and first vertex of every command added while `sort_by_z` is on.
+    z_ranges: Vec<(i16, usize)>,
+    sort_by_z: bool,
     context_active: Arc<RefCell<bool>>,
 }
 
-impl BackendTexture for OpenGLTexture {
-    fn dimensions(&self) -> (u16, u16) {
-        (self.width, self.height)
-    }
-
-    fn add(&mut self, command: SpriteBatchCommand) {
+impl OpenGLTexture {
+    fn push_command(&mut self, command: SpriteBatchCommand) {
         let (tex_scale_x, tex_scale_y) = (1.0 / self.width as f32, 1.0 / self.height as f32);
 
         match command {
@@ -209,11 +208,115 @@ impl BackendTexture for OpenGLTexture {
                 ];
                 self.vertices.extend_from_slice(&vertices);
             }
+            SpriteBatchCommand::DrawRectF(src, dest) => {
+                // vertex positions are floats already, no special handling needed
+                self.push_command(SpriteBatchCommand::DrawRect(src, dest));
+            }
+            SpriteBatchCommand::DrawRectHsv(src, dest, _, _, _) => {
+                // no HSV support in the default shaders yet
+                self.push_command(SpriteBatchCommand::DrawRect(src, dest));
+            }
+            SpriteBatchCommand::DrawQuad(src, corners) => {
+                let uv = [
+                    (src.left * tex_scale_x, src.top * tex_scale_y),
+                    (src.right * tex_scale_x, src.top * tex_scale_y),
+                    (src.right * tex_scale_x, src.bottom * tex_scale_y),
+                    (src.left * tex_scale_x, src.bottom * tex_scale_y),
+                ];
+
+                for i in [0, 1, 2, 0, 2, 3] {
+                    self.vertices.push(VertexData { position: corners[i], uv: uv[i], color: (255, 255, 255, 255) });
+                }
+            }
+            SpriteBatchCommand::DrawRectEx(mut src, dest, angle, center, flip_x, flip_y) => {
+                if flip_x {
+                    std::mem::swap(&mut src.left, &mut src.right);
+                }
+
+                if flip_y {
+                    std::mem::swap(&mut src.top, &mut src.bottom);
+                }
+
+                let (cx, cy) = center.unwrap_or((dest.width() / 2.0, dest.height() / 2.0));
+                let (cx, cy) = (dest.left + cx, dest.top + cy);
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let rotate = |x: f32, y: f32| {
+                    let (dx, dy) = (x - cx, y - cy);
+                    (cx + dx * cos - dy * sin, cy + dx * sin + dy * cos)
+                };
+
+                self.push_command(SpriteBatchCommand::DrawQuad(
+                    src,
+                    [
+                        rotate(dest.left, dest.top),
+                        rotate(dest.right, dest.top),
+                        rotate(dest.right, dest.bottom),
+                        rotate(dest.left, dest.bottom),
+                    ],
+                ));
+            }
+        }
+    }
+
+    /// Reorders vertices so commands with lower z come first, commands with equal z keep their order.
+    fn sort_vertices_by_z(&mut self) {
+        if self.z_ranges.windows(2).all(|w| w[0].0 <= w[1].0) {
+            return;
+        }
+
+        let ends: Vec<usize> = self
+            .z_ranges
+            .iter()
+            .skip(1)
+            .map(|(_, start)| *start)
+            .chain(std::iter::once(self.vertices.len()))
+            .collect();
+        let mut order: Vec<usize> = (0..self.z_ranges.len()).collect();
+        order.sort_by_key(|&i| self.z_ranges[i].0);
+
+        let mut vertices = Vec::with_capacity(self.vertices.len());
+        let mut z_ranges = Vec::with_capacity(self.z_ranges.len());
+        for i in order {
+            let (z, start) = self.z_ranges[i];
+            z_ranges.push((z, vertices.len()));
+            vertices.extend_from_slice(&self.vertices[start..ends[i]]);
+        }
+
+        self.vertices = vertices;
+        self.z_ranges = z_ranges;
+    }
+}
+
+impl BackendTexture for OpenGLTexture {
+    fn dimensions(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    fn add(&mut self, command: SpriteBatchCommand) {
+        self.add_z(0, command);
+    }
+
+    fn add_z(&mut self, z: i16, command: SpriteBatchCommand) {
+        if self.sort_by_z {
+            self.z_ranges.push((z, self.vertices.len()));
+        }
+
+        self.push_command(command);
+    }
+
+    fn set_sort_by_z(&mut self, enabled: bool) {
+        self.sort_by_z = enabled;
+        self.z_ranges.clear();
+
+        if enabled && !self.vertices.is_empty() {
+            // whatever was queued before counts as z = 0
+            self.z_ranges.push((0, 0));
         }
     }
 
     fn clear(&mut self) {
         self.vertices.clear();
+        self.z_ranges.clear();
     }
 
     fn draw(&mut self) -> GameResult {
@@ -223,6 +326,10 @@ impl BackendTexture for OpenGLTexture {
                     return Ok(());
                 }
 
+                if self.sort_by_z {
+                    self.sort_vertices_by_z();
+                }
+
                 if gl.gl.BindSampler.is_loaded() {
                     gl.gl.BindSampler(0, 0);
                 }
@@ -591,6 +698,8 @@ pub struct OpenGLRenderer {
     context_active: Arc<RefCell<bool>>,
     def_matrix: [[f32; 4]; 4],
     curr_matrix: [[f32; 4]; 4],
+    /// Whether a texture is bound as the render target, tracked by `set_render_target`.
+    target_bound: bool,
 }
 
 impl OpenGLRenderer {
@@ -602,6 +711,7 @@ impl OpenGLRenderer {
             context_active: Arc::new(RefCell::new(true)),
             def_matrix: [[0.0; 4]; 4],
             curr_matrix: [[0.0; 4]; 4],
+            target_bound: false,
         }
     }
 
@@ -754,6 +864,7 @@ impl BackendRenderer for OpenGLRenderer {
                     [-1.0, 1.0, 0.0, 1.0],
                 ];
                 self.curr_matrix = self.def_matrix;
+                self.target_bound = false;
 
                 gl.gl.BindBuffer(gl::ARRAY_BUFFER, 0);
                 gl.gl.BindBuffer(gl::ELEMENT_ARRAY_BUFFER, 0);
@@ -832,6 +943,8 @@ impl BackendRenderer for OpenGLRenderer {
                     width,
                     height,
                     vertices: Vec::new(),
+                    z_ranges: Vec::new(),
+                    sort_by_z: false,
                     shader: self.render_data.tex_shader,
                     vbo: self.render_data.vbo,
                     context_active: self.context_active.clone(),
@@ -871,6 +984,8 @@ impl BackendRenderer for OpenGLRenderer {
                     width,
                     height,
                     vertices: Vec::new(),
+                    z_ranges: Vec::new(),
+                    sort_by_z: false,
                     shader: self.render_data.tex_shader,
                     vbo: self.render_data.vbo,
                     context_active: self.context_active.clone(),
@@ -919,6 +1034,7 @@ impl BackendRenderer for OpenGLRenderer {
                         .downcast_ref::<OpenGLTexture>()
                         .ok_or_else(|| RenderError("This texture was not created by OpenGL backend.".to_string()))?;
 
+                    self.target_bound = true;
                     self.curr_matrix = [
                         [2.0 / (gl_texture.width as f32), 0.0, 0.0, 0.0],
                         [0.0, 2.0 / (gl_texture.height as f32), 0.0, 0.0],
@@ -952,6 +1068,7 @@ impl BackendRenderer for OpenGLRenderer {
                     gl.gl.BindFramebuffer(gl::FRAMEBUFFER, gl_texture.framebuffer_id);
                     gl.gl.Viewport(0, 0, gl_texture.width as _, gl_texture.height as _);
                 } else {
+                    self.target_bound = false;
                     self.curr_matrix = self.def_matrix;
 
                     gl.gl.UseProgram(self.render_data.fill_shader.program_id);
@@ -1072,6 +1189,9 @@ impl BackendRenderer for OpenGLRenderer {
 
     fn render_imgui(&mut self, draw_data: &DrawData) -> GameResult {
         // https://github.com/michaelfairley/rust-imgui-opengl-renderer
+        // render targets aren't flipped vertically, see set_render_target
+        let to_target = self.target_bound;
+
         if let Some((_, gl)) = self.get_context() {
             unsafe {
                 gl.gl.ActiveTexture(gl::TEXTURE0);
@@ -1090,11 +1210,12 @@ impl BackendRenderer for OpenGLRenderer {
                 let fb_height = height * scale_h;
 
                 gl.gl.Viewport(0, 0, fb_width as _, fb_height as _);
+                let (flip, offset) = if to_target { (1.0, -1.0) } else { (-1.0, 1.0) };
                 let matrix = [
                     [2.0 / width as f32, 0.0, 0.0, 0.0],
-                    [0.0, 2.0 / -(height as f32), 0.0, 0.0],
+                    [0.0, 2.0 / (flip * height as f32), 0.0, 0.0],
                     [0.0, 0.0, -1.0, 0.0],
-                    [-1.0, 1.0, 0.0, 1.0],
+                    [-1.0, offset, 0.0, 1.0],
                 ];
 
                 gl.gl.UseProgram(self.render_data.tex_shader.program_id);
@@ -1165,9 +1286,11 @@ impl BackendRenderer for OpenGLRenderer {
                             } => {
                                 gl.gl.BindTexture(gl::TEXTURE_2D, texture_id.id() as _);
 
+                                let scissor_y = if to_target { y * scale_h } else { fb_height - w * scale_h };
+
                                 gl.gl.Scissor(
                                     (x * scale_w) as GLint,
-                                    (fb_height - w * scale_h) as GLint,
+                                    scissor_y as GLint,
                                     ((z - x) * scale_w) as GLint,
                                     ((w - y) * scale_h) as GLint,
                                 );
@@ -1195,6 +1318,38 @@ impl BackendRenderer for OpenGLRenderer {
         Ok(())
     }
 
+    fn read_screen_pixels(&mut self) -> GameResult<(u32, u32, Vec<u8>)> {
+        let imgui = self.imgui()?;
+        let [width, height] = imgui.io().display_size;
+        let [scale_w, scale_h] = imgui.io().display_framebuffer_scale;
+        let (width, height) = ((width * scale_w) as u32, (height * scale_h) as u32);
+
+        if let Some((_, gl)) = self.get_context() {
+            let mut pixels = vec![0u8; width as usize * height as usize * 4];
+
+            unsafe {
+                gl.gl.PixelStorei(gl::PACK_ALIGNMENT, 1);
+                gl.gl.ReadPixels(
+                    0,
+                    0,
+                    width as _,
+                    height as _,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    pixels.as_mut_ptr() as *mut c_void,
+                );
+            }
+
+            // OpenGL returns the bottom row first
+            let row = width as usize * 4;
+            let pixels = pixels.chunks_exact(row).rev().flatten().copied().collect();
+
+            Ok((width, height, pixels))
+        } else {
+            Err(RenderError("No OpenGL context available!".to_string()))
+        }
+    }
+
     fn supports_vertex_draw(&self) -> bool {
         true
     }
@@ -3,8 +3,11 @@ use std::time::Instant;
 use imgui::{FontConfig, FontSource};
 use imgui::sys::*;
 
+use crate::common::{Color, Rect};
+use crate::framework::backend::{BackendTexture, SpriteBatchCommand};
 use crate::framework::context::Context;
 use crate::framework::error::GameResult;
+use crate::framework::graphics;
 use crate::framework::graphics::{imgui_context, prepare_imgui, render_imgui};
 use crate::game::shared_game_state::SharedGameState;
 use crate::live_debugger::LiveDebugger;
@@ -12,6 +15,11 @@ use crate::scene::Scene;
 
 pub struct UI {
     pub components: Components,
+    /// Render imgui into a separate texture which is blitted by `draw_overlay` right before presenting,
+    /// so the frame can be captured without the UI on top.
+    render_ui_to_overlay: bool,
+    overlay: Option<Box<dyn BackendTexture>>,
+    overlay_drawn: bool,
     last_frame: Instant,
 }
 
@@ -104,7 +112,21 @@ pub fn init_imgui() -> GameResult<imgui::Context> {
 
 impl UI {
     pub fn new(_ctx: &mut Context) -> GameResult<Self> {
-        Ok(Self { components: Components { live_debugger: LiveDebugger::new() }, last_frame: Instant::now() })
+        Ok(Self {
+            components: Components { live_debugger: LiveDebugger::new() },
+            render_ui_to_overlay: false,
+            overlay: None,
+            overlay_drawn: false,
+            last_frame: Instant::now(),
+        })
+    }
+
+    /// Switches rendering imgui into a separate overlay texture on or off, the texture is freed when it's off.
+    pub fn set_render_to_overlay(&mut self, enabled: bool) {
+        self.render_ui_to_overlay = enabled;
+        if !enabled {
+            self.overlay = None;
+        }
     }
 
     pub fn draw(&mut self, state: &mut SharedGameState, ctx: &mut Context, scene: &mut Box<dyn Scene>) -> GameResult {
@@ -120,8 +142,53 @@ impl UI {
 
         prepare_imgui(ctx2, &ui);
         let draw_data = imgui.render();
+
+        self.overlay_drawn = false;
+        if self.render_ui_to_overlay {
+            let [width, height] = draw_data.display_size;
+            let [scale_w, scale_h] = draw_data.framebuffer_scale;
+            let size = ((width * scale_w) as u16, (height * scale_h) as u16);
+
+            if self.overlay.as_ref().map(|overlay| overlay.dimensions()) != Some(size) {
+                self.overlay = graphics::create_texture_mutable(ctx2, size.0, size.1).ok();
+            }
+
+            if let Some(overlay) = self.overlay.as_ref() {
+                graphics::push_state(ctx2)?;
+
+                let result = graphics::set_render_target(ctx2, Some(overlay)).and_then(|_| {
+                    graphics::clear(ctx2, Color::new(0.0, 0.0, 0.0, 0.0));
+                    render_imgui(ctx2, draw_data)
+                });
+
+                graphics::pop_state(ctx2)?;
+                result?;
+
+                self.overlay_drawn = true;
+                return Ok(());
+            }
+        }
+
         render_imgui(ctx2, draw_data)?;
 
         Ok(())
     }
+
+    /// Blits the UI overlay rendered by `draw` onto the screen, does nothing if rendering to it is off.
+    pub fn draw_overlay(&mut self) -> GameResult {
+        if !self.overlay_drawn {
+            return Ok(());
+        }
+
+        if let Some(overlay) = self.overlay.as_mut() {
+            let (width, height) = overlay.dimensions();
+            let rect = Rect::new(0.0, 0.0, width as f32, height as f32);
+
+            overlay.clear();
+            overlay.add(SpriteBatchCommand::DrawRect(rect, rect));
+            overlay.draw()?;
+        }
+
+        Ok(())
+    }
 }
@@ -1,7 +1,10 @@
 use crate::common::{CDEG_RAD, Condition, Direction, Rect};
 use crate::engine_constants::EngineConstants;
+use crate::util::animator::{AnimationMode, Animator};
 use crate::util::rng::RNG;
 
+const BUBBLE_ANIMATION: Animator = Animator::new(6, AnimationMode::OneShot);
+
 #[derive(Debug, EnumIter, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum CaretType {
     None,
@@ -91,7 +94,7 @@ impl Caret {
         }
     }
 
-    pub fn tick(&mut self, rng: &dyn RNG, constants: &EngineConstants) {
+    pub fn tick(&mut self, rng: &dyn RNG, constants: &EngineConstants, anim_scale: f32) {
         match self.ctype {
             CaretType::None => {}
             CaretType::Bubble => {
@@ -105,19 +108,18 @@ impl Caret {
                 self.x += self.vel_x;
                 self.y += self.vel_y;
 
-                self.anim_counter += 1;
-                if self.anim_counter > 5 {
-                    self.anim_counter = 0;
-                    self.anim_num += 1;
-                    if self.anim_num > 3 {
-                        self.cond.set_alive(false);
-                        self.anim_num = 3;
-                    }
+                let frame_count = constants.caret.bubble_left_rects.len();
+                if !BUBBLE_ANIMATION.tick_scaled(frame_count, &mut self.anim_counter, &mut self.anim_num, anim_scale) {
+                    self.cond.set_alive(false);
                 }
 
                 match self.direction {
-                    Direction::Left => self.anim_rect = constants.caret.bubble_left_rects[self.anim_num as usize],
-                    Direction::Right => self.anim_rect = constants.caret.bubble_right_rects[self.anim_num as usize],
+                    Direction::Left => {
+                        self.anim_rect = BUBBLE_ANIMATION.current(&constants.caret.bubble_left_rects, self.anim_num)
+                    }
+                    Direction::Right => {
+                        self.anim_rect = BUBBLE_ANIMATION.current(&constants.caret.bubble_right_rects, self.anim_num)
+                    }
                     _ => (),
                 }
             }
@@ -1,4 +1,5 @@
-use crate::common::{fix9_scale, interpolate_fix9_scale};
+use crate::common::{fix9_scale, interpolate_fix9_scale, interpolate_fix9_snapped};
+use crate::game::map::CameraBoundaries;
 use crate::game::shared_game_state::SharedGameState;
 use crate::game::stage::Stage;
 use crate::util::rng::RNG;
@@ -20,6 +21,8 @@ pub struct Frame {
     pub target_x: i32,
     pub target_y: i32,
     pub wait: i32,
+    /// Whether the drawn camera position is kept on whole screen pixels, see `set_camera_pixel_snap`.
+    pixel_snap: bool,
 }
 
 impl Frame {
@@ -33,10 +36,25 @@ impl Frame {
             target_x: 0,
             target_y: 0,
             wait: 16,
+            pixel_snap: true,
         }
     }
 
+    /// Snaps the camera position used for drawing to whole screen pixels. Sprites are drawn relative to the
+    /// camera and rounded on their own, so an unsnapped camera makes them jitter against the background.
+    /// The camera itself isn't touched, so its movement stays the same.
+    pub fn set_camera_pixel_snap(&mut self, enabled: bool) {
+        self.pixel_snap = enabled;
+    }
+
     pub fn xy_interpolated(&self, frame_time: f64) -> (f32, f32) {
+        if self.pixel_snap {
+            return (
+                interpolate_fix9_snapped(self.prev_x, self.x, frame_time),
+                interpolate_fix9_snapped(self.prev_y, self.y, frame_time),
+            );
+        }
+
         if self.prev_x == self.x && self.prev_y == self.y {
             return (fix9_scale(self.x), fix9_scale(self.y));
         }
@@ -60,14 +78,9 @@ impl Frame {
         } else {
             self.x = self.target_x - (screen_width as i32 * 0x200 / 2);
 
-            if self.x < 0 {
-                self.x = 0;
-            }
-
             let max_x = (((stage.map.width as i32 - 1) * tile_size) - screen_width as i32) * 0x200;
-            if self.x > max_x {
-                self.x = max_x;
-            }
+            let bounds = stage.map.camera_boundaries;
+            self.x = CameraBoundaries::apply(bounds.left, bounds.right, self.x, max_x);
         }
 
         if (stage.map.height as usize).saturating_sub(1) * (tile_size as usize) < state.canvas_size.1 as usize {
@@ -75,14 +88,9 @@ impl Frame {
         } else {
             self.y = self.target_y - (state.canvas_size.1 as i32 * 0x200 / 2);
 
-            if self.y < 0 {
-                self.y = 0;
-            }
-
             let max_y = (((stage.map.height as i32 - 1) * tile_size) - state.canvas_size.1 as i32) * 0x200;
-            if self.y > max_y {
-                self.y = max_y;
-            }
+            let bounds = stage.map.camera_boundaries;
+            self.y = CameraBoundaries::apply(bounds.top, bounds.bottom, self.y, max_y);
         }
 
         self.prev_x = self.x;
@@ -107,14 +115,9 @@ impl Frame {
         } else {
             self.x += (self.target_x - (screen_width as i32 * 0x200 / 2) - self.x) / self.wait;
 
-            if self.x < 0 {
-                self.x = 0;
-            }
-
             let max_x = (((stage.map.width as i32 - 1) * tile_size) - screen_width as i32) * 0x200;
-            if self.x > max_x {
-                self.x = max_x;
-            }
+            let bounds = stage.map.camera_boundaries;
+            self.x = CameraBoundaries::apply(bounds.left, bounds.right, self.x, max_x);
         }
 
         if (stage.map.height as usize).saturating_sub(1) * (tile_size as usize) < state.canvas_size.1 as usize {
@@ -122,14 +125,9 @@ impl Frame {
         } else {
             self.y += (self.target_y - (state.canvas_size.1 as i32 * 0x200 / 2) - self.y) / self.wait;
 
-            if self.y < 0 {
-                self.y = 0;
-            }
-
             let max_y = (((stage.map.height as i32 - 1) * tile_size) - state.canvas_size.1 as i32) * 0x200;
-            if self.y > max_y {
-                self.y = max_y;
-            }
+            let bounds = stage.map.camera_boundaries;
+            self.y = CameraBoundaries::apply(bounds.top, bounds.bottom, self.y, max_y);
         }
 
         let intensity = state.settings.screen_shake_intensity.to_val();
@@ -123,6 +123,14 @@ impl Inventory {
         }
     }
 
+    /// Limits the ammo of given weapon to `max_ammo`, weapons with infinite ammo are left untouched.
+    pub fn clamp_ammo(&mut self, weapon_id: WeaponType, max_ammo: u16) {
+        if let Some(w) = self.get_weapon_by_type_mut(weapon_id) {
+            w.max_ammo = w.max_ammo.min(max_ammo);
+            w.ammo = w.ammo.min(w.max_ammo);
+        }
+    }
+
     pub fn trade_weapon(&mut self, old: Option<WeaponType>, new: WeaponType, max_ammo: u16) {
         if let Some(wtype) = old {
             if let Some(weapon) = self.get_weapon_by_type_mut(wtype) {
@@ -16,6 +16,47 @@ use crate::game::stage::{PxPackScroll, PxPackStageData, StageData};
 static SUPPORTED_PXM_VERSIONS: [u8; 1] = [0x10];
 static SUPPORTED_PXE_VERSIONS: [u8; 2] = [0, 0x10];
 
+/// What the camera does once it reaches an edge of the map.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum CameraBoundary {
+    /// Stops at the edge, like in the original game.
+    #[default]
+    Clamp,
+    /// Stays pinned to the edge, the camera doesn't scroll along this axis at all.
+    NoScroll,
+    /// Keeps following its target past the edge.
+    Follow,
+}
+
+/// Camera behavior at each edge of the map. When both edges of an axis are `NoScroll`, the left or top one wins.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct CameraBoundaries {
+    pub left: CameraBoundary,
+    pub top: CameraBoundary,
+    pub right: CameraBoundary,
+    pub bottom: CameraBoundary,
+}
+
+impl CameraBoundaries {
+    /// Applies the boundaries of one axis to a camera position, `max` being the position of the far edge.
+    pub fn apply(low: CameraBoundary, high: CameraBoundary, pos: i32, max: i32) -> i32 {
+        match (low, high) {
+            (CameraBoundary::NoScroll, _) => 0,
+            (_, CameraBoundary::NoScroll) => max,
+            _ => {
+                let mut pos = pos;
+                if low == CameraBoundary::Clamp && pos < 0 {
+                    pos = 0;
+                }
+                if high == CameraBoundary::Clamp && pos > max {
+                    pos = max;
+                }
+                pos
+            }
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Map {
     pub width: u16,
@@ -23,6 +64,8 @@ pub struct Map {
     pub tiles: Vec<u8>,
     pub attrib: [u8; 0x100],
     pub tile_size: TileSize,
+    /// None of the map formats store these, they default to clamping at every edge.
+    pub camera_boundaries: CameraBoundaries,
 }
 
 static SOLID_TILES: [u8; 8] = [0x05, 0x41, 0x43, 0x46, 0x54, 0x55, 0x56, 0x57];
@@ -53,7 +96,7 @@ impl Map {
 
         let width = map_data.read_u16::<LE>()?;
         let height = map_data.read_u16::<LE>()?;
-        let mut tiles = vec![0u8; (width * height) as usize];
+        let mut tiles = vec![0u8; width as usize * height as usize];
         let mut attrib = [0u8; 0x100];
 
         log::info!("Map size: {}x{}", width, height);
@@ -63,7 +106,19 @@ impl Map {
             log::warn!("Map attribute data is shorter than 256 bytes!");
         }
 
-        Ok(Map { width, height, tiles, attrib, tile_size: TileSize::Tile16x16 })
+        Ok(Map {
+            width,
+            height,
+            tiles,
+            attrib,
+            tile_size: TileSize::Tile16x16,
+            camera_boundaries: CameraBoundaries::default(),
+        })
+    }
+
+    /// Parses a PXM map held in memory. Tile attributes aren't part of the format and are left zeroed.
+    pub fn from_pxm(data: &[u8]) -> GameResult<Map> {
+        Map::load_pxm(data, &[0u8; 0x100][..]).map_err(|e| GameError::ParseError(format!("Invalid PXM map: {}", e)))
     }
 
     pub fn load_pxpack<R: io::Read>(
@@ -279,7 +334,23 @@ impl Map {
             offset_bg: size_fg + size_mg,
         });
 
-        Ok(Map { width: width_fg, height: height_fg, tiles, attrib, tile_size: TileSize::Tile8x8 })
+        Ok(Map {
+            width: width_fg,
+            height: height_fg,
+            tiles,
+            attrib,
+            tile_size: TileSize::Tile8x8,
+            camera_boundaries: CameraBoundaries::default(),
+        })
+    }
+
+    /// Returns the tile id at given position, out of bounds positions are treated as tile 0.
+    pub fn tile_at(&self, x: usize, y: usize) -> u8 {
+        if x >= self.width as usize || y >= self.height as usize {
+            return 0;
+        }
+
+        self.tiles[self.width as usize * y + x]
     }
 
     pub fn get_attribute(&self, x: usize, y: usize) -> u8 {
@@ -613,3 +684,22 @@ impl WaterParams {
         self.entries.get(&tile).unwrap_or(&DEFAULT_ENTRY)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pxm_from_memory() {
+        let data = [b'P', b'X', b'M', 0x10, 3, 0, 2, 0, 1, 2, 3, 4, 5, 6];
+        let map = Map::from_pxm(&data).unwrap();
+
+        assert_eq!((map.width, map.height), (3, 2));
+        assert_eq!(map.tile_at(2, 0), 3);
+        assert_eq!(map.tile_at(0, 1), 4);
+        assert_eq!(map.tile_at(3, 0), 0);
+
+        assert!(matches!(Map::from_pxm(&data[..10]), Err(GameError::ParseError(_))));
+        assert!(matches!(Map::from_pxm(b"PXE\x10\x01\x00\x01\x00\x00"), Err(GameError::ParseError(_))));
+    }
+}
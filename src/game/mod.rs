@@ -11,8 +11,11 @@ use scripting::tsc::text_script::ScriptMode;
 
 use crate::framework::context::Context;
 use crate::framework::error::GameResult;
+use crate::framework::event;
 use crate::framework::graphics;
-use crate::framework::graphics::VSyncMode;
+use crate::framework::graphics::{Pacing, VSyncMode};
+#[cfg(feature = "debug")]
+use crate::framework::keyboard::ScanCode;
 use crate::framework::ui::UI;
 use crate::game::filesystem_container::FilesystemContainer;
 use crate::game::shared_game_state::{Fps, SharedGameState, TimingMode};
@@ -33,6 +36,7 @@ pub mod scripting;
 pub mod settings;
 pub mod shared_game_state;
 pub mod stage;
+pub mod state_dump;
 pub mod weapon;
 
 pub struct LaunchOptions {
@@ -52,8 +56,19 @@ pub struct Game {
     last_tick: u128,
     next_tick: u128,
     pub(crate) loops: u32,
+    /// Bumped by the backends every time a new scene is installed, see `Game::set_scene`.
+    scene_generation: u32,
     next_tick_draw: u128,
+    #[cfg(feature = "debug")]
+    frame_advance_held: u32,
+    /// Scene generation frame advance was last checked against, a different one turns it off.
+    #[cfg(feature = "debug")]
+    frame_advance_generation: u32,
     present: bool,
+    /// Set when the next frame has to be redrawn in dirty rect mode.
+    redraw: bool,
+    /// Scene generation and screen size of the last drawn frame, changes to either force a redraw.
+    last_drawn: (u32, (f32, f32)),
     fps: Fps,
 }
 
@@ -67,18 +82,71 @@ impl Game {
             last_tick: 0,
             next_tick: 0,
             loops: 0,
+            scene_generation: 0,
             next_tick_draw: 0,
+            #[cfg(feature = "debug")]
+            frame_advance_held: 0,
+            #[cfg(feature = "debug")]
+            frame_advance_generation: 0,
             present: true,
+            redraw: true,
+            last_drawn: (u32::MAX, (0.0, 0.0)),
             fps: Fps::new(),
         };
 
         Ok(s)
     }
 
+    /// Returns how many of `elapsed` ticks should actually be simulated while in frame advance mode.
+    /// Single steps are queued by the scene's debug key handler, holding the step key past
+    /// a short delay keeps advancing at a quarter of the normal rate.
+    #[cfg(feature = "debug")]
+    fn frame_advance_ticks(held: &mut u32, state: &mut SharedGameState, ctx: &Context, elapsed: u32) -> u32 {
+        if !state.frame_advance {
+            *held = 0;
+            return elapsed;
+        }
+
+        let mut ticks = std::mem::take(&mut state.frame_advance_steps);
+
+        if ctx.keyboard_context.is_key_pressed(ScanCode::F1) && !ctx.keyboard_context.active_mods().ctrl() {
+            for _ in 0..elapsed {
+                *held += 1;
+                if *held > 25 && *held % 4 == 0 {
+                    ticks += 1;
+                }
+            }
+        } else {
+            *held = 0;
+        }
+
+        ticks
+    }
+
+    /// Installs `scene` as the current one, the caller is responsible for calling its `init`.
+    pub(crate) fn set_scene(&mut self, scene: Option<Box<dyn Scene>>) {
+        self.scene = scene;
+        self.scene_generation = self.scene_generation.wrapping_add(1);
+    }
+
     pub(crate) fn update(&mut self, ctx: &mut Context) -> GameResult {
+        // drained even without a scene, so they don't pile up while loading
+        let events = event::drain_events(ctx);
+
         if let Some(scene) = &mut self.scene {
             let state_ref = unsafe { &mut *self.state.get() };
 
+            #[cfg(feature = "debug")]
+            if self.frame_advance_generation != self.scene_generation {
+                self.frame_advance_generation = self.scene_generation;
+                state_ref.frame_advance = false;
+                state_ref.frame_advance_steps = 0;
+            }
+
+            for event in events {
+                scene.process_event(state_ref, ctx, &event)?;
+            }
+
             let speed =
                 if state_ref.textscript_vm.mode == ScriptMode::Map && state_ref.textscript_vm.flags.cutscene_skip() {
                     4.0 * state_ref.settings.speed
@@ -108,23 +176,71 @@ impl Game {
                     }
 
                     if self.loops != 0 {
-                        scene.draw_tick(state_ref)?;
                         self.last_tick = last_tick;
                     }
 
-                    for _ in 0..self.loops {
+                    #[cfg(feature = "debug")]
+                    let ticks = Self::frame_advance_ticks(&mut self.frame_advance_held, state_ref, ctx, self.loops);
+                    #[cfg(not(feature = "debug"))]
+                    let ticks = self.loops;
+                    if ticks != 0 {
+                        scene.draw_tick(state_ref)?;
+                    }
+
+                    for _ in 0..ticks {
+                        ctx.keyboard_context.update_held();
                         scene.tick(state_ref, ctx)?;
                     }
-                    self.fps.tick_count = self.fps.tick_count.saturating_add(self.loops as u32);
+                    self.redraw |= ticks != 0;
+                    self.fps.tick_count = self.fps.tick_count.saturating_add(ticks);
                 }
                 TimingMode::FrameSynchronized => {
-                    scene.tick(state_ref, ctx)?;
+                    #[cfg(feature = "debug")]
+                    let ticks = Self::frame_advance_ticks(&mut self.frame_advance_held, state_ref, ctx, 1);
+                    #[cfg(not(feature = "debug"))]
+                    let ticks = 1;
+
+                    for _ in 0..ticks {
+                        ctx.keyboard_context.update_held();
+                        scene.tick(state_ref, ctx)?;
+                        self.redraw = true;
+                    }
                 }
             }
         }
         Ok(())
     }
 
+    /// Waits until `deadline` (in nanoseconds since `start_time`). Sleeps while there's more than 1ms left,
+    /// since that's roughly how late the sleep can wake up on most systems, and spins for the remainder.
+    fn wait_until(&self, deadline: u128) {
+        const SPIN_NANOS: u128 = 1_000_000;
+
+        let now = self.start_time.elapsed().as_nanos();
+        if deadline > now + SPIN_NANOS {
+            std::thread::sleep(Duration::from_nanos((deadline - now - SPIN_NANOS) as u64));
+        }
+
+        while self.start_time.elapsed().as_nanos() < deadline {
+            std::hint::spin_loop();
+        }
+    }
+
+    fn needs_redraw(&mut self, state: &SharedGameState) -> bool {
+        let last_drawn = (self.scene_generation, state.screen_size);
+
+        if self.last_drawn != last_drawn {
+            self.last_drawn = last_drawn;
+            return true;
+        }
+
+        // interpolated frames and the debug UI can change without the game ticking
+        let interpolated =
+            state.settings.motion_interpolation && state.settings.timing_mode != TimingMode::FrameSynchronized;
+
+        self.redraw || interpolated || state.debugger || state.command_line
+    }
+
     pub(crate) fn draw(&mut self, ctx: &mut Context) -> GameResult {
         let state_ref = unsafe { &mut *self.state.get() };
 
@@ -157,7 +273,18 @@ impl Game {
         }
 
         if !self.present {
-            std::thread::sleep(Duration::from_millis(2));
+            match ctx.pacing {
+                Pacing::SleepOnly => std::thread::sleep(Duration::from_millis(2)),
+                Pacing::Hybrid => {
+                    // don't oversleep the game tick either, it's handled by the same loop
+                    let deadline = if state_ref.settings.timing_mode != TimingMode::FrameSynchronized {
+                        self.next_tick_draw.min(self.next_tick)
+                    } else {
+                        self.next_tick_draw
+                    };
+                    self.wait_until(deadline);
+                }
+            }
             self.loops = 0;
             return Ok(());
         }
@@ -168,6 +295,13 @@ impl Game {
             return Ok(());
         }
 
+        if ctx.dirty_rect_mode && !self.needs_redraw(state_ref) {
+            std::thread::sleep(Duration::from_millis(1));
+            self.loops = 0;
+            return Ok(());
+        }
+        self.redraw = false;
+
         if state_ref.settings.timing_mode != TimingMode::FrameSynchronized {
             let mut elapsed = self.start_time.elapsed().as_nanos();
 
@@ -179,7 +313,11 @@ impl Game {
 
             let n1 = (elapsed - self.last_tick) as f64;
             let n2 = (self.next_tick - self.last_tick) as f64;
-            state_ref.frame_time = if state_ref.settings.motion_interpolation { n1 / n2 } else { 1.0 };
+            state_ref.frame_time = if state_ref.settings.motion_interpolation && !state_ref.frame_advance_active() {
+                n1 / n2
+            } else {
+                1.0
+            };
         }
         unsafe {
             G_MAG = if state_ref.settings.subpixel_coords { state_ref.scale } else { 1.0 };
@@ -187,8 +325,9 @@ impl Game {
         }
         self.loops = 0;
 
+        state_ref.texture_set.begin_frame();
         graphics::prepare_draw(ctx)?;
-        graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
+        graphics::clear_letterbox(ctx)?;
 
         if let Some(scene) = &mut self.scene {
             scene.draw(state_ref, ctx)?;
@@ -206,9 +345,13 @@ impl Game {
                 self.fps.act(state_ref, ctx, self.start_time.elapsed().as_nanos())?;
             }
 
+            self.ui.set_render_to_overlay(state_ref.settings.render_ui_to_overlay);
             self.ui.draw(state_ref, ctx, scene)?;
         }
 
+        // anything captured with graphics::take_screenshot before this point doesn't include the UI overlay
+        self.ui.draw_overlay()?;
+
         graphics::present(ctx)?;
 
         Ok(())
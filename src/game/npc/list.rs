@@ -14,6 +14,7 @@ pub struct NPCList {
     // from theoretically performing some optimizations that might break the code.
     npcs: Box<UnsafeCell<[NPC; NPC_LIST_MAX_CAP]>>,
     max_npc: Cell<u16>,
+    spawn_limit: u16,
     seed: i32,
 }
 
@@ -32,6 +33,7 @@ impl NPCList {
                 transmute(parts_uninit)
             })),
             max_npc: Cell::new(0),
+            spawn_limit: NPC_LIST_MAX_CAP as u16,
             seed: 0,
         };
 
@@ -48,9 +50,15 @@ impl NPCList {
         self.seed = seed;
     }
 
+    /// Limits the slots `spawn` can use, clamped to the list's capacity. Slots taken with `spawn_at_slot`,
+    /// such as the stage's own NPCs, aren't limited.
+    pub fn set_spawn_limit(&mut self, limit: u16) {
+        self.spawn_limit = limit.min(NPC_LIST_MAX_CAP as u16);
+    }
+
     /// Inserts NPC into list in first available slot after given ID.
     pub fn spawn(&self, min_id: u16, mut npc: NPC) -> GameResult {
-        let npc_len = unsafe { self.npcs().len() };
+        let npc_len = self.spawn_limit as usize;
 
         if min_id as usize >= npc_len {
             return Err(GameError::InvalidValue("NPC ID is out of bounds".to_string()));
@@ -144,6 +152,11 @@ impl NPCList {
         NPC_LIST_MAX_CAP as u16
     }
 
+    /// Returns the number of slots `spawn` is allowed to use.
+    pub fn spawn_limit(&self) -> u16 {
+        self.spawn_limit
+    }
+
     unsafe fn npcs<'a: 'b, 'b>(&'a self) -> &'b [NPC; NPC_LIST_MAX_CAP] {
         &*self.npcs.get()
     }
@@ -272,5 +285,18 @@ pub fn test_npc_list() -> GameResult {
         assert!(map.spawn(0, npc.clone()).is_err());
     }
 
+    {
+        let mut map = Box::new(NPCList::new());
+        map.set_spawn_limit(4);
+
+        for _ in 0..4 {
+            map.spawn(0, npc.clone())?;
+        }
+
+        assert!(map.spawn(0, npc.clone()).is_err());
+        map.spawn_at_slot(10, npc.clone())?;
+        assert_eq!(map.iter_alive().count(), 5);
+    }
+
     Ok(())
 }
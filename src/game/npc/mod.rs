@@ -977,3 +977,32 @@ impl Deref for TexRef<'_> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn npc_table_bounds() {
+        // npc.tbl stores each field as a column for all entries, hit rects come before display rects
+        let mut data = Vec::new();
+        data.extend_from_slice(&[0, 0, 0x20, 0]); // flags
+        data.extend_from_slice(&[1, 0, 3, 0]); // life
+        data.extend_from_slice(&[0, 21]); // spritesheet
+        data.extend_from_slice(&[0, 0]); // death sound
+        data.extend_from_slice(&[0, 0]); // hurt sound
+        data.extend_from_slice(&[0, 1]); // size
+        data.extend_from_slice(&[0; 8]); // experience
+        data.extend_from_slice(&[0; 8]); // damage
+        data.extend_from_slice(&[0, 0, 0, 0, 4, 5, 4, 6]); // hit bounds
+        data.extend_from_slice(&[0, 0, 0, 0, 8, 8, 8, 8]); // display bounds
+
+        let table = NPCTable::load_from(data.as_slice()).unwrap();
+        let entry = table.get_entry(1).unwrap();
+
+        assert_eq!((entry.hit_bounds.left, entry.hit_bounds.top, entry.hit_bounds.bottom), (4, 5, 6));
+        assert_eq!((entry.display_bounds.left, entry.display_bounds.bottom), (8, 8));
+        assert_eq!(table.get_hit_bounds(1).top, 5 * 0x200);
+        assert_eq!(table.get_display_bounds(1).top, 8 * 0x200);
+    }
+}
@@ -264,18 +264,9 @@ impl NPCList {
                     state.sound_manager.play_sfx(table_entry.death_sound);
                 }
 
-                match npc.size {
-                    1 => {
-                        self.create_death_smoke(npc.x, npc.y, npc.display_bounds.right as usize, 4, state, &npc.rng);
-                    }
-                    2 => {
-                        self.create_death_smoke(npc.x, npc.y, npc.display_bounds.right as usize, 8, state, &npc.rng);
-                    }
-                    3 => {
-                        self.create_death_smoke(npc.x, npc.y, npc.display_bounds.right as usize, 16, state, &npc.rng);
-                    }
-                    _ => {}
-                };
+                if let Some(count) = state.constants.npc_effects.vanish_smoke_count(npc.size) {
+                    self.create_death_smoke(npc.x, npc.y, npc.display_bounds.right as usize, count, state, &npc.rng);
+                }
             }
         }
     }
@@ -287,18 +278,9 @@ impl NPCList {
                 state.sound_manager.play_sfx(table_entry.death_sound);
             }
 
-            match npc.size {
-                1 => {
-                    self.create_death_smoke(npc.x, npc.y, npc.display_bounds.right as usize, 3, state, &npc.rng);
-                }
-                2 => {
-                    self.create_death_smoke(npc.x, npc.y, npc.display_bounds.right as usize, 7, state, &npc.rng);
-                }
-                3 => {
-                    self.create_death_smoke(npc.x, npc.y, npc.display_bounds.right as usize, 12, state, &npc.rng);
-                }
-                _ => {}
-            };
+            if let Some(count) = state.constants.npc_effects.death_smoke_count(npc.size) {
+                self.create_death_smoke(npc.x, npc.y, npc.display_bounds.right as usize, count, state, &npc.rng);
+            }
 
             if npc.exp != 0 {
                 let rng = npc.rng.range(0..4);
@@ -120,9 +120,12 @@ pub trait PhysicalEntity {
     }
 
     fn test_block_hit(&mut self, state: &mut SharedGameState, x: i32, y: i32) {
-        let bounds_x = if self.is_player() { 0x600 } else { 0x600 };
-        let bounds_top = if self.is_player() { 0x800 } else { 0x600 };
-        let bounds_bottom = if self.is_player() { 0x800 } else { 0x600 };
+        let collision = state.constants.collision;
+        let bounds_x = collision.ceiling_floor_tolerance;
+        let bounds_top =
+            if self.is_player() { collision.player_wall_tolerance } else { collision.npc_wall_tolerance };
+        let bounds_bottom = bounds_top;
+        let push_out_speed = collision.wall_push_out_speed;
         let half_tile_size = state.tile_size.as_int() * 0x100;
 
         if (self.y() - self.hit_bounds().top as i32) < ((y * 2 + 1) * half_tile_size - bounds_top)
@@ -135,8 +138,8 @@ pub trait PhysicalEntity {
                 self.set_x(((x * 2 + 1) * half_tile_size) + self.hit_bounds().right as i32);
 
                 if self.is_player() {
-                    if self.vel_x() < -0x180 {
-                        self.set_vel_x(-0x180);
+                    if self.vel_x() < -push_out_speed {
+                        self.set_vel_x(-push_out_speed);
                     }
 
                     if !self.player_left_pressed() && self.vel_x() < 0 {
@@ -154,8 +157,8 @@ pub trait PhysicalEntity {
                 self.set_x(((x * 2 - 1) * half_tile_size) - self.hit_bounds().right as i32);
 
                 if self.is_player() {
-                    if self.vel_x() > 0x180 {
-                        self.set_vel_x(0x180);
+                    if self.vel_x() > push_out_speed {
+                        self.set_vel_x(push_out_speed);
                     }
 
                     if !self.player_right_pressed() && self.vel_x() > 0 {
@@ -232,7 +235,8 @@ pub trait PhysicalEntity {
         if ((self.x() - self.hit_bounds().right as i32) < (x * 2 + 1) * half_tile_size)
             && ((self.x() + self.hit_bounds().right as i32) > (x * 2 - 1) * half_tile_size)
             && ((self.y() + self.hit_bounds().bottom as i32) > ((y * 2 - 1) * half_tile_size))
-            && ((self.y() + self.hit_bounds().bottom as i32) < (y * 2 - 1) * half_tile_size + 0x400)
+            && ((self.y() + self.hit_bounds().bottom as i32)
+                < (y * 2 - 1) * half_tile_size + state.constants.collision.platform_snap_distance)
         {
             self.set_y(((y * 2 - 1) * half_tile_size) - self.hit_bounds().bottom as i32);
 
@@ -5,6 +5,7 @@ use num_traits::clamp;
 
 use crate::common::{interpolate_fix9_scale, Condition, Direction, Equipment, Flag, Rect};
 use crate::components::number_popup::NumberPopup;
+use crate::engine_constants::{EngineConstants, FlickerMode};
 use crate::entity::GameEntity;
 use crate::framework::context::Context;
 use crate::framework::error::GameResult;
@@ -15,6 +16,7 @@ use crate::game::npc::NPC;
 use crate::game::player::skin::basic::BasicPlayerSkin;
 use crate::game::player::skin::{PlayerAnimationState, PlayerAppearanceState, PlayerSkin};
 use crate::game::shared_game_state::SharedGameState;
+use crate::graphics::texture_set::SpriteBatch;
 use crate::input::dummy_player_controller::DummyPlayerController;
 use crate::input::player_controller::PlayerController;
 use crate::util::rng::RNG;
@@ -113,6 +115,8 @@ pub struct Player {
     strafe_up: bool,
     weapon_offset_y: i8,
     splash: bool,
+    /// How far the physics have blended towards water ones, up to `PlayerConsts::water_transition_ticks`.
+    water_blend: u8,
     tick: u8,
     booster_switch: BoosterSwitch,
     pub anim_num: u16,
@@ -152,6 +156,7 @@ impl Player {
             question: false,
             booster_fuel: 0,
             splash: false,
+            water_blend: 0,
             up: false,
             down: false,
             current_weapon: 0,
@@ -225,12 +230,24 @@ impl Player {
             return Ok(());
         }
 
-        let physics = if self.flags.in_water() {
-            state.constants.player.water_physics
+        let (air_physics, water_physics) = (state.constants.player.air_physics, state.constants.player.water_physics);
+        let transition = if state.smooth_water_physics() { state.constants.player.water_transition_ticks } else { 0 };
+
+        self.water_blend = if self.flags.in_water() {
+            self.water_blend.saturating_add(1).min(transition)
         } else {
-            state.constants.player.air_physics
+            self.water_blend.saturating_sub(1).min(transition)
         };
 
+        let physics = if transition != 0 {
+            air_physics.blend(&water_physics, self.water_blend, transition)
+        } else if self.flags.in_water() {
+            water_physics
+        } else {
+            air_physics
+        };
+        let max_dash = if self.controller.run() { physics.max_run } else { physics.max_dash };
+
         self.question = false;
 
         if !state.control_flags.control_enabled() {
@@ -283,11 +300,11 @@ impl Player {
                     self.cond.set_interacted(true);
                     self.question = true;
                 } else {
-                    if self.controller.move_left() && self.vel_x > -physics.max_dash {
+                    if self.controller.move_left() && self.vel_x > -max_dash {
                         self.vel_x -= physics.dash_ground;
                     }
 
-                    if self.controller.move_right() && self.vel_x < physics.max_dash {
+                    if self.controller.move_right() && self.vel_x < max_dash {
                         self.vel_x += physics.dash_ground;
                     }
 
@@ -355,11 +372,11 @@ impl Player {
                     }
                 }
 
-                if self.controller.move_left() && self.vel_x > -physics.max_dash {
+                if self.controller.move_left() && self.vel_x > -max_dash {
                     self.vel_x -= physics.dash_air;
                 }
 
-                if self.controller.move_right() && self.vel_x < physics.max_dash {
+                if self.controller.move_right() && self.vel_x < max_dash {
                     self.vel_x += physics.dash_air;
                 }
 
@@ -435,9 +452,14 @@ impl Player {
             self.vel_y += 0x55;
         }
 
+        let booster = state.constants.booster;
+
         if (state.settings.infinite_booster || self.equip.has_booster_2_0())
             && self.booster_switch != BoosterSwitch::None
         {
+            let exhaust = self.controller.trigger_jump()
+                || booster.spawns_exhaust(self.booster_fuel, booster.exhaust_phase_2_0);
+
             match self.booster_switch {
                 BoosterSwitch::Left | BoosterSwitch::Right => {
                     if self.flags.hit_left_wall() || self.flags.hit_right_wall() {
@@ -466,12 +488,12 @@ impl Player {
                         _ => 0,
                     };
 
-                    if self.controller.trigger_jump() || self.booster_fuel % 3 == 1 {
+                    if exhaust {
                         if self.direction == Direction::Left || self.direction == Direction::Right {
                             state.create_caret(
                                 self.x - (0x400 * self.direction.vector_x()),
                                 self.y + 0x400,
-                                CaretType::Exhaust,
+                                booster.exhaust_caret,
                                 booster_dir.opposite(),
                             );
                         }
@@ -481,13 +503,13 @@ impl Player {
                 BoosterSwitch::Up => {
                     self.vel_y -= 0x20;
 
-                    if self.controller.trigger_jump() || self.booster_fuel % 3 == 1 {
-                        state.create_caret(self.x, self.y + 0xc00, CaretType::Exhaust, Direction::Bottom);
+                    if exhaust {
+                        state.create_caret(self.x, self.y + 0xc00, booster.exhaust_caret, Direction::Bottom);
                         state.sound_manager.play_sfx(113);
                     }
                 }
-                BoosterSwitch::Down if self.controller.trigger_jump() || self.booster_fuel % 3 == 1 => {
-                    state.create_caret(self.x, self.y - 0xc00, CaretType::Exhaust, Direction::Up);
+                BoosterSwitch::Down if exhaust => {
+                    state.create_caret(self.x, self.y - 0xc00, booster.exhaust_caret, Direction::Up);
                     state.sound_manager.play_sfx(113);
                 }
                 _ => {}
@@ -497,11 +519,11 @@ impl Player {
         } else if self.equip.has_booster_0_8() && self.booster_switch != BoosterSwitch::None && self.vel_y > -0x400 {
             self.vel_y -= 0x20;
 
-            if self.booster_fuel % 3 == 0 {
+            if booster.spawns_exhaust(self.booster_fuel, booster.exhaust_phase_0_8) {
                 state.create_caret(
                     self.x,
                     self.y + self.hit_bounds.bottom as i32 / 2,
-                    CaretType::Exhaust,
+                    booster.exhaust_caret,
                     Direction::Bottom,
                 );
                 state.sound_manager.play_sfx(113);
@@ -532,19 +554,19 @@ impl Player {
                     && self.flags.hit_left_lower_half()
                     && self.flags.hit_right_lower_half())
             {
-                self.vel_y = 0x400; // 2.0fix9
+                self.vel_y = state.constants.collision.slope_snap_speed;
             }
         }
 
-        let max_move = if self.flags.in_water()
-            && !(self.flags.force_left()
-                || self.flags.force_up()
-                || self.flags.force_right()
-                || self.flags.force_down())
+        // currents push the player at full speed even underwater
+        let max_move = if self.flags.force_left()
+            || self.flags.force_up()
+            || self.flags.force_right()
+            || self.flags.force_down()
         {
-            state.constants.player.water_physics.max_move
+            air_physics.max_move
         } else {
-            state.constants.player.air_physics.max_move
+            physics.max_move
         };
 
         self.vel_x = self.vel_x.clamp(-max_move, max_move);
@@ -737,6 +759,19 @@ impl Player {
         Ok(())
     }
 
+    /// Returns the alternate sheet for currently worn equipment, if there's one. When several match,
+    /// the one requiring the highest equip flags wins.
+    fn equip_texture_name<'a>(&'a self, constants: &'a EngineConstants) -> Option<&'a str> {
+        let equip = self.equip.0;
+
+        constants
+            .equip_appearances
+            .iter()
+            .filter(|(&flags, _)| flags != 0 && equip & flags == flags)
+            .max_by_key(|(&flags, _)| flags)
+            .map(|(_, name)| name.as_str())
+    }
+
     fn tick_animation(&mut self, state: &mut SharedGameState) {
         if self.cond.hidden() {
             return;
@@ -873,7 +908,7 @@ impl Player {
         }
 
         state.sound_manager.play_sfx(16);
-        self.shock_counter = 128;
+        self.shock_counter = state.constants.player.iframes;
         self.cond.set_interacted(false);
 
         if self.control_mode == ControlMode::Normal {
@@ -1032,15 +1067,22 @@ impl GameEntity<&NPCList> for Player {
             }
         }
 
-        if self.shock_counter / 2 % 2 != 0 {
-            return Ok(());
-        }
+        let flicker_interval = state.constants.player.iframe_flicker_interval.max(1);
+        let alpha = if self.shock_counter / flicker_interval % 2 != 0 {
+            match state.constants.player.iframe_flicker_mode {
+                FlickerMode::Hide => return Ok(()),
+                FlickerMode::Dim(alpha) => alpha,
+            }
+        } else {
+            255
+        };
 
         if self.current_weapon != 0 {
             let batch = state.texture_set.get_or_load_batch(ctx, &state.constants, "Arms")?;
             let (gun_off_x, gun_off_y) = self.skin.get_gun_offset();
 
-            batch.add_rect(
+            add_rect_with_alpha(
+                &mut **batch,
                 interpolate_fix9_scale(
                     self.prev_x - self.display_bounds.left as i32,
                     self.x - self.display_bounds.left as i32,
@@ -1054,6 +1096,7 @@ impl GameEntity<&NPCList> for Player {
                 ) + self.weapon_offset_y as f32
                     + gun_off_y as f32
                     - frame_y,
+                alpha,
                 &self.weapon_rect,
             );
 
@@ -1061,9 +1104,11 @@ impl GameEntity<&NPCList> for Player {
         }
 
         {
-            let batch =
-                state.texture_set.get_or_load_batch(ctx, &state.constants, self.skin.get_skin_texture_name())?;
-            batch.add_rect(
+            let texture_name =
+                self.equip_texture_name(&state.constants).unwrap_or_else(|| self.skin.get_skin_texture_name());
+            let batch = state.texture_set.get_or_load_batch(ctx, &state.constants, texture_name)?;
+            add_rect_with_alpha(
+                &mut **batch,
                 interpolate_fix9_scale(
                     self.prev_x - self.display_bounds.left as i32,
                     self.x - self.display_bounds.left as i32,
@@ -1074,6 +1119,7 @@ impl GameEntity<&NPCList> for Player {
                     self.y - self.display_bounds.top as i32,
                     state.frame_time,
                 ) - frame_y,
+                alpha,
                 &self.anim_rect,
             );
             batch.draw(ctx)?;
@@ -1092,3 +1138,11 @@ impl GameEntity<&NPCList> for Player {
         Ok(())
     }
 }
+
+fn add_rect_with_alpha(batch: &mut dyn SpriteBatch, x: f32, y: f32, alpha: u8, rect: &Rect<u16>) {
+    if alpha == 255 {
+        batch.add_rect(x, y, rect);
+    } else {
+        batch.add_rect_tinted(x, y, (255, 255, 255, alpha), rect);
+    }
+}
@@ -5,7 +5,7 @@ use num_traits::{clamp, FromPrimitive};
 
 use crate::common::{Direction, FadeState, get_timestamp};
 use crate::framework::context::Context;
-use crate::framework::error::GameError::ResourceLoadError;
+use crate::framework::error::GameError::ParseError;
 use crate::framework::error::GameResult;
 use crate::game::player::{ControlMode, TargetPlayer};
 use crate::game::shared_game_state::{GameDifficulty, SharedGameState};
@@ -105,30 +105,9 @@ impl GameProfile {
             state.set_map_flag(idx, flag != 0);
         }
 
-        for (idx, &flags) in self.flags.iter().enumerate() {
-            if flags & 0b00000001 != 0 {
-                state.set_flag(idx * 8, true);
-            }
-            if flags & 0b00000010 != 0 {
-                state.set_flag(idx * 8 + 1, true);
-            }
-            if flags & 0b00000100 != 0 {
-                state.set_flag(idx * 8 + 2, true);
-            }
-            if flags & 0b00001000 != 0 {
-                state.set_flag(idx * 8 + 3, true);
-            }
-            if flags & 0b00010000 != 0 {
-                state.set_flag(idx * 8 + 4, true);
-            }
-            if flags & 0b00100000 != 0 {
-                state.set_flag(idx * 8 + 5, true);
-            }
-            if flags & 0b01000000 != 0 {
-                state.set_flag(idx * 8 + 6, true);
-            }
-            if flags & 0b10000000 != 0 {
-                state.set_flag(idx * 8 + 7, true);
+        for id in 0..self.flags.len() * 8 {
+            if self.get_flag(id) {
+                state.set_flag(id, true);
             }
         }
 
@@ -265,6 +244,23 @@ impl GameProfile {
         }
     }
 
+    /// Returns the state of a `<FL+`/`<FL-` flag, flags are packed 8 per byte starting from the lowest bit.
+    /// Out of range flags are always unset.
+    pub fn get_flag(&self, id: usize) -> bool {
+        self.flags.get(id / 8).map_or(false, |byte| byte & (1 << (id % 8)) != 0)
+    }
+
+    /// Sets the state of a `<FL+`/`<FL-` flag, out of range flags are ignored.
+    pub fn set_flag(&mut self, id: usize, value: bool) {
+        if let Some(byte) = self.flags.get_mut(id / 8) {
+            if value {
+                *byte |= 1 << (id % 8);
+            } else {
+                *byte &= !(1 << (id % 8));
+            }
+        }
+    }
+
     pub fn write_save<W: io::Write>(&self, mut data: W) -> GameResult {
         data.write_u64::<BE>(0x446f303431323230)?;
 
@@ -300,11 +296,10 @@ impl GameProfile {
             data.write_u32::<LE>(slot.event_num)?;
         }
 
-        let something = [0u8; 0x80];
-        data.write(&something)?;
+        data.write_all(&self.map_flags)?;
 
         data.write_u32::<BE>(0x464c4147)?;
-        data.write(&self.flags)?;
+        data.write_all(&self.flags)?;
 
         data.write_u32::<LE>(0)?; // unused(?) CS+ space
 
@@ -314,11 +309,23 @@ impl GameProfile {
         Ok(())
     }
 
+    /// Serializes the profile in the `Profile.dat` format, followed by the d-rs specific fields.
+    pub fn to_bytes(&self) -> GameResult<Vec<u8>> {
+        let mut data = Vec::with_capacity(0x604 + 13);
+        self.write_save(&mut data)?;
+
+        Ok(data)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> GameResult<GameProfile> {
+        GameProfile::load_from_save(data)
+    }
+
     pub fn load_from_save<R: io::Read>(mut data: R) -> GameResult<GameProfile> {
         let magic = data.read_u64::<BE>()?;
         // Do041220, Do041115
         if magic != 0x446f303431323230 && magic != 0x446f303431313135 {
-            return Err(ResourceLoadError("Invalid magic".to_owned()));
+            return Err(ParseError("Invalid magic".to_owned()));
         }
 
         let current_map = data.read_u32::<LE>()?;
@@ -378,7 +385,7 @@ impl GameProfile {
         data.read_exact(&mut map_flags)?;
 
         if data.read_u32::<BE>()? != 0x464c4147 {
-            return Err(ResourceLoadError("Invalid FLAG signature".to_owned()));
+            return Err(ParseError("Invalid FLAG signature".to_owned()));
         }
 
         let mut flags = [0u8; 1000];
@@ -413,3 +420,100 @@ impl GameProfile {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a save in the original 0x604 byte `Profile.dat` layout.
+    fn original_profile() -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"Do041220");
+        data.write_u32::<LE>(12).unwrap(); // map
+        data.write_u32::<LE>(8).unwrap(); // song
+        data.write_i32::<LE>(0x2000).unwrap();
+        data.write_i32::<LE>(0x4000).unwrap();
+        data.write_u32::<LE>(2).unwrap(); // direction
+        data.write_u16::<LE>(20).unwrap(); // max life
+        data.write_u16::<LE>(3).unwrap(); // stars
+        data.write_u16::<LE>(17).unwrap(); // life
+        data.write_u16::<LE>(0).unwrap();
+        data.write_u32::<LE>(1).unwrap(); // current weapon
+        data.write_u32::<LE>(0).unwrap(); // current item
+        data.write_u32::<LE>(0x24).unwrap(); // equipment
+        data.write_u32::<LE>(0).unwrap(); // control mode
+        data.write_u32::<LE>(1234).unwrap(); // counter
+
+        for i in 0..8u32 {
+            for v in [i, 1, 5, 100, 50] {
+                data.write_u32::<LE>(v).unwrap();
+            }
+        }
+
+        for i in 0..32u32 {
+            data.write_u32::<LE>(i * 2).unwrap();
+        }
+
+        for i in 0..8u32 {
+            data.write_u32::<LE>(i).unwrap();
+            data.write_u32::<LE>(1000 + i).unwrap();
+        }
+
+        data.extend((0..0x80).map(|i| i as u8));
+        data.extend_from_slice(b"FLAG");
+        data.extend((0..1000).map(|i| (i * 7) as u8));
+
+        assert_eq!(data.len(), 0x604);
+        data
+    }
+
+    #[test]
+    fn profile_round_trip() {
+        let original = original_profile();
+        let profile = GameProfile::from_bytes(&original).unwrap();
+
+        assert_eq!(profile.current_map, 12);
+        assert_eq!(profile.life, 17);
+        assert_eq!(profile.direction, Direction::Right);
+        assert_eq!(profile.weapon_data[3].weapon_id, 3);
+        assert_eq!(profile.teleporter_slots[7].event_num, 1007);
+        assert_eq!(profile.timestamp, 0);
+
+        let bytes = profile.to_bytes().unwrap();
+        assert_eq!(&bytes[..0x604], &original[..]);
+        assert_eq!(GameProfile::from_bytes(&bytes).unwrap().to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn profile_flags() {
+        let mut profile = GameProfile::from_bytes(&original_profile()).unwrap();
+
+        // byte 1 is 0b111
+        assert!(profile.get_flag(8) && profile.get_flag(9) && profile.get_flag(10));
+        assert!(!profile.get_flag(11));
+        assert!(!profile.get_flag(8000));
+
+        profile.set_flag(11, true);
+        profile.set_flag(8, false);
+        profile.set_flag(8000, true);
+        assert_eq!(profile.flags[1], 0b1110);
+
+        let profile = GameProfile::from_bytes(&profile.to_bytes().unwrap()).unwrap();
+        assert!(profile.get_flag(11) && !profile.get_flag(8));
+    }
+
+    #[test]
+    fn profile_truncated_flags() {
+        let data = original_profile();
+
+        assert!(GameProfile::from_bytes(&data[..0x500]).is_err());
+    }
+
+    #[test]
+    fn profile_invalid_magic() {
+        let mut data = original_profile();
+        data[..8].copy_from_slice(b"Do000000");
+
+        assert!(matches!(GameProfile::from_bytes(&data), Err(ParseError(_))));
+    }
+}
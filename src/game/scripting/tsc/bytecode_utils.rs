@@ -57,17 +57,23 @@ pub fn read_varint<I: Iterator<Item = u8>>(iter: &mut I) -> GameResult<i32> {
     Ok(((result << 31) ^ (result >> 1)) as i32)
 }
 
-pub fn put_string(buffer: &mut Vec<u8>, out: &mut Vec<u8>, encoding: TextScriptEncoding) {
+/// Decodes the text in `buffer` and appends it to the bytecode. In strict mode bytes that aren't valid in given
+/// encoding are an error, otherwise they're replaced with U+FFFD.
+pub fn put_string(buffer: &mut Vec<u8>, out: &mut Vec<u8>, encoding: TextScriptEncoding, strict: bool) -> GameResult {
     if buffer.is_empty() {
-        return;
+        return Ok(());
     }
     let mut chars_count = 0;
 
     let mut tmp_buf = Vec::new();
 
-    let encoding: &encoding_rs::Encoding = encoding.into();
+    let decoder: &encoding_rs::Encoding = encoding.into();
+
+    let (decoded_text, had_errors) = decoder.decode_without_bom_handling(&buffer);
+    if strict && had_errors {
+        return Err(ParseError(format!("Invalid {:?} text: {}", encoding, decoded_text.replace('\u{fffd}', "?"))));
+    }
 
-    let decoded_text = encoding.decode_without_bom_handling(&buffer).0;
     for chr in decoded_text.chars() {
         chars_count += 1;
         put_varint(chr as _, &mut tmp_buf);
@@ -77,6 +83,8 @@ pub fn put_string(buffer: &mut Vec<u8>, out: &mut Vec<u8>, encoding: TextScriptE
 
     put_varint(chars_count, out);
     out.append(&mut tmp_buf);
+
+    Ok(())
 }
 
 #[test]
@@ -92,3 +100,20 @@ fn test_varint() {
         assert_eq!(result, n);
     }
 }
+
+#[test]
+fn test_put_string_invalid_bytes() {
+    let mut out = Vec::new();
+
+    let mut text = b"\x83\x4e\x83\x8a\x83\x58".to_vec();
+    put_string(&mut text, &mut out, TextScriptEncoding::ShiftJIS, true).unwrap();
+    let mut cur: Cursor<&[u8]> = Cursor::new(&out);
+    assert_eq!(read_cur_varint(&mut cur).unwrap(), 3);
+    assert_eq!(read_cur_varint(&mut cur).unwrap(), 'ク' as i32);
+
+    let mut text = b"\x83".to_vec();
+    assert!(put_string(&mut text, &mut out, TextScriptEncoding::ShiftJIS, true).is_err());
+
+    let mut text = b"\x83".to_vec();
+    assert!(put_string(&mut text, &mut out, TextScriptEncoding::ShiftJIS, false).is_ok());
+}
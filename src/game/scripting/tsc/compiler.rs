@@ -81,7 +81,7 @@ impl TextScript {
                 b'#' if allow_next_event => {
                     if !char_buf.is_empty() {
                         put_varint(TSCOpCode::_STR as i32, &mut bytecode);
-                        put_string(&mut char_buf, &mut bytecode, encoding);
+                        put_string(&mut char_buf, &mut bytecode, encoding, strict)?;
                     }
 
                     // some events end without <END marker.
@@ -93,7 +93,7 @@ impl TextScript {
 
                     if !char_buf.is_empty() {
                         put_varint(TSCOpCode::_STR as i32, &mut bytecode);
-                        put_string(&mut char_buf, &mut bytecode, encoding);
+                        put_string(&mut char_buf, &mut bytecode, encoding, strict)?;
                     }
 
                     iter.next();
@@ -325,10 +325,12 @@ impl CreditScript {
                 }
                 b'[' => {
                     let mut char_buf = Vec::new();
+                    let mut terminated = false;
 
                     while let Some(&chr) = iter.peek() {
                         if chr == b']' {
                             iter.next();
+                            terminated = true;
                             break;
                         }
 
@@ -336,10 +338,19 @@ impl CreditScript {
                         iter.next();
                     }
 
-                    if let Ok(cast_tile) = read_number(&mut iter) {
-                        put_varint(CreditOpCode::PushLine as i32, &mut bytecode);
-                        put_varint((cast_tile as u16) as i32, &mut bytecode);
-                        put_string(&mut char_buf, &mut bytecode, encoding);
+                    if strict && !terminated {
+                        return Err(ParseError("Unterminated credits line.".to_owned()));
+                    }
+
+                    match read_number(&mut iter) {
+                        Ok(cast_tile) => {
+                            put_varint(CreditOpCode::PushLine as i32, &mut bytecode);
+                            put_varint((cast_tile as u16) as i32, &mut bytecode);
+                            put_string(&mut char_buf, &mut bytecode, encoding, strict)?;
+                        }
+                        // the original engine silently skips lines without a cast number
+                        Err(e) if strict => return Err(e),
+                        Err(_) => (),
                     }
                 }
                 b'-' => {
@@ -409,3 +420,14 @@ impl CreditScript {
         Ok(CreditScript { labels, bytecode })
     }
 }
+
+#[test]
+fn test_credits_malformed_lines() {
+    let valid = CreditScript::compile(b"[Cave Story]0001-0060/", true, TextScriptEncoding::UTF8).unwrap();
+    assert!(!valid.bytecode.is_empty());
+
+    assert!(CreditScript::compile(b"[Cave Story", true, TextScriptEncoding::UTF8).is_err());
+    assert!(CreditScript::compile(b"[Cave Story]00", true, TextScriptEncoding::UTF8).is_err());
+    assert!(CreditScript::compile(b"-00", true, TextScriptEncoding::UTF8).is_err());
+    assert!(CreditScript::compile(b"[Cave Story]00", false, TextScriptEncoding::UTF8).is_ok());
+}
@@ -1,6 +1,12 @@
+/// Decrypts a Cave Story TSC script in place. Every byte except the middle one is shifted by the key,
+/// which is the middle byte itself (or 7 if it happens to be zero).
 pub fn decrypt_tsc(buf: &mut [u8]) {
     let half = buf.len() / 2;
-    let key = if let Some(0) = buf.get(half) { 0x7 } else { *buf.get(half).unwrap() };
+    let key = match buf.get(half) {
+        Some(0) => 0x7,
+        Some(&key) => key,
+        None => return,
+    };
     log::info!("Decrypting TSC using key {:#x}", key);
 
     for (idx, byte) in buf.iter_mut().enumerate() {
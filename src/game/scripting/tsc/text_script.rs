@@ -24,11 +24,13 @@ use crate::game::scripting::tsc::bytecode_utils::read_cur_varint;
 use crate::game::scripting::tsc::encryption::decrypt_tsc;
 use crate::game::scripting::tsc::opcodes::TSCOpCode;
 use crate::game::shared_game_state::ReplayState;
+use crate::game::settings::Settings;
 use crate::game::shared_game_state::SharedGameState;
 use crate::game::weapon::WeaponType;
 use crate::graphics::font::{Font, Symbols};
 use crate::input::touch_controls::TouchControlType;
 use crate::scene::game_scene::GameScene;
+use crate::sound::music_control::MusicControl;
 
 const TSC_SUBSTITUTION_MAP_SIZE: usize = 1;
 
@@ -1455,19 +1457,12 @@ impl TextScriptVM {
 
                 exec_state = TextScriptExecutionState::Running(event, cursor.position() as u32);
             }
-            TSCOpCode::CMU => {
-                let song_id = read_cur_varint(&mut cursor)? as usize;
-                state.sound_manager.play_song(song_id, &state.constants, &state.settings, ctx, false)?;
-
-                exec_state = TextScriptExecutionState::Running(event, cursor.position() as u32);
-            }
-            TSCOpCode::FMU => {
-                state.sound_manager.play_song(0, &state.constants, &state.settings, ctx, true)?;
-
-                exec_state = TextScriptExecutionState::Running(event, cursor.position() as u32);
-            }
-            TSCOpCode::RMU => {
-                state.sound_manager.restore_state()?;
+            TSCOpCode::CMU | TSCOpCode::FMU | TSCOpCode::RMU => {
+                let music: &mut dyn MusicControl = match &mut state.music_control {
+                    Some(music) => music.as_mut(),
+                    None => &mut state.sound_manager,
+                };
+                exec_music_op(op, &mut cursor, music, &state.constants, &state.settings, ctx)?;
 
                 exec_state = TextScriptExecutionState::Running(event, cursor.position() as u32);
             }
@@ -1768,6 +1763,8 @@ impl TextScriptVM {
 
                     game_scene.inventory_player1.add_weapon(wtype, max_ammo);
                     game_scene.inventory_player2.add_weapon(wtype, max_ammo);
+                    game_scene.inventory_player1.clamp_ammo(wtype, state.constants.player.max_ammo);
+                    game_scene.inventory_player2.clamp_ammo(wtype, state.constants.player.max_ammo);
                     state.mod_requirements.append_weapon(ctx, weapon_id as u16)?;
                 }
 
@@ -1992,6 +1989,26 @@ impl Default for TextScript {
     }
 }
 
+/// Runs `<CMU`, `<FMU` and `<RMU` against given music controller, `op` has already been read from `cursor`.
+fn exec_music_op(
+    op: TSCOpCode,
+    cursor: &mut Cursor<&[u8]>,
+    music: &mut dyn MusicControl,
+    constants: &EngineConstants,
+    settings: &Settings,
+    ctx: &mut Context,
+) -> GameResult {
+    match op {
+        TSCOpCode::CMU => {
+            let song_id = read_cur_varint(cursor)? as usize;
+            music.play_song(song_id, constants, settings, ctx)
+        }
+        TSCOpCode::FMU => music.fade_out(constants, settings, ctx),
+        TSCOpCode::RMU => music.resume_previous(),
+        _ => Ok(()),
+    }
+}
+
 impl TextScript {
     pub fn new() -> TextScript {
         Self { event_map: HashMap::new() }
@@ -2019,3 +2036,26 @@ impl TextScript {
         self.event_map.contains_key(&id)
     }
 }
+
+#[test]
+fn test_music_opcodes_headless() {
+    use crate::sound::music_control::{song, HeadlessMusic};
+
+    let script = TextScript::compile(b"#0100\n<CMU0009<FMU<RMU<CMU0000<END", true, TextScriptEncoding::UTF8).unwrap();
+    let constants = EngineConstants::defaults();
+    let settings = Settings::default();
+    let mut ctx = Context::new();
+    let mut music = HeadlessMusic::new();
+
+    let mut cursor = Cursor::new(script.event_map[&100].as_slice());
+    loop {
+        let op: TSCOpCode = FromPrimitive::from_i32(read_cur_varint(&mut cursor).unwrap()).unwrap();
+        if op == TSCOpCode::END {
+            break;
+        }
+
+        exec_music_op(op, &mut cursor, &mut music, &constants, &settings, &mut ctx).unwrap();
+    }
+
+    assert_eq!(music.history, vec![song::MURA, song::SILENCE, song::MURA, song::SILENCE]);
+}
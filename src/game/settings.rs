@@ -5,7 +5,9 @@ use crate::framework::gamepad::{Axis, AxisDirection, Button, PlayerControllerInp
 use crate::framework::graphics::VSyncMode;
 use crate::framework::keyboard::ScanCode;
 use crate::game::player::TargetPlayer;
-use crate::game::shared_game_state::{CutsceneSkipMode, ScreenShakeIntensity, TimingMode, WindowMode};
+use crate::game::shared_game_state::{
+    CutsceneSkipMode, RunMode, ScalingMode, ScreenShakeIntensity, TimingMode, WindowMode,
+};
 use crate::input::combined_player_controller::CombinedPlayerController;
 use crate::input::gamepad_player_controller::GamepadController;
 use crate::input::keyboard_player_controller::KeyboardController;
@@ -69,6 +71,9 @@ pub struct Settings {
     pub infinite_booster: bool,
     #[serde(skip)]
     pub debug_outlines: bool,
+    /// Keeps the debug UI out of screen captures, see `UI::set_render_to_overlay`.
+    #[serde(skip)]
+    pub render_ui_to_overlay: bool,
     pub fps_counter: bool,
     pub locale: String,
     #[serde(default = "default_window_mode")]
@@ -87,6 +92,24 @@ pub struct Settings {
     pub discord_rpc: bool,
     #[serde(default = "default_true")]
     pub allow_strafe: bool,
+    #[serde(default = "default_scaling_mode")]
+    pub scaling_mode: ScalingMode,
+    #[serde(default = "default_run_mode")]
+    pub run_mode: RunMode,
+    #[serde(default = "default_true")]
+    pub camera_pixel_snap: bool,
+    #[serde(default)]
+    pub window_always_on_top: bool,
+    #[serde(default = "default_window_opacity")]
+    pub window_opacity: f32,
+    #[serde(default)]
+    pub reduce_flashing: bool,
+    /// Advance animations by elapsed time instead of by game ticks, so they play at the same speed at any tick rate.
+    #[serde(default)]
+    pub time_based_animation: bool,
+    /// Blend the player's physics over a few ticks when entering or leaving water instead of switching right away.
+    #[serde(default)]
+    pub smooth_water_physics: bool,
 }
 
 fn default_true() -> bool {
@@ -95,7 +118,12 @@ fn default_true() -> bool {
 
 #[inline(always)]
 fn current_version() -> u32 {
-    25
+    33
+}
+
+#[inline(always)]
+fn default_window_opacity() -> f32 {
+    1.0
 }
 
 #[inline(always)]
@@ -108,6 +136,11 @@ fn default_window_mode() -> WindowMode {
     WindowMode::Windowed
 }
 
+#[inline(always)]
+fn default_scaling_mode() -> ScalingMode {
+    ScalingMode::Integer
+}
+
 #[inline(always)]
 fn default_interpolation() -> InterpolationMode {
     InterpolationMode::Linear
@@ -171,6 +204,16 @@ fn default_cutscene_skip_mode() -> CutsceneSkipMode {
     CutsceneSkipMode::Hold
 }
 
+#[inline(always)]
+fn default_run_mode() -> RunMode {
+    RunMode::Hold
+}
+
+#[inline(always)]
+fn default_run_key() -> ScanCode {
+    ScanCode::C
+}
+
 impl Settings {
     pub fn load(ctx: &Context) -> GameResult<Settings> {
         if let Ok(file) = user_open(ctx, "/settings.json") {
@@ -359,6 +402,55 @@ impl Settings {
             }
         }
 
+        if self.version == 25 {
+            self.version = 26;
+            self.scaling_mode = default_scaling_mode();
+        }
+
+        if self.version == 26 {
+            self.version = 27;
+            self.run_mode = default_run_mode();
+            self.player1_key_map.run = p1_default_keymap().run;
+            self.player2_key_map.run = p2_default_keymap().run;
+        }
+
+        if self.version == 27 {
+            self.version = 28;
+            self.camera_pixel_snap = true;
+        }
+
+        if self.version == 28 {
+            self.version = 29;
+            self.window_always_on_top = false;
+            self.window_opacity = default_window_opacity();
+        }
+
+        if self.version == 29 {
+            self.version = 30;
+            self.reduce_flashing = false;
+        }
+
+        if self.version == 30 {
+            self.version = 31;
+            self.time_based_animation = false;
+        }
+
+        if self.version == 31 {
+            self.version = 32;
+            self.smooth_water_physics = false;
+        }
+
+        if self.version == 32 {
+            self.version = 33;
+            // Ctrl is taken by the debug hotkeys, move the old run defaults off it
+            if self.player1_key_map.run == ScanCode::LControl {
+                self.player1_key_map.run = p1_default_keymap().run;
+            }
+            if self.player2_key_map.run == ScanCode::RControl {
+                self.player2_key_map.run = p2_default_keymap().run;
+            }
+        }
+
         if self.version != initial_version {
             log::info!("Upgraded configuration file from version {} to {}.", initial_version, self.version);
         }
@@ -456,6 +548,7 @@ impl Default for Settings {
             god_mode: false,
             infinite_booster: false,
             debug_outlines: false,
+            render_ui_to_overlay: false,
             fps_counter: false,
             locale: default_locale(),
             window_mode: WindowMode::Windowed,
@@ -467,6 +560,14 @@ impl Default for Settings {
             cutscene_skip_mode: CutsceneSkipMode::Hold,
             discord_rpc: true,
             allow_strafe: true,
+            scaling_mode: default_scaling_mode(),
+            run_mode: default_run_mode(),
+            camera_pixel_snap: true,
+            window_always_on_top: false,
+            window_opacity: default_window_opacity(),
+            reduce_flashing: false,
+            time_based_animation: false,
+            smooth_water_physics: false,
         }
     }
 }
@@ -485,6 +586,8 @@ pub struct PlayerKeyMap {
     pub inventory: ScanCode,
     pub map: ScanCode,
     pub strafe: ScanCode,
+    #[serde(default = "default_run_key")]
+    pub run: ScanCode,
     pub menu_ok: ScanCode,
     pub menu_back: ScanCode,
 }
@@ -504,6 +607,7 @@ pub fn p1_default_keymap() -> PlayerKeyMap {
         inventory: ScanCode::Q,
         map: ScanCode::W,
         strafe: ScanCode::LShift,
+        run: ScanCode::C,
         menu_ok: ScanCode::Z,
         menu_back: ScanCode::X,
     }
@@ -524,6 +628,7 @@ pub fn p2_default_keymap() -> PlayerKeyMap {
         inventory: ScanCode::T,
         map: ScanCode::Y,
         strafe: ScanCode::RShift,
+        run: ScanCode::M,
         menu_ok: ScanCode::B,
         menu_back: ScanCode::N,
     }
@@ -1,4 +1,4 @@
-use std::{cmp, ops::Div};
+use std::cmp;
 
 use chrono::{Datelike, Local};
 
@@ -33,36 +33,45 @@ use crate::mod_requirements::ModRequirements;
 use crate::scene::game_scene::GameScene;
 use crate::scene::title_scene::TitleScene;
 use crate::scene::Scene;
+use crate::sound::music_control::MusicControl;
 use crate::sound::SoundManager;
+use crate::util::animator::ANIMATION_REFERENCE_TPS;
 use crate::util::bitvec::BitVec;
 use crate::util::rng::XorShift;
 
 use super::filesystem_container::FilesystemContainer;
 
+/// How often the game logic ticks. Physics, animations and invincibility frames are counted in ticks like in
+/// the original, so changing the rate changes how fast the game plays, not just how smooth it is. Timers that
+/// represent real time (the Nikumaru counter, teleport delays, rumble) are converted using `get_tps` instead.
 #[derive(PartialEq, Eq, Copy, Clone, serde::Serialize, serde::Deserialize)]
 pub enum TimingMode {
+    /// The original freeware timing, used by most speedrun categories.
     _50Hz,
+    /// Cave Story+ timing, everything runs 20% faster than in freeware.
     _60Hz,
+    /// One tick per rendered frame, the speed depends on the display refresh rate.
     FrameSynchronized,
 }
 
 impl TimingMode {
+    /// Length of a fixed game tick in nanoseconds, 0 if ticks are synchronized with frames.
     pub fn get_delta(self) -> usize {
-        match self {
-            TimingMode::_50Hz => 1000000000 / 50,
-            TimingMode::_60Hz => 1000000000 / 60,
-            TimingMode::FrameSynchronized => 0,
+        match self.get_tps() {
+            0 => 0,
+            tps => 1000000000 / tps,
         }
     }
 
+    /// Length of a fixed game tick in milliseconds, 0 if ticks are synchronized with frames.
     pub fn get_delta_millis(self) -> f64 {
-        match self {
-            TimingMode::_50Hz => 1000.0 / 50.0,
-            TimingMode::_60Hz => 1000.0 / 60.0,
-            TimingMode::FrameSynchronized => 0.0,
+        match self.get_tps() {
+            0 => 0.0,
+            tps => 1000.0 / tps as f64,
         }
     }
 
+    /// Fixed tick rate the game loop accumulates real time against, 0 if ticks are synchronized with frames.
     pub fn get_tps(self) -> usize {
         match self {
             TimingMode::_50Hz => 50,
@@ -103,6 +112,35 @@ impl WindowMode {
     }
 }
 
+#[derive(PartialEq, Eq, Copy, Clone, serde::Serialize, serde::Deserialize)]
+pub enum ScalingMode {
+    /// Scale the canvas by whole multiples only, keeping every pixel the same size.
+    Integer,
+    /// Scale the canvas by the largest factor that fits the window, even if it's not a whole number.
+    Fractional,
+    /// Scale so the viewport width exactly fills the window, the visible height follows the window's aspect ratio.
+    FitWidth,
+    /// Scale so the viewport height exactly fills the window, the visible width follows the window's aspect ratio.
+    FitHeight,
+}
+
+impl ScalingMode {
+    /// Returns the scale factor from canvas to screen pixels. It's the same on both axes, the renderer doesn't
+    /// support stretching, so a window that's wider or taller than the viewport shows more of the game instead.
+    /// `Integer` is the default.
+    pub fn get_scale(self, screen_size: (f32, f32), viewport_size: (f32, f32)) -> f32 {
+        let scale_x = screen_size.0 / viewport_size.0;
+        let scale_y = screen_size.1 / viewport_size.1;
+
+        match self {
+            ScalingMode::Integer => f32::min(scale_x.floor(), scale_y.floor()).max(1.0),
+            ScalingMode::Fractional => f32::min(scale_x, scale_y).max(1.0),
+            ScalingMode::FitWidth => scale_x.max(1.0),
+            ScalingMode::FitHeight => scale_y.max(1.0),
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, Copy, Clone, Debug, num_derive::FromPrimitive)]
 pub enum GameDifficulty {
     Normal = 0,
@@ -123,6 +161,13 @@ pub enum CutsceneSkipMode {
     Auto,
 }
 
+/// Whether the run key has to be held down, or switches running on and off with each press.
+#[derive(PartialEq, Eq, Copy, Clone, serde::Serialize, serde::Deserialize)]
+pub enum RunMode {
+    Hold,
+    Toggle,
+}
+
 impl GameDifficulty {
     pub fn from_primitive(val: u8) -> GameDifficulty {
         return num_traits::FromPrimitive::from_u8(val).unwrap_or(GameDifficulty::Normal);
@@ -318,6 +363,12 @@ pub struct SharedGameState {
     pub frame_time: f64,
     pub debugger: bool,
     pub command_line: bool,
+    /// Debug frame advance mode, the world only ticks when requested via `frame_advance_steps`.
+    /// Turned off whenever the scene changes, it can only be toggled from the game scene.
+    #[cfg(feature = "debug")]
+    pub frame_advance: bool,
+    #[cfg(feature = "debug")]
+    pub frame_advance_steps: u32,
     pub scale: f32,
     pub canvas_size: (f32, f32),
     pub screen_size: (f32, f32),
@@ -333,6 +384,9 @@ pub struct SharedGameState {
     pub font: BMFont,
     pub texture_set: TextureSet,
     pub sound_manager: SoundManager,
+    /// Receives the music requests of scripts instead of `sound_manager` when set, e.g. a `HeadlessMusic`
+    /// when running without audio.
+    pub music_control: Option<Box<dyn MusicControl>>,
     pub settings: Settings,
     pub save_slot: usize,
     pub difficulty: GameDifficulty,
@@ -347,6 +401,10 @@ pub struct SharedGameState {
     #[cfg(feature = "discord-rpc")]
     pub discord_rpc: DiscordRPC,
     pub shutdown: bool,
+    /// Set when the window was asked to close but `quit_handler` held the shutdown back.
+    pub quit_requested: bool,
+    /// Called when the window is asked to close, returning `false` keeps the game running.
+    pub quit_handler: Option<Box<dyn FnMut(&mut SharedGameState) -> bool>>,
 }
 
 impl SharedGameState {
@@ -475,6 +533,10 @@ impl SharedGameState {
             frame_time: 0.0,
             debugger: false,
             command_line: false,
+            #[cfg(feature = "debug")]
+            frame_advance: false,
+            #[cfg(feature = "debug")]
+            frame_advance_steps: 0,
             scale: 2.0,
             screen_size: (640.0, 480.0),
             canvas_size: (320.0, 240.0),
@@ -490,6 +552,7 @@ impl SharedGameState {
             font,
             texture_set: TextureSet::new(),
             sound_manager,
+            music_control: None,
             settings,
             save_slot: 1,
             difficulty: GameDifficulty::Normal,
@@ -504,6 +567,8 @@ impl SharedGameState {
             #[cfg(feature = "discord-rpc")]
             discord_rpc: DiscordRPC::new(discord_rpc_app_id),
             shutdown: false,
+            quit_requested: false,
+            quit_handler: None,
         })
     }
 
@@ -527,6 +592,7 @@ impl SharedGameState {
         self.constants.load_csplus_tables(ctx)?;
         self.constants.load_animated_faces(ctx)?;
         self.constants.load_texture_size_hints(ctx)?;
+        self.constants.load_npc_spawn_table(ctx)?;
         self.reload_stage_table(ctx)?;
 
         let npc_tbl = filesystem::open_find(ctx, &self.constants.base_paths, "npc.tbl")?;
@@ -731,12 +797,18 @@ impl SharedGameState {
         self.textscript_vm.suspend = true;
     }
 
+    /// Resizes the window to given size, but not smaller than the preferred viewport, and applies the new size.
+    pub fn set_window_size(&mut self, ctx: &mut Context, width: u32, height: u32) -> GameResult {
+        let width = width.max(self.preferred_viewport_size.0 as u32);
+        let height = height.max(self.preferred_viewport_size.1 as u32);
+
+        graphics::set_window_size(ctx, width, height)?;
+        self.handle_resize(ctx)
+    }
+
     pub fn handle_resize(&mut self, ctx: &mut Context) -> GameResult {
         self.screen_size = graphics::screen_size(ctx);
-        let scale_x = self.screen_size.1.div(self.preferred_viewport_size.1).floor().max(1.0);
-        let scale_y = self.screen_size.0.div(self.preferred_viewport_size.0).floor().max(1.0);
-
-        self.scale = f32::min(scale_x, scale_y);
+        self.scale = self.settings.scaling_mode.get_scale(self.screen_size, self.preferred_viewport_size);
         self.canvas_size = (self.screen_size.0 / self.scale, self.screen_size.1 / self.scale);
 
         let (width, height) = (self.screen_size.0 as u16, self.screen_size.1 as u16);
@@ -748,9 +820,45 @@ impl SharedGameState {
         Ok(())
     }
 
+    /// Converts a position in screen pixels (mouse, touch) to canvas coordinates.
+    pub fn screen_to_canvas(&self, x: f32, y: f32) -> (f32, f32) {
+        (x / self.scale, y / self.scale)
+    }
+
+    /// Converts a position in canvas coordinates to screen pixels, the inverse of `screen_to_canvas`.
+    pub fn canvas_to_screen(&self, x: f32, y: f32) -> (f32, f32) {
+        (x * self.scale, y * self.scale)
+    }
+
+    /// How many ticks of the original 50 TPS timing a game tick is worth for animations, see `Animator::tick_scaled`.
+    /// Always 1 unless `Settings::time_based_animation` is enabled. Frame synchronized timing has no fixed tick
+    /// length to go by, so ticks are used as they are there too.
+    pub fn animation_tick_scale(&self) -> f32 {
+        match self.settings.timing_mode.get_tps() {
+            tps if self.settings.time_based_animation && tps != 0 => ANIMATION_REFERENCE_TPS / tps as f32,
+            _ => 1.0,
+        }
+    }
+
+    /// Whether player physics blend over `PlayerConsts::water_transition_ticks` when entering or leaving water.
+    /// Replays don't store the setting, so it's always off while recording or playing one back.
+    pub fn smooth_water_physics(&self) -> bool {
+        self.settings.smooth_water_physics && self.replay_state == ReplayState::None
+    }
+
+    /// Whether the world is frozen in debug frame advance mode.
+    pub fn frame_advance_active(&self) -> bool {
+        #[cfg(feature = "debug")]
+        return self.frame_advance;
+
+        #[cfg(not(feature = "debug"))]
+        false
+    }
+
     pub fn tick_carets(&mut self) {
+        let anim_scale = self.animation_tick_scale();
         for caret in &mut self.carets {
-            caret.tick(&self.effect_rng, &self.constants);
+            caret.tick(&self.effect_rng, &self.constants, anim_scale);
         }
 
         self.carets.retain(|c| !c.is_dead());
@@ -776,6 +884,34 @@ impl SharedGameState {
         self.discord_rpc.dispose();
     }
 
+    /// Handles a close request coming from the OS (window close button, SIGTERM etc.), shuts the game down
+    /// unless the quit handler decides otherwise.
+    pub fn request_quit(&mut self) {
+        let proceed = match self.quit_handler.take() {
+            Some(mut handler) => {
+                let proceed = handler(self);
+                // unless the handler replaced itself
+                if self.quit_handler.is_none() {
+                    self.quit_handler = Some(handler);
+                }
+                proceed
+            }
+            None => true,
+        };
+
+        if proceed {
+            self.quit_requested = false;
+            self.shutdown();
+        } else {
+            self.quit_requested = true;
+        }
+    }
+
+    /// Dismisses a pending close request, eg. after the player declined the confirmation dialog.
+    pub fn cancel_quit(&mut self) {
+        self.quit_requested = false;
+    }
+
     // Stops SFX 40/41/58 (CPS and CSS)
     pub fn stop_noise(&mut self) {
         self.sound_manager.stop_sfx(40);
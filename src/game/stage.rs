@@ -73,16 +73,21 @@ impl Background {
 
 #[derive(Debug, EnumIter, PartialEq, Eq, Hash, Copy, Clone)]
 pub enum BackgroundType {
+    /// Tiled across the screen, doesn't move with the camera.
     TiledStatic,
+    /// Tiled, scrolls at half the camera's speed.
     TiledParallax,
+    /// Tiled, scrolls together with the camera.
     Tiled,
     /// Used in Core room, renders water in front of tilemap which also affects player's physics.
     Water,
+    /// Solid background color, the background texture isn't drawn.
     Black,
     /// Used in Ironhead fight. Affects physics of XP/heart/missile drops.
     Scrolling,
     /// Same as Outside, except it affects physics of XP/heart/missile drops.
     OutsideWind,
+    /// Layered sky and clouds scrolling at different speeds over time, independent of the camera.
     Outside,
     /// Present in CS+KAGE, Seems to be a clone of Outside, isn't used anywhere and has unknown purpose
     OutsideUnknown,
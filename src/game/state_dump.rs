@@ -0,0 +1,108 @@
+use crate::framework::error::GameResult;
+use crate::game::map::Map;
+use crate::game::npc::list::NPCList;
+
+/// Bumped whenever a field is removed or changes meaning, so external tools can tell dumps apart.
+/// Adding fields doesn't need a bump.
+pub const STATE_DUMP_VERSION: u32 = 1;
+
+#[derive(serde::Serialize)]
+struct StateDump<'a> {
+    version: u32,
+    stage_id: usize,
+    map: MapDump<'a>,
+    npcs: Vec<NPCDump>,
+}
+
+#[derive(serde::Serialize)]
+struct MapDump<'a> {
+    width: u16,
+    height: u16,
+    /// Row-major, `width * height` tile indices.
+    tiles: &'a [u8],
+}
+
+#[derive(serde::Serialize)]
+struct NPCDump {
+    id: u16,
+    npc_type: u16,
+    /// Fixed point (1/512 of a pixel) world coordinates, same as in game.
+    x: i32,
+    y: i32,
+    event_num: u16,
+    flag_num: u16,
+    npc_flags: u16,
+    cond: u16,
+}
+
+/// Serializes the map and every alive NPC to JSON, for inspecting a running game with external tools.
+pub fn dump_state(stage_id: usize, map: &Map, npc_list: &NPCList) -> GameResult<String> {
+    let dump = StateDump {
+        version: STATE_DUMP_VERSION,
+        stage_id,
+        map: MapDump { width: map.width, height: map.height, tiles: &map.tiles },
+        npcs: npc_list
+            .iter_alive()
+            .map(|npc| NPCDump {
+                id: npc.id,
+                npc_type: npc.npc_type,
+                x: npc.x,
+                y: npc.y,
+                event_num: npc.event_num,
+                flag_num: npc.flag_num,
+                npc_flags: npc.npc_flags.0,
+                cond: npc.cond.0,
+            })
+            .collect(),
+    };
+
+    Ok(serde_json::to_string(&dump)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::map::CameraBoundaries;
+    use crate::game::npc::NPC;
+    use crate::game::shared_game_state::TileSize;
+
+    #[test]
+    fn state_dump_schema() {
+        let map = Map {
+            width: 2,
+            height: 2,
+            tiles: vec![1, 2, 3, 4],
+            attrib: [0; 0x100],
+            tile_size: TileSize::Tile16x16,
+            camera_boundaries: CameraBoundaries::default(),
+        };
+
+        let npc_list = NPCList::new();
+        let mut npc = NPC::empty();
+        npc.cond.set_alive(true);
+        npc.npc_type = 5;
+        npc.x = 0x400;
+        npc.y = 0x600;
+        npc.event_num = 7;
+        npc_list.spawn(0, npc).unwrap();
+
+        let dump: serde_json::Value = serde_json::from_str(&dump_state(13, &map, &npc_list).unwrap()).unwrap();
+        let keys = |value: &serde_json::Value| value.as_object().unwrap().keys().cloned().collect::<Vec<_>>();
+
+        assert_eq!(keys(&dump), ["map", "npcs", "stage_id", "version"]);
+        assert_eq!(dump["version"], STATE_DUMP_VERSION);
+        assert_eq!(dump["stage_id"], 13);
+
+        assert_eq!(keys(&dump["map"]), ["height", "tiles", "width"]);
+        assert_eq!(dump["map"]["width"], 2);
+        assert_eq!(dump["map"]["tiles"], serde_json::json!([1, 2, 3, 4]));
+
+        let npcs = dump["npcs"].as_array().unwrap();
+        assert_eq!(npcs.len(), 1);
+        assert_eq!(keys(&npcs[0]), ["cond", "event_num", "flag_num", "id", "npc_flags", "npc_type", "x", "y"]);
+        assert_eq!(npcs[0]["npc_type"], 5);
+        assert_eq!(npcs[0]["x"], 0x400);
+        assert_eq!(npcs[0]["y"], 0x600);
+        assert_eq!(npcs[0]["event_num"], 7);
+    }
+}
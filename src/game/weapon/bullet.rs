@@ -15,6 +15,8 @@ pub struct BulletManager {
     pub bullets: Vec<Bullet>,
     pub new_bullets: Vec<Bullet>,
     pub seeder: XorShift,
+    /// New bullets are refused once this many are alive, see `GameConsts::max_bullets`.
+    pub max_bullets: usize,
 }
 
 impl BulletManager {
@@ -24,9 +26,22 @@ impl BulletManager {
             bullets: Vec::with_capacity(64),
             new_bullets: Vec::with_capacity(8),
             seeder: XorShift::new(0x359c482f),
+            max_bullets: 64,
         }
     }
 
+    /// Makes bullets derive their randomness from given seed, so the same seed and inputs produce
+    /// the same bullet patterns.
+    pub fn reseed(&mut self, seed: i32) {
+        // xorshift never leaves the all-zeroes state
+        self.seeder = XorShift::new(if seed == 0 { 0x359c482f } else { seed });
+    }
+
+    /// Seeds bullets from the current state of `game_rng`, without advancing it.
+    pub fn reseed_from(&mut self, game_rng: &XorShift) {
+        self.reseed(game_rng.dump_state() as i32);
+    }
+
     pub fn create_bullet(
         &mut self,
         x: i32,
@@ -36,6 +51,10 @@ impl BulletManager {
         direction: Direction,
         constants: &EngineConstants,
     ) {
+        if self.is_full() {
+            return;
+        }
+
         let mut bullet = Bullet::new(x, y, btype, owner, direction, constants);
         bullet.rng = Xoroshiro32PlusPlus::new(self.seeder.next_u32());
 
@@ -43,6 +62,10 @@ impl BulletManager {
     }
 
     pub fn push_bullet(&mut self, mut bullet: Bullet) {
+        if self.is_full() {
+            return;
+        }
+
         bullet.rng = Xoroshiro32PlusPlus::new(self.seeder.next_u32());
         self.bullets.push(bullet);
     }
@@ -57,6 +80,7 @@ impl BulletManager {
                 bullet.tick(state, players, npc_list, &mut self.new_bullets);
             }
 
+            self.new_bullets.truncate(self.max_bullets.saturating_sub(self.bullets.len()));
             for bullet in &mut self.new_bullets {
                 bullet.rng = Xoroshiro32PlusPlus::new(self.seeder.next_u32());
             }
@@ -79,6 +103,11 @@ impl BulletManager {
         }
     }
 
+    /// Returns whether the bullet limit has been reached and new bullets won't be spawned.
+    pub fn is_full(&self) -> bool {
+        self.bullets.len() >= self.max_bullets
+    }
+
     pub fn count_bullets(&self, btype: u16, player_id: TargetPlayer) -> usize {
         self.bullets.iter().filter(|b| b.owner == player_id && b.btype == btype).count()
     }
@@ -144,6 +173,7 @@ impl Bullet {
             block_hit_width: 0,
             block_hit_height: 0,
             display_bounds: Rect { left: 0, top: 0, right: 0, bottom: 0 },
+            rotate_with_velocity: false,
         });
 
         Bullet {
@@ -484,6 +514,12 @@ impl Bullet {
         }
     }
 
+    /// Random velocity perpendicular to the direction of travel, in `[-magnitude, magnitude]`.
+    /// Small relative to the bullet speed, so it works out as a slight angle off the firing direction.
+    pub fn spread_velocity(&self, magnitude: i32) -> i32 {
+        self.rng.range(-magnitude..magnitude)
+    }
+
     fn tick_machine_gun(&mut self, state: &mut SharedGameState, npc_list: &NPCList) {
         self.action_counter += 1;
         if self.action_counter > self.lifetime {
@@ -498,19 +534,19 @@ impl Bullet {
             match self.direction {
                 Direction::Left => {
                     self.vel_x = -0x1000;
-                    self.vel_y = self.rng.range(-0xaa..0xaa);
+                    self.vel_y = self.spread_velocity(state.constants.weapon.machine_gun_spread);
                 }
                 Direction::Up => {
                     self.vel_y = -0x1000;
-                    self.vel_x = self.rng.range(-0xaa..0xaa);
+                    self.vel_x = self.spread_velocity(state.constants.weapon.machine_gun_spread);
                 }
                 Direction::Right => {
                     self.vel_x = 0x1000;
-                    self.vel_y = self.rng.range(-0xaa..0xaa);
+                    self.vel_y = self.spread_velocity(state.constants.weapon.machine_gun_spread);
                 }
                 Direction::Bottom => {
                     self.vel_y = 0x1000;
-                    self.vel_x = self.rng.range(-0xaa..0xaa);
+                    self.vel_x = self.spread_velocity(state.constants.weapon.machine_gun_spread);
                 }
                 Direction::FacingPlayer => unreachable!(),
             }
@@ -799,19 +835,19 @@ impl Bullet {
             match self.direction {
                 Direction::Left => {
                     self.vel_x = -0x600;
-                    self.vel_y = self.rng.range(-0x100..0x100);
+                    self.vel_y = self.spread_velocity(state.constants.weapon.bubbler_spread);
                 }
                 Direction::Up => {
                     self.vel_y = -0x600;
-                    self.vel_x = self.rng.range(-0x100..0x100);
+                    self.vel_x = self.spread_velocity(state.constants.weapon.bubbler_spread);
                 }
                 Direction::Right => {
                     self.vel_x = 0x600;
-                    self.vel_y = self.rng.range(-0x100..0x100);
+                    self.vel_y = self.spread_velocity(state.constants.weapon.bubbler_spread);
                 }
                 Direction::Bottom => {
                     self.vel_y = 0x600;
-                    self.vel_x = self.rng.range(-0x100..0x100);
+                    self.vel_x = self.spread_velocity(state.constants.weapon.bubbler_spread);
                 }
                 Direction::FacingPlayer => unreachable!(),
             }
@@ -140,7 +140,7 @@ impl Weapon {
             if self.experience >= lvl_table[2] {
                 self.experience = lvl_table[2];
 
-                if player.equip.has_whimsical_star() && player.stars < 3 {
+                if player.equip.has_whimsical_star() && player.stars < state.constants.player.max_stars {
                     player.stars += 1;
                 }
             }
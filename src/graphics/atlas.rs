@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use crate::common::Rect;
+use crate::framework::backend::BackendTexture;
+use crate::framework::context::Context;
+use crate::framework::error::{GameError, GameResult};
+use crate::framework::graphics;
+
+struct AtlasImage {
+    rect: Rect<u16>,
+    data: Vec<u8>,
+}
+
+/// Packs several small RGBA images into a single texture, so sprites coming from them can be drawn
+/// without switching textures. Images are placed left to right on shelves as tall as the tallest image
+/// on them, a new shelf is started below when the current one is full.
+pub struct Atlas {
+    width: u16,
+    height: u16,
+    shelf_x: u16,
+    shelf_y: u16,
+    shelf_height: u16,
+    images: HashMap<String, AtlasImage>,
+}
+
+impl Atlas {
+    /// Creates an empty atlas of given width, the height grows as images are added.
+    pub fn new(width: u16) -> Atlas {
+        Atlas { width, height: 0, shelf_x: 0, shelf_y: 0, shelf_height: 0, images: HashMap::new() }
+    }
+
+    /// Adds an image and returns the rect it will occupy in the built texture.
+    pub fn add(&mut self, name: &str, data: &[u8], width: u16, height: u16) -> GameResult<Rect<u16>> {
+        if data.len() != width as usize * height as usize * 4 {
+            return Err(GameError::InvalidValue(format!("Image data of {} doesn't match its size.", name)));
+        }
+
+        if width > self.width {
+            return Err(GameError::InvalidValue(format!("{} is wider than the atlas.", name)));
+        }
+
+        if self.images.contains_key(name) {
+            return Err(GameError::InvalidValue(format!("{} is already in the atlas.", name)));
+        }
+
+        if self.shelf_x + width > self.width {
+            self.shelf_y += self.shelf_height;
+            self.shelf_x = 0;
+            self.shelf_height = 0;
+        }
+
+        let bottom = self
+            .shelf_y
+            .checked_add(height)
+            .ok_or_else(|| GameError::InvalidValue("Atlas height overflow.".to_owned()))?;
+        let rect = Rect::new(self.shelf_x, self.shelf_y, self.shelf_x + width, bottom);
+
+        self.shelf_x += width;
+        self.shelf_height = self.shelf_height.max(height);
+        self.height = self.height.max(bottom);
+        self.images.insert(name.to_owned(), AtlasImage { rect, data: data.to_vec() });
+
+        Ok(rect)
+    }
+
+    /// Returns where given image was placed.
+    pub fn get(&self, name: &str) -> Option<Rect<u16>> {
+        self.images.get(name).map(|image| image.rect)
+    }
+
+    pub fn dimensions(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    /// Composes all added images into one texture.
+    pub fn build(&self, ctx: &mut Context) -> GameResult<Box<dyn BackendTexture>> {
+        let stride = self.width as usize * 4;
+        let mut pixels = vec![0u8; stride * self.height as usize];
+
+        for image in self.images.values() {
+            let row_len = image.rect.width() as usize * 4;
+
+            for (y, row) in image.data.chunks_exact(row_len).enumerate() {
+                let offset = (image.rect.top as usize + y) * stride + image.rect.left as usize * 4;
+                pixels[offset..offset + row_len].copy_from_slice(row);
+            }
+        }
+
+        graphics::create_texture(ctx, self.width, self.height.max(1), &pixels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(r: &Rect<u16>) -> (u16, u16, u16, u16) {
+        (r.left, r.top, r.right, r.bottom)
+    }
+
+    fn overlaps(a: &Rect<u16>, b: &Rect<u16>) -> bool {
+        a.left < b.right && b.left < a.right && a.top < b.bottom && b.top < a.bottom
+    }
+
+    #[test]
+    fn test_shelf_packing() {
+        let mut atlas = Atlas::new(64);
+        let sizes = [(32, 16), (16, 8), (16, 24), (40, 8), (64, 4), (8, 8)];
+        let rects: Vec<Rect<u16>> = sizes
+            .iter()
+            .enumerate()
+            .map(|(i, &(w, h))| atlas.add(&i.to_string(), &vec![0; w as usize * h as usize * 4], w, h).unwrap())
+            .collect();
+
+        // first shelf is 24px tall because of the third image
+        assert_eq!(bounds(&rects[0]), (0, 0, 32, 16));
+        assert_eq!(bounds(&rects[2]), (48, 0, 64, 24));
+        assert_eq!(bounds(&rects[3]), (0, 24, 40, 32));
+        assert_eq!(bounds(&rects[4]), (0, 32, 64, 36));
+        assert_eq!(atlas.dimensions(), (64, 36));
+
+        for (i, a) in rects.iter().enumerate() {
+            assert!(a.right <= 64);
+            for b in &rects[i + 1..] {
+                assert!(!overlaps(a, b));
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_invalid_images() {
+        let mut atlas = Atlas::new(16);
+
+        assert!(atlas.add("wide", &[0; 32 * 4], 32, 1).is_err());
+        assert!(atlas.add("short", &[0; 3], 1, 1).is_err());
+        assert!(atlas.add("ok", &[0; 4], 1, 1).is_ok());
+        assert!(atlas.add("ok", &[0; 4], 1, 1).is_err());
+    }
+}
@@ -5,14 +5,14 @@ use std::path::PathBuf;
 
 use byteorder::{LE, ReadBytesExt};
 
-use crate::common::{FILE_TYPES, Rect};
+use crate::common::{Color, FILE_TYPES, Rect};
 use crate::engine_constants::EngineConstants;
 use crate::framework::context::Context;
 use crate::framework::error::GameError::ResourceLoadError;
 use crate::framework::error::GameResult;
-use crate::framework::filesystem;
+use crate::framework::{filesystem, graphics};
 use crate::graphics::font::{EMPTY_SYMBOLS, Font, Symbols, TextBuilderFlag};
-use crate::graphics::texture_set::TextureSet;
+use crate::graphics::texture_set::{TextureSet, I_MAG};
 
 #[derive(Debug)]
 pub struct BMChar {
@@ -127,6 +127,43 @@ impl BMFontMetadata {
 
         Ok(Self { pages, font_size, line_height, base, chars })
     }
+
+    pub fn has_glyph(&self, chr: char) -> bool {
+        self.chars.contains_key(&chr)
+    }
+
+    /// Horizontal advance of given character in unscaled pixels. Characters missing from the font
+    /// advance by half of the font size, or by the full font size if they're full-width (CJK etc.).
+    pub fn advance(&self, chr: char) -> i16 {
+        if let Some(glyph) = self.chars.get(&chr) {
+            return glyph.x_advance;
+        }
+
+        // negative font size means it's matching the cell height, the magnitude is what we care about
+        let size = self.font_size.abs();
+        if is_full_width(chr) {
+            size
+        } else {
+            size / 2
+        }
+    }
+
+    /// Returns the width of given text in unscaled pixels.
+    pub fn measure(&self, text: &str) -> u32 {
+        text.chars().map(|chr| self.advance(chr) as i32).sum::<i32>().max(0) as u32
+    }
+}
+
+/// Whether the character takes up two columns, like CJK text does.
+pub(crate) fn is_full_width(chr: char) -> bool {
+    matches!(chr as u32,
+        0x1100..=0x115f // Hangul Jamo
+        | 0x2e80..=0xa4cf // CJK radicals, punctuation, kana, ideographs, Yi
+        | 0xac00..=0xd7a3 // Hangul syllables
+        | 0xf900..=0xfaff // CJK compatibility ideographs
+        | 0xfe30..=0xfe4f // CJK compatibility forms
+        | 0xff00..=0xff60 // fullwidth forms
+        | 0xffe0..=0xffe6)
 }
 
 pub struct BMFont {
@@ -140,6 +177,10 @@ impl Font for BMFont {
         self.font.line_height as f32 * self.font_scale
     }
 
+    fn has_glyph(&self, chr: char) -> bool {
+        self.font.has_glyph(chr)
+    }
+
     fn compute_width(&self, text: &mut dyn Iterator<Item = char>, symbols: Option<&Symbols>) -> f32 {
         let mut offset_x = 0.0;
 
@@ -149,15 +190,13 @@ impl Font for BMFont {
 
                 if let Some((_, rect)) = rect_map_entry {
                     offset_x += rect.width() as f32;
-                } else if let Some(glyph) = self.font.chars.get(&chr) {
-                    offset_x += glyph.x_advance as f32 * self.font_scale;
+                } else {
+                    offset_x += self.font.advance(chr) as f32 * self.font_scale;
                 }
             }
         } else {
             for chr in text {
-                if let Some(glyph) = self.font.chars.get(&chr) {
-                    offset_x += glyph.x_advance as f32 * self.font_scale;
-                }
+                offset_x += self.font.advance(chr) as f32 * self.font_scale;
             }
         }
 
@@ -172,6 +211,7 @@ impl Font for BMFont {
         scale: f32,
         box_width: f32,
         shadow_color: (u8, u8, u8, u8),
+        shadow_offset: (f32, f32),
         color: (u8, u8, u8, u8),
         flags: TextBuilderFlag,
         constants: &EngineConstants,
@@ -196,8 +236,8 @@ impl Font for BMFont {
             if flags.shadow() {
                 self.draw_text_line(
                     &mut TEXT_BUF.iter().copied(),
-                    x + scale,
-                    y + scale,
+                    x + shadow_offset.0 * scale,
+                    y + shadow_offset.1 * scale,
                     scale,
                     shadow_color,
                     constants,
@@ -269,9 +309,11 @@ impl BMFont {
     ) -> GameResult {
         unsafe {
             static mut RECTS_BUF: Vec<(f32, f32, *const Rect<u16>)> = Vec::new();
+            static mut MISSING_BUF: Vec<(f32, f32)> = Vec::new();
 
             let syms = symbols.unwrap_or(&EMPTY_SYMBOLS);
             RECTS_BUF.clear();
+            MISSING_BUF.clear();
 
             if self.pages.len() == 1 {
                 let batch = texture_set.get_or_load_batch(ctx, constants, self.pages.get(0).unwrap())?;
@@ -305,6 +347,12 @@ impl BMFont {
 
                             offset_x += glyph.x_advance as f32 * self.font_scale * scale;
                         }
+                    } else {
+                        let advance = self.font.advance(chr) as f32 * self.font_scale * scale;
+                        if !chr.is_whitespace() && !chr.is_control() {
+                            MISSING_BUF.push((offset_x, advance));
+                        }
+                        offset_x += advance;
                     }
                 }
 
@@ -314,12 +362,19 @@ impl BMFont {
                 let mut chars = Vec::new();
 
                 for chr in iter {
-                    if let Some(glyph) = self.font.chars.get(&chr) {
+                    let glyph = self.font.chars.get(&chr);
+                    if let Some(glyph) = glyph {
                         pages.insert(glyph.page);
-                        chars.push((chr, glyph));
                     }
+                    chars.push((chr, glyph));
+                }
+
+                // make sure placeholders are still laid out if none of the characters are in the font
+                if pages.is_empty() {
+                    pages.insert(0);
                 }
 
+                let mut first_page = true;
                 for page in pages {
                     let page_tex = if let Some(p) = self.pages.get(page as usize) {
                         p
@@ -331,6 +386,17 @@ impl BMFont {
                     let mut offset_x = x;
 
                     for (chr, glyph) in chars.iter() {
+                        let glyph = if let Some(glyph) = glyph {
+                            glyph
+                        } else {
+                            let advance = scale * (self.font.advance(*chr) as f32 * self.font_scale);
+                            if first_page && !chr.is_whitespace() && !chr.is_control() {
+                                MISSING_BUF.push((offset_x, advance));
+                            }
+                            offset_x += advance;
+                            continue;
+                        };
+
                         let rect_map_entry = syms.symbols.iter().find(|(c, _)| *c == *chr);
 
                         if let Some((_, rect)) = rect_map_entry {
@@ -358,6 +424,7 @@ impl BMFont {
                     }
 
                     batch.draw(ctx)?;
+                    first_page = false;
                 }
             }
 
@@ -370,6 +437,21 @@ impl BMFont {
 
                 sprite_batch.draw(ctx)?;
             }
+
+            if !MISSING_BUF.is_empty() {
+                let (r, g, b, a) = color;
+                let height = self.line_height() * scale;
+
+                for &(x, advance) in MISSING_BUF.iter() {
+                    let rect = Rect::new_size(
+                        ((x + scale) * I_MAG) as isize,
+                        ((y + scale) * I_MAG) as isize,
+                        ((advance - scale * 2.0).max(scale) * I_MAG) as isize,
+                        ((height - scale * 2.0).max(scale) * I_MAG) as isize,
+                    );
+                    graphics::draw_outline_rect(ctx, rect, I_MAG.max(1.0) as usize, Color::from_rgba(r, g, b, a))?;
+                }
+            }
         }
 
         Ok(())
@@ -383,3 +465,56 @@ impl BMFont {
         self.font_scale
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_font() -> BMFontMetadata {
+        let mut chars = HashMap::new();
+        let glyph = |x_advance| BMChar {
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+            x_offset: 0,
+            y_offset: 0,
+            x_advance,
+            page: 0,
+            channel: 0,
+        };
+        chars.insert('A', glyph(6));
+        chars.insert(' ', glyph(4));
+        chars.insert('あ', glyph(12));
+
+        BMFontMetadata { pages: 1, font_size: -12, line_height: 12, base: 10, chars }
+    }
+
+    #[test]
+    fn test_has_glyph() {
+        let font = test_font();
+
+        assert!(font.has_glyph('A'));
+        assert!(font.has_glyph('あ'));
+        assert!(!font.has_glyph('é'));
+    }
+
+    #[test]
+    fn test_measure_ascii() {
+        let font = test_font();
+
+        assert_eq!(font.measure(""), 0);
+        assert_eq!(font.measure("A A"), 16);
+    }
+
+    #[test]
+    fn test_measure_mixed_width() {
+        let font = test_font();
+
+        assert_eq!(font.measure("Aあ"), 18);
+        // missing glyphs: 'B' is half-width, 'い' is full-width
+        assert_eq!(font.measure("B"), 6);
+        assert_eq!(font.measure("い"), 12);
+        assert_eq!(font.measure("AいBあ"), 36);
+    }
+}
@@ -32,6 +32,9 @@ pub trait Font {
 
     fn line_height(&self) -> f32;
 
+    /// Returns true if the font can draw given character, missing ones are drawn as an empty box.
+    fn has_glyph(&self, chr: char) -> bool;
+
     fn compute_width(&self, text: &mut dyn Iterator<Item = char>, symbols: Option<&Symbols>) -> f32;
 
     fn draw(
@@ -42,6 +45,7 @@ pub trait Font {
         scale: f32,
         box_width: f32,
         shadow_color: (u8, u8, u8, u8),
+        shadow_offset: (f32, f32),
         color: (u8, u8, u8, u8),
         flags: TextBuilderFlag,
         constants: &EngineConstants,
@@ -57,6 +61,7 @@ pub struct TextBuilder<'a, 'b> {
     y: f32,
     scale: f32,
     shadow_color: (u8, u8, u8, u8),
+    shadow_offset: (f32, f32),
     color: (u8, u8, u8, u8),
     flags: TextBuilderFlag,
     box_width: f32,
@@ -73,6 +78,7 @@ impl<'a, 'b> TextBuilder<'a, 'b> {
             y: 0.0,
             scale: 1.0,
             shadow_color: (0, 0, 0, 150),
+            shadow_offset: (1.0, 1.0),
             color: (255, 255, 255, 255),
             flags: TextBuilderFlag(0),
             box_width: 0.0,
@@ -126,6 +132,18 @@ impl<'a, 'b> TextBuilder<'a, 'b> {
         self.shadow_color
     }
 
+    /// Sets how far the shadow is drawn from the text, in unscaled pixels. Defaults to `(1.0, 1.0)`.
+    #[inline]
+    pub const fn shadow_offset(mut self, x: f32, y: f32) -> Self {
+        self.shadow_offset = (x, y);
+        self
+    }
+
+    #[inline]
+    pub const fn get_shadow_offset(&self) -> (f32, f32) {
+        self.shadow_offset
+    }
+
     #[inline]
     pub const fn scale(mut self, scale: f32) -> Self {
         self.scale = scale;
@@ -197,6 +215,7 @@ impl<'a, 'b> TextBuilder<'a, 'b> {
             self.scale,
             self.box_width,
             self.shadow_color,
+            self.shadow_offset,
             self.color,
             self.flags,
             constants,
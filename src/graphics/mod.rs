@@ -1,3 +1,4 @@
+pub mod atlas;
 pub mod bmfont;
 pub mod font;
 pub mod texture_set;
@@ -1,9 +1,9 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::{BufReader, Read, Seek, SeekFrom};
 
 use image::RgbaImage;
 use itertools::Itertools;
-use log::info;
+use log::{info, warn};
 
 use crate::common;
 use crate::common::{FILE_TYPES, Rect};
@@ -12,7 +12,7 @@ use crate::framework::backend::{BackendTexture, SpriteBatchCommand};
 use crate::framework::context::Context;
 use crate::framework::error::{GameError, GameResult};
 use crate::framework::filesystem;
-use crate::framework::graphics::{create_texture, FilterMode};
+use crate::framework::graphics::{create_texture, create_texture_keep_pixels, warm_texture, FilterMode};
 
 pub static mut I_MAG: f32 = 1.0;
 pub static mut G_MAG: f32 = 1.0;
@@ -74,11 +74,32 @@ pub trait SpriteBatch {
         rect: &common::Rect<u16>,
     );
 
+    /// Same as `add_rect`, but the destination position is kept subpixel-accurate on all backends.
+    fn add_rect_f(&mut self, x: f32, y: f32, rect: &common::Rect<u16>);
+
+    /// Draws the rect with a hue shift (in degrees) and saturation/value multipliers applied.
+    /// On backends that recolor on the CPU, the texture must be loaded with `TextureSet::set_keep_pixels`,
+    /// otherwise it's drawn unshifted.
+    fn add_rect_hsv(&mut self, x: f32, y: f32, hue_shift: f32, sat_mul: f32, val_mul: f32, rect: &common::Rect<u16>);
+
+    /// Draws the rect stretched onto a quad, corners go clockwise from top-left. See `SpriteBatchCommand::DrawQuad`.
+    fn add_quad(&mut self, corners: [(f32, f32); 4], rect: &common::Rect<u16>);
+
+    /// Draws the rect rotated by `angle` degrees clockwise around its center, optionally flipped.
+    fn add_rect_ex(&mut self, x: f32, y: f32, angle: f32, flip_x: bool, flip_y: bool, rect: &common::Rect<u16>);
+
+    /// Same as `add_rect`, drawn in order of `z` once the batch has z sorting enabled.
+    fn add_rect_z(&mut self, x: f32, y: f32, z: i16, rect: &common::Rect<u16>);
+
+    fn set_sort_by_z(&mut self, enabled: bool);
+
     fn draw(&mut self, ctx: &mut Context) -> GameResult;
 
     fn draw_filtered(&mut self, _filter: FilterMode, _ctx: &mut Context) -> GameResult;
 
     fn get_texture(&self) -> Option<&Box<dyn BackendTexture>>;
+
+    fn get_texture_mut(&mut self) -> Option<&mut Box<dyn BackendTexture>>;
 }
 
 pub struct DummyBatch;
@@ -148,6 +169,27 @@ impl SpriteBatch for DummyBatch {
         _rect: &Rect<u16>,
     ) {}
 
+    fn add_rect_f(&mut self, _x: f32, _y: f32, _rect: &Rect<u16>) {}
+
+    fn add_rect_hsv(
+        &mut self,
+        _x: f32,
+        _y: f32,
+        _hue_shift: f32,
+        _sat_mul: f32,
+        _val_mul: f32,
+        _rect: &Rect<u16>,
+    ) {
+    }
+
+    fn add_quad(&mut self, _corners: [(f32, f32); 4], _rect: &Rect<u16>) {}
+
+    fn add_rect_ex(&mut self, _x: f32, _y: f32, _angle: f32, _flip_x: bool, _flip_y: bool, _rect: &Rect<u16>) {}
+
+    fn add_rect_z(&mut self, _x: f32, _y: f32, _z: i16, _rect: &Rect<u16>) {}
+
+    fn set_sort_by_z(&mut self, _enabled: bool) {}
+
     fn draw(&mut self, _ctx: &mut Context) -> GameResult {
         Ok(())
     }
@@ -159,6 +201,10 @@ impl SpriteBatch for DummyBatch {
     fn get_texture(&self) -> Option<&Box<dyn BackendTexture>> {
         None
     }
+
+    fn get_texture_mut(&mut self) -> Option<&mut Box<dyn BackendTexture>> {
+        None
+    }
 }
 
 pub struct SubBatch {
@@ -360,6 +406,138 @@ impl SpriteBatch for SubBatch {
         ));
     }
 
+    fn add_rect_f(&mut self, x: f32, y: f32, rect: &common::Rect<u16>) {
+        if (rect.right.saturating_sub(rect.left)) == 0 || (rect.bottom.saturating_sub(rect.top)) == 0 {
+            return;
+        }
+
+        let mag = unsafe { I_MAG };
+
+        self.batch.add(SpriteBatchCommand::DrawRectF(
+            Rect {
+                left: rect.left as f32 / self.scale_x,
+                top: rect.top as f32 / self.scale_y,
+                right: rect.right as f32 / self.scale_x,
+                bottom: rect.bottom as f32 / self.scale_y,
+            },
+            Rect {
+                left: x * mag,
+                top: y * mag,
+                right: (x + rect.width() as f32) * mag,
+                bottom: (y + rect.height() as f32) * mag,
+            },
+        ));
+    }
+
+    fn add_rect_hsv(
+        &mut self,
+        x: f32,
+        y: f32,
+        hue_shift: f32,
+        sat_mul: f32,
+        val_mul: f32,
+        rect: &common::Rect<u16>,
+    ) {
+        if (rect.right.saturating_sub(rect.left)) == 0 || (rect.bottom.saturating_sub(rect.top)) == 0 {
+            return;
+        }
+
+        let mag = unsafe { I_MAG };
+
+        self.batch.add(SpriteBatchCommand::DrawRectHsv(
+            Rect {
+                left: rect.left as f32 / self.scale_x,
+                top: rect.top as f32 / self.scale_y,
+                right: rect.right as f32 / self.scale_x,
+                bottom: rect.bottom as f32 / self.scale_y,
+            },
+            Rect {
+                left: x * mag,
+                top: y * mag,
+                right: (x + rect.width() as f32) * mag,
+                bottom: (y + rect.height() as f32) * mag,
+            },
+            hue_shift,
+            sat_mul,
+            val_mul,
+        ));
+    }
+
+    fn add_quad(&mut self, corners: [(f32, f32); 4], rect: &common::Rect<u16>) {
+        if (rect.right.saturating_sub(rect.left)) == 0 || (rect.bottom.saturating_sub(rect.top)) == 0 {
+            return;
+        }
+
+        let mag = unsafe { I_MAG };
+
+        self.batch.add(SpriteBatchCommand::DrawQuad(
+            Rect {
+                left: rect.left as f32 / self.scale_x,
+                top: rect.top as f32 / self.scale_y,
+                right: rect.right as f32 / self.scale_x,
+                bottom: rect.bottom as f32 / self.scale_y,
+            },
+            corners.map(|(x, y)| (x * mag, y * mag)),
+        ));
+    }
+
+    fn add_rect_ex(&mut self, x: f32, y: f32, angle: f32, flip_x: bool, flip_y: bool, rect: &common::Rect<u16>) {
+        if (rect.right.saturating_sub(rect.left)) == 0 || (rect.bottom.saturating_sub(rect.top)) == 0 {
+            return;
+        }
+
+        let mag = unsafe { I_MAG };
+
+        self.batch.add(SpriteBatchCommand::DrawRectEx(
+            Rect {
+                left: rect.left as f32 / self.scale_x,
+                top: rect.top as f32 / self.scale_y,
+                right: rect.right as f32 / self.scale_x,
+                bottom: rect.bottom as f32 / self.scale_y,
+            },
+            Rect {
+                left: x * mag,
+                top: y * mag,
+                right: (x + rect.width() as f32) * mag,
+                bottom: (y + rect.height() as f32) * mag,
+            },
+            angle,
+            None,
+            flip_x,
+            flip_y,
+        ));
+    }
+
+    fn add_rect_z(&mut self, x: f32, y: f32, z: i16, rect: &common::Rect<u16>) {
+        if (rect.right.saturating_sub(rect.left)) == 0 || (rect.bottom.saturating_sub(rect.top)) == 0 {
+            return;
+        }
+
+        let mag = unsafe { I_MAG };
+
+        self.batch.add_z(
+            z,
+            SpriteBatchCommand::DrawRect(
+                Rect {
+                    left: rect.left as f32 / self.scale_x,
+                    top: rect.top as f32 / self.scale_y,
+                    right: rect.right as f32 / self.scale_x,
+                    bottom: rect.bottom as f32 / self.scale_y,
+                },
+                Rect {
+                    left: x * mag,
+                    top: y * mag,
+                    right: (x + rect.width() as f32) * mag,
+                    bottom: (y + rect.height() as f32) * mag,
+                },
+            ),
+        );
+    }
+
+    fn set_sort_by_z(&mut self, enabled: bool) {
+        self.batch.set_sort_by_z(enabled);
+    }
+
     #[inline(always)]
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
         self.draw_filtered(FilterMode::Nearest, ctx)
@@ -375,6 +553,10 @@ impl SpriteBatch for SubBatch {
     fn get_texture(&self) -> Option<&Box<dyn BackendTexture>> {
         Some(&self.batch)
     }
+
+    fn get_texture_mut(&mut self) -> Option<&mut Box<dyn BackendTexture>> {
+        Some(&mut self.batch)
+    }
 }
 
 impl SpriteBatch for CombinedBatch {
@@ -462,6 +644,30 @@ impl SpriteBatch for CombinedBatch {
         self.main_batch.add_rect_scaled_tinted(x, y, color, scale_x, scale_y, rect)
     }
 
+    fn add_rect_f(&mut self, x: f32, y: f32, rect: &Rect<u16>) {
+        self.main_batch.add_rect_f(x, y, rect)
+    }
+
+    fn add_rect_hsv(&mut self, x: f32, y: f32, hue_shift: f32, sat_mul: f32, val_mul: f32, rect: &Rect<u16>) {
+        self.main_batch.add_rect_hsv(x, y, hue_shift, sat_mul, val_mul, rect)
+    }
+
+    fn add_quad(&mut self, corners: [(f32, f32); 4], rect: &Rect<u16>) {
+        self.main_batch.add_quad(corners, rect)
+    }
+
+    fn add_rect_ex(&mut self, x: f32, y: f32, angle: f32, flip_x: bool, flip_y: bool, rect: &Rect<u16>) {
+        self.main_batch.add_rect_ex(x, y, angle, flip_x, flip_y, rect)
+    }
+
+    fn add_rect_z(&mut self, x: f32, y: f32, z: i16, rect: &Rect<u16>) {
+        self.main_batch.add_rect_z(x, y, z, rect)
+    }
+
+    fn set_sort_by_z(&mut self, enabled: bool) {
+        self.main_batch.set_sort_by_z(enabled)
+    }
+
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
         self.main_batch.draw(ctx)
     }
@@ -473,20 +679,125 @@ impl SpriteBatch for CombinedBatch {
     fn get_texture(&self) -> Option<&Box<dyn BackendTexture>> {
         self.main_batch.get_texture()
     }
+
+    fn get_texture_mut(&mut self) -> Option<&mut Box<dyn BackendTexture>> {
+        self.main_batch.get_texture_mut()
+    }
+}
+
+struct TextureUsage {
+    bytes: usize,
+    last_used: u64,
+    /// Set for textures given to `replace`, they can't be reloaded so they're never evicted.
+    pinned: bool,
 }
 
 pub struct TextureSet {
     pub tex_map: HashMap<String, Box<dyn SpriteBatch>>,
     dummy_batch: Box<dyn SpriteBatch>,
+    generation: u32,
+    usage: HashMap<String, TextureUsage>,
+    memory_budget: Option<usize>,
+    frame: u64,
+    /// Textures loaded with a CPU-side copy of their pixels, see `set_keep_pixels`.
+    keep_pixels: HashSet<String>,
 }
 
 impl TextureSet {
     pub fn new() -> TextureSet {
-        TextureSet { tex_map: HashMap::new(), dummy_batch: Box::new(DummyBatch) }
+        TextureSet {
+            tex_map: HashMap::new(),
+            dummy_batch: Box::new(DummyBatch),
+            generation: 0,
+            usage: HashMap::new(),
+            memory_budget: None,
+            frame: 0,
+            keep_pixels: HashSet::new(),
+        }
+    }
+
+    /// Makes given texture keep a CPU-side copy of its pixels, which doubles its memory usage. It's needed for
+    /// `BackendTexture::alpha_at`, HSV shifted drawing and `BackendTexture::into_render_target`. If the texture
+    /// is already loaded, it's reloaded on next use, unless it was replaced with `replace`.
+    pub fn set_keep_pixels(&mut self, name: &str, keep: bool) {
+        let changed = if keep { self.keep_pixels.insert(name.to_owned()) } else { self.keep_pixels.remove(name) };
+        let replaced = self.usage.get(name).map_or(false, |u| u.pinned);
+
+        if changed && !replaced {
+            self.revert(name);
+        }
+    }
+
+    pub fn keeps_pixels(&self, name: &str) -> bool {
+        self.keep_pixels.contains(name)
     }
 
     pub fn unload_all(&mut self) {
         self.tex_map.clear();
+        self.usage.clear();
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Marks the start of a new frame, textures used in the current frame are never evicted.
+    pub fn begin_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// Limits the memory used by loaded textures, in bytes. When a newly loaded texture goes over the budget,
+    /// the least recently drawn textures are unloaded until it fits again, they're reloaded on next use.
+    /// `None` (the default) never unloads anything.
+    pub fn set_memory_budget(&mut self, budget: Option<usize>) {
+        self.memory_budget = budget;
+        self.evict_over_budget();
+    }
+
+    pub fn memory_budget(&self) -> Option<usize> {
+        self.memory_budget
+    }
+
+    /// Estimated memory used by loaded textures, in bytes.
+    pub fn memory_usage(&self) -> usize {
+        self.usage.values().map(|u| u.bytes).sum()
+    }
+
+    fn texture_bytes(batch: &mut Box<dyn SpriteBatch>) -> usize {
+        let (width, height) = batch.real_dimensions();
+        let glow = batch.glow().map(|g| g.real_dimensions()).map_or(0, |(w, h)| w * h * 4);
+
+        width * height * 4 + glow
+    }
+
+    fn evict_over_budget(&mut self) {
+        let budget = match self.memory_budget {
+            Some(budget) => budget,
+            None => return,
+        };
+
+        let mut usage = self.memory_usage();
+        while usage > budget {
+            let victim = self
+                .usage
+                .iter()
+                .filter(|(_, u)| !u.pinned && u.last_used < self.frame)
+                .min_by_key(|(_, u)| u.last_used)
+                .map(|(name, _)| name.clone());
+
+            let name = match victim {
+                Some(name) => name,
+                None => break,
+            };
+
+            info!("Unloading texture to stay within memory budget: {}", name);
+            if let Some(u) = self.usage.remove(&name) {
+                usage -= u.bytes;
+            }
+            self.tex_map.remove(&name);
+        }
+    }
+
+    /// Incremented every time the textures are unloaded, lets caches of rendered textures know they're stale.
+    pub fn generation(&self) -> u32 {
+        self.generation
     }
 
     fn make_transparent(rgba: &mut RgbaImage) {
@@ -497,7 +808,13 @@ impl TextureSet {
         }
     }
 
-    fn load_image(&self, ctx: &mut Context, roots: &Vec<String>, path: &str) -> GameResult<Box<dyn BackendTexture>> {
+    fn load_image(
+        &self,
+        ctx: &mut Context,
+        roots: &Vec<String>,
+        path: &str,
+        keep_pixels: bool,
+    ) -> GameResult<Box<dyn BackendTexture>> {
         let img = {
             let mut buf = [0u8; 8];
             let mut reader = filesystem::open_find(ctx, roots, path)?;
@@ -513,13 +830,49 @@ impl TextureSet {
         };
         let (width, height) = img.dimensions();
 
-        create_texture(ctx, width as u16, height as u16, &img)
+        if keep_pixels {
+            create_texture_keep_pixels(ctx, width as u16, height as u16, &img)
+        } else {
+            create_texture(ctx, width as u16, height as u16, &img)
+        }
     }
 
     pub fn find_texture(&self, ctx: &mut Context, roots: &Vec<String>, name: &str) -> Option<String> {
         FILE_TYPES.iter().map(|ext| [name, ext].join("")).find(|path| filesystem::exists_find(ctx, roots, path))
     }
 
+    fn make_batch(name: &str, constants: &EngineConstants, batch: Box<dyn BackendTexture>) -> SubBatch {
+        let size = batch.dimensions();
+
+        let orig_dimensions = constants.tex_sizes.get(name).unwrap_or(&size);
+
+        let scale =
+            if f32::abs((orig_dimensions.0 as f32 / size.0 as f32) - (orig_dimensions.1 as f32 / size.1 as f32))
+                <= f32::EPSILON
+            {
+                orig_dimensions.0 as f32 / size.0 as f32
+            } else if constants.is_cs_plus && constants.base_paths.iter().any(|p| p.contains("/ogph")) {
+                1.0
+            } else if constants.is_cs_plus {
+                0.5
+            } else {
+                1.0
+            };
+
+        let width = (size.0 as f32 * scale) as _;
+        let height = (size.1 as f32 * scale) as _;
+
+        SubBatch {
+            batch,
+            width,
+            height,
+            scale_x: scale,
+            scale_y: scale,
+            real_width: size.0 as _,
+            real_height: size.1 as _,
+        }
+    }
+
     pub fn load_texture(
         &self,
         ctx: &mut Context,
@@ -534,41 +887,11 @@ impl TextureSet {
 
         info!("Loading texture: {} -> {}", name, path);
 
-        fn make_batch(name: &str, constants: &EngineConstants, batch: Box<dyn BackendTexture>) -> SubBatch {
-            let size = batch.dimensions();
-
-            let orig_dimensions = constants.tex_sizes.get(name).unwrap_or(&size);
-
-            let scale =
-                if f32::abs((orig_dimensions.0 as f32 / size.0 as f32) - (orig_dimensions.1 as f32 / size.1 as f32))
-                    <= f32::EPSILON
-                {
-                    orig_dimensions.0 as f32 / size.0 as f32
-                } else if constants.is_cs_plus && constants.base_paths.iter().any(|p| p.contains("/ogph")) {
-                    1.0
-                } else if constants.is_cs_plus {
-                    0.5
-                } else {
-                    1.0
-                };
-
-            let width = (size.0 as f32 * scale) as _;
-            let height = (size.1 as f32 * scale) as _;
-
-            SubBatch {
-                batch,
-                width,
-                height,
-                scale_x: scale,
-                scale_y: scale,
-                real_width: size.0 as _,
-                real_height: size.1 as _,
-            }
-        }
-
-        let main_batch = make_batch(name, constants, self.load_image(ctx, &constants.base_paths, &path)?);
+        let main_texture = self.load_image(ctx, &constants.base_paths, &path, self.keeps_pixels(name))?;
+        let main_batch = TextureSet::make_batch(name, constants, main_texture);
         let glow_batch = if let Some(glow_path) = glow_path {
-            self.load_image(ctx, &constants.base_paths, &glow_path).ok().map(|b| make_batch(name, constants, b))
+            let glow = self.load_image(ctx, &constants.base_paths, &glow_path, false).ok();
+            glow.map(|b| TextureSet::make_batch(name, constants, b))
         } else {
             None
         };
@@ -587,10 +910,175 @@ impl TextureSet {
         }
 
         if !self.tex_map.contains_key(name) {
-            let batch = self.load_texture(ctx, constants, name)?;
+            let mut batch = self.load_texture(ctx, constants, name)?;
+            let mut bytes = TextureSet::texture_bytes(&mut batch);
+            if self.keeps_pixels(name) {
+                let (width, height) = batch.real_dimensions();
+                bytes += width * height * 4;
+            }
             self.tex_map.insert(name.to_owned(), batch);
+            self.usage.insert(name.to_owned(), TextureUsage { bytes, last_used: self.frame, pinned: false });
+            self.evict_over_budget();
+        } else if let Some(usage) = self.usage.get_mut(name) {
+            usage.last_used = self.frame;
         }
 
         Ok(self.tex_map.get_mut(name).unwrap())
     }
+
+    /// Loads given texture with a copy of its pixels and turns it into a render target, see
+    /// `BackendTexture::into_render_target`. The converted texture is never evicted, since reloading it would
+    /// lose whatever was rendered into it, `revert` drops it.
+    pub fn make_render_target(&mut self, ctx: &mut Context, constants: &EngineConstants, name: &str) -> GameResult {
+        if ctx.headless {
+            return Ok(());
+        }
+
+        self.set_keep_pixels(name, true);
+        let batch = self.get_or_load_batch(ctx, constants, name)?;
+        match batch.get_texture_mut() {
+            Some(texture) => texture.into_render_target()?,
+            None => return Err(GameError::RenderError(format!("Texture {} has no backing texture.", name))),
+        }
+
+        // the pixel copy is dropped by the conversion
+        let bytes = TextureSet::texture_bytes(batch);
+        self.keep_pixels.remove(name);
+        if let Some(usage) = self.usage.get_mut(name) {
+            usage.bytes = bytes;
+            usage.pinned = true;
+        }
+
+        Ok(())
+    }
+
+    /// Loads given textures and warms them with `graphics::warm_texture`, so they don't hitch when first drawn.
+    /// Textures that fail to load are skipped, they'll report the error once something tries to draw them.
+    pub fn warm(&mut self, ctx: &mut Context, constants: &EngineConstants, names: &[&str]) -> GameResult {
+        if ctx.headless {
+            return Ok(());
+        }
+
+        for name in names {
+            let batch = match self.get_or_load_batch(ctx, constants, name) {
+                Ok(batch) => batch,
+                Err(_) => continue,
+            };
+
+            if let Some(texture) = batch.get_texture() {
+                warm_texture(ctx, texture)?;
+            }
+            if let Some(texture) = batch.glow().and_then(|glow| glow.get_texture()) {
+                warm_texture(ctx, texture)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Draws two whole textures stretched over `dest`, `tex_a` at `1 - t` opacity and `tex_b` at `t` on top,
+    /// so moving `t` from 0 to 1 crossfades between them. `t` is clamped to `[0.0-1.0]`.
+    pub fn draw_crossfade(
+        &mut self,
+        ctx: &mut Context,
+        constants: &EngineConstants,
+        tex_a: &str,
+        tex_b: &str,
+        dest: common::Rect<f32>,
+        t: f32,
+    ) -> GameResult {
+        let t = t.clamp(0.0, 1.0);
+
+        for (name, alpha) in [(tex_a, 1.0 - t), (tex_b, t)] {
+            let alpha = (alpha * 255.0).round() as u8;
+            if alpha == 0 {
+                continue;
+            }
+
+            let batch = self.get_or_load_batch(ctx, constants, name)?;
+            let (width, height) = batch.dimensions();
+            let rect = common::Rect::new(0, 0, width as u16, height as u16);
+            let scale_x = dest.width() / width.max(1) as f32;
+            let scale_y = dest.height() / height.max(1) as f32;
+
+            batch.add_rect_scaled_tinted(dest.left, dest.top, (255, 255, 255, alpha), scale_x, scale_y, &rect);
+            batch.draw(ctx)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the texture if it's already loaded, without trying to load it.
+    pub fn get(&mut self, name: &str) -> Option<&mut Box<dyn SpriteBatch>> {
+        self.tex_map.get_mut(name)
+    }
+
+    /// Replaces a texture at runtime, e.g. from a resource pack. The texture is scaled to the size listed
+    /// in `tex_sizes` the same way as ones loaded from disk, a warning is logged if it doesn't match.
+    pub fn replace(&mut self, constants: &EngineConstants, name: &str, texture: Box<dyn BackendTexture>) {
+        if let Some(&(orig_width, orig_height)) = constants.tex_sizes.get(name) {
+            let (width, height) = texture.dimensions();
+
+            if width as u32 * orig_height as u32 != height as u32 * orig_width as u32 {
+                warn!(
+                    "Replacement for texture {} is {}x{}, which doesn't match the expected {}x{} aspect ratio.",
+                    name, width, height, orig_width, orig_height
+                );
+            }
+        }
+
+        let main_batch = TextureSet::make_batch(name, constants, texture);
+        let mut batch: Box<dyn SpriteBatch> = Box::new(CombinedBatch { main_batch, glow_batch: None });
+        let bytes = TextureSet::texture_bytes(&mut batch);
+        self.tex_map.insert(name.to_owned(), batch);
+        self.usage.insert(name.to_owned(), TextureUsage { bytes, last_used: self.frame, pinned: true });
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    /// Drops a loaded or replaced texture, it's loaded again from the game data next time it's used.
+    pub fn revert(&mut self, name: &str) {
+        self.usage.remove(name);
+        if self.tex_map.remove(name).is_some() {
+            self.generation = self.generation.wrapping_add(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::framework::backend::Backend;
+    use crate::framework::backend_null::NullBackend;
+
+    fn null_context() -> Context {
+        let mut ctx = Context::new();
+        let builtin = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/data/builtin/builtin_data");
+        filesystem::mount(&mut ctx, &builtin, true);
+
+        let event_loop = NullBackend.create_event_loop(&ctx).unwrap();
+        ctx.renderer = Some(event_loop.new_renderer(&mut ctx as *mut Context).unwrap());
+        ctx
+    }
+
+    #[test]
+    fn keep_pixels_load_path() {
+        let mut ctx = null_context();
+        let mut constants = EngineConstants::defaults();
+        constants.base_paths = vec!["/".to_owned()];
+        let mut texture_set = TextureSet::new();
+
+        let batch = texture_set.get_or_load_batch(&mut ctx, &constants, "buttons").unwrap();
+        assert!(batch.get_texture().unwrap().alpha_at(0, 0).is_none());
+        let plain_bytes = texture_set.memory_usage();
+
+        texture_set.set_keep_pixels("buttons", true);
+        assert!(texture_set.keeps_pixels("buttons"));
+        assert!(!texture_set.tex_map.contains_key("buttons"));
+
+        let batch = texture_set.get_or_load_batch(&mut ctx, &constants, "buttons").unwrap();
+        assert!(batch.get_texture().unwrap().alpha_at(0, 0).is_some());
+        assert_eq!(texture_set.memory_usage(), plain_bytes * 2);
+    }
 }
@@ -77,6 +77,10 @@ impl PlayerController for CombinedPlayerController {
         self.controllers.iter().any(|cont| cont.strafe())
     }
 
+    fn run(&self) -> bool {
+        self.controllers.iter().any(|cont| cont.run())
+    }
+
     fn trigger_up(&self) -> bool {
         self.controllers.iter().any(|cont| cont.trigger_up())
     }
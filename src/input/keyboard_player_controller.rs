@@ -3,13 +3,13 @@ use crate::framework::context::Context;
 use crate::framework::error::GameResult;
 use crate::framework::keyboard;
 use crate::framework::keyboard::ScanCode;
-use crate::game::shared_game_state::SharedGameState;
+use crate::game::shared_game_state::{RunMode, SharedGameState};
 use crate::input::player_controller::PlayerController;
 use crate::game::player::TargetPlayer;
 
 bitfield! {
   #[derive(Clone, Copy)]
-  pub struct KeyState(u16);
+  pub struct KeyState(u32);
   impl Debug;
 
   pub left, set_left: 0;
@@ -28,6 +28,7 @@ bitfield! {
   pub strafe, set_strafe: 13;
   pub menu_ok, set_menu_ok: 14;
   pub menu_back, set_menu_back: 15;
+  pub run, set_run: 16;
 }
 
 #[derive(Clone)]
@@ -36,11 +37,18 @@ pub struct KeyboardController {
     state: KeyState,
     old_state: KeyState,
     trigger: KeyState,
+    running: bool,
 }
 
 impl KeyboardController {
     pub fn new(target: TargetPlayer) -> KeyboardController {
-        KeyboardController { target, state: KeyState(0), old_state: KeyState(0), trigger: KeyState(0) }
+        KeyboardController {
+            target,
+            state: KeyState(0),
+            old_state: KeyState(0),
+            trigger: KeyState(0),
+            running: false,
+        }
     }
 }
 
@@ -68,6 +76,14 @@ impl PlayerController for KeyboardController {
         self.state.set_menu_ok(keyboard::is_key_pressed(ctx, keymap.menu_ok));
         self.state.set_menu_back(keyboard::is_key_pressed(ctx, keymap.menu_back));
 
+        let run = keyboard::is_key_pressed(ctx, keymap.run);
+        match state.settings.run_mode {
+            RunMode::Hold => self.running = run,
+            RunMode::Toggle if run && !self.state.run() => self.running = !self.running,
+            RunMode::Toggle => {}
+        }
+        self.state.set_run(run);
+
         Ok(())
     }
 
@@ -126,6 +142,10 @@ impl PlayerController for KeyboardController {
         self.state.strafe()
     }
 
+    fn run(&self) -> bool {
+        self.running
+    }
+
     fn trigger_up(&self) -> bool {
         self.trigger.up()
     }
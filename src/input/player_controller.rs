@@ -43,6 +43,12 @@ pub trait PlayerController: PlayerControllerClone {
     /// True if "strafe" button is down.
     fn strafe(&self) -> bool;
 
+    /// True if the player should run, depending on `Settings::run_mode` either while the run key is held
+    /// or since it was last pressed. Only the keyboard has a run binding.
+    fn run(&self) -> bool {
+        false
+    }
+
     fn trigger_up(&self) -> bool;
 
     fn trigger_left(&self) -> bool;
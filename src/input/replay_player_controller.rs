@@ -7,7 +7,7 @@ use crate::input::player_controller::PlayerController;
 bitfield! {
   #[allow(unused)]
   #[derive(Clone, Copy)]
-  pub struct KeyState(u16);
+  pub struct KeyState(u32);
   impl Debug;
 
   pub left, set_left: 0;
@@ -26,6 +26,7 @@ bitfield! {
   pub strafe, set_strafe: 13;
   pub menu_ok, set_menu_ok: 14;
   pub menu_back, set_menu_back: 15;
+  pub run, set_run: 16;
 }
 
 #[derive(Copy, Clone)]
@@ -107,6 +108,10 @@ impl PlayerController for ReplayController {
         self.state.strafe()
     }
 
+    fn run(&self) -> bool {
+        self.state.run()
+    }
+
     fn trigger_up(&self) -> bool {
         self.trigger.up()
     }
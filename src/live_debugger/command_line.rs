@@ -23,6 +23,7 @@ pub enum CommandLineCommand {
     SpawnNPC(u16),
     TeleportPlayer(f32, f32),
     TSC(String),
+    DumpState,
 }
 
 impl CommandLineCommand {
@@ -155,6 +156,9 @@ impl CommandLineCommand {
                     return Some(CommandLineCommand::TeleportPlayer(x, y));
                 }
             }
+            "dump_state" => {
+                return Some(CommandLineCommand::DumpState);
+            }
             "tsc" => {
                 if components.len() < 2 {
                     return None;
@@ -260,6 +264,9 @@ impl CommandLineCommand {
                     }
                 };
             }
+            CommandLineCommand::DumpState => {
+                log::info!("Game state: {}", game_scene.dump_state()?);
+            }
         }
 
         Ok(())
@@ -282,6 +289,7 @@ impl CommandLineCommand {
             CommandLineCommand::SpawnNPC(npc_id) => format!("/spawn_npc {}", npc_id),
             CommandLineCommand::TeleportPlayer(x, y) => format!("/teleport_player {} {}", x, y),
             CommandLineCommand::TSC(script) => format!("/tsc {}", script.replace("\n", "\\n")),
+            CommandLineCommand::DumpState => "/dump_state".to_string(),
         }
     }
 
@@ -305,6 +313,7 @@ impl CommandLineCommand {
             CommandLineCommand::SpawnNPC(npc_id) => format!("Spawned NPC ID {} in front of player.", npc_id),
             CommandLineCommand::TeleportPlayer(x, y) => format!("Teleported players to ({}, {}).", x, y),
             CommandLineCommand::TSC(_) => "Executed TSC script.".to_string(),
+            CommandLineCommand::DumpState => "Dumped game state to the log.".to_string(),
         }
     }
 }
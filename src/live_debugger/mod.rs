@@ -3,6 +3,7 @@ use itertools::Itertools;
 
 use crate::framework::context::Context;
 use crate::framework::error::GameResult;
+use crate::framework::graphics;
 use crate::game::scripting::tsc::text_script::TextScriptExecutionState;
 use crate::game::shared_game_state::SharedGameState;
 use crate::scene::game_scene::GameScene;
@@ -26,6 +27,7 @@ pub struct LiveDebugger {
     flags_visible: bool,
     npc_inspector_visible: bool,
     hotkey_list_visible: bool,
+    renderer_visible: bool,
     command_line_parser: CommandLineParser,
     command_line_focused: bool,
     last_stage_id: usize,
@@ -46,6 +48,7 @@ impl LiveDebugger {
             flags_visible: false,
             npc_inspector_visible: false,
             hotkey_list_visible: false,
+            renderer_visible: false,
             command_line_parser: CommandLineParser::new(),
             command_line_focused: false,
             last_stage_id: usize::MAX,
@@ -164,10 +167,12 @@ impl LiveDebugger {
                 ));
 
                 ui.text(format!(
-                    "NPC Count: {}/{}/{} Booster fuel: {}",
+                    "NPC Count: {}/{}/{} Bullets: {}/{} Booster fuel: {}",
                     game_scene.npc_list.iter_alive().count(),
                     game_scene.npc_list.current_capacity(),
-                    game_scene.npc_list.max_capacity(),
+                    game_scene.npc_list.spawn_limit(),
+                    game_scene.bullet_manager.bullets.len(),
+                    game_scene.bullet_manager.max_bullets,
                     game_scene.player1.booster_fuel
                 ));
 
@@ -229,9 +234,16 @@ impl LiveDebugger {
                     state.command_line = !state.command_line;
                 }
 
+                ui.same_line();
+                if ui.button("Renderer") {
+                    self.renderer_visible = !self.renderer_visible;
+                }
+
                 ui.checkbox("noclip", &mut state.settings.noclip);
                 ui.same_line();
                 ui.checkbox("more rust", &mut state.more_rust);
+                ui.same_line();
+                ui.checkbox("UI overlay", &mut state.settings.render_ui_to_overlay);
             });
 
         if self.map_selector_visible {
@@ -491,6 +503,27 @@ impl LiveDebugger {
                         "` > Toggle Command Line",
                         "Ctrl + F3 > Reload Sound Manager",
                         "Ctrl + S > Quick Save",
+                        "Ctrl + D > Dump Game State",
+                        if cfg!(feature = "debug") {
+                            "Ctrl + F1 > Frame Advance (F1 steps)"
+                        } else {
+                            "Ctrl + F1 > Frame Advance (needs the debug feature)"
+                        },
+                        if cfg!(feature = "debug-outlines") {
+                            "Ctrl + F10 > Sprite Outlines"
+                        } else {
+                            "Ctrl + F10 > Sprite Outlines (needs the debug-outlines feature)"
+                        },
+                        if cfg!(feature = "debug") {
+                            "Ctrl + F11 > Cycle NPC Blend Mode"
+                        } else {
+                            "Ctrl + F11 > Cycle NPC Blend Mode (needs the debug feature)"
+                        },
+                        if cfg!(feature = "debug") {
+                            "Ctrl + G > Cycle Debug Grid"
+                        } else {
+                            "Ctrl + G > Cycle Debug Grid (needs the debug feature)"
+                        },
                     ];
                     for hotkeys in key.iter() {
                         match hotkeys {
@@ -503,6 +536,37 @@ impl LiveDebugger {
                 });
         }
 
+        if self.renderer_visible {
+            let info = graphics::renderer_debug_info(ctx);
+
+            ui.window("Renderer")
+                .position([400.0, 310.0], Condition::FirstUseEver)
+                .size([300.0, 150.0], Condition::FirstUseEver)
+                .resizable(false)
+                .build(|| {
+                    let info = match &info {
+                        Some(info) => info,
+                        None => {
+                            ui.text("Not available with this backend.");
+                            return;
+                        }
+                    };
+
+                    ui.text(format!("Blend mode: {}", info.blend_mode));
+                    match info.clip_rect {
+                        Some(r) => ui.text(format!("Clip rect: ({},{}) - ({},{})", r.left, r.top, r.right, r.bottom)),
+                        None => ui.text("Clip rect: none"),
+                    }
+                    match info.render_target {
+                        Some(target) => ui.text(format!("Render target: {:#x}", target)),
+                        None => ui.text("Render target: screen"),
+                    }
+                    ui.text(format!("Viewport offset: ({},{})", info.viewport_offset.0, info.viewport_offset.1));
+                    ui.text(format!("Saved states: {}", info.state_depth));
+                    ui.text(format!("Draw calls: {}", info.draw_calls));
+                });
+        }
+
         let mut remove = -1;
         for (idx, (_, title, contents)) in self.text_windows.iter().enumerate() {
             let mut opened = true;
@@ -124,6 +124,7 @@ enum ControlEntry {
     Inventory,
     Map,
     Strafe,
+    Run,
     MenuOk,
     MenuBack,
 }
@@ -143,6 +144,7 @@ impl ControlEntry {
             ControlEntry::Inventory => state.loc.t("menus.controls_menu.rebind_menu.inventory"),
             ControlEntry::Map => state.loc.t("menus.controls_menu.rebind_menu.map"),
             ControlEntry::Strafe => state.loc.t("menus.controls_menu.rebind_menu.strafe"),
+            ControlEntry::Run => state.loc.t("menus.controls_menu.rebind_menu.run"),
             ControlEntry::MenuOk => state.loc.t("menus.controls_menu.rebind_menu.menu_ok"),
             ControlEntry::MenuBack => state.loc.t("menus.controls_menu.rebind_menu.menu_back"),
         }
@@ -305,6 +307,7 @@ impl ControlsMenu {
         map.push((ControlEntry::Map, settings_key_map.map));
         map.push((ControlEntry::Skip, settings_key_map.skip));
         map.push((ControlEntry::Strafe, settings_key_map.strafe));
+        map.push((ControlEntry::Run, settings_key_map.run));
 
         map
     }
@@ -414,27 +417,22 @@ impl ControlsMenu {
             MenuEntry::Active(state.loc.t("menus.controls_menu.controller.keyboard").to_owned()),
         );
 
-        let gamepads = gamepad::get_gamepads(ctx);
+        let gamepads = gamepad::connected_controllers(ctx);
 
         let other_player_controller_type = match self.selected_player {
             Player::Player1 => state.settings.player2_controller_type,
             Player::Player2 => state.settings.player1_controller_type,
         };
 
-        let mut available_gamepads = gamepads.len();
-
-        for i in 0..gamepads.len() {
+        for (i, name) in gamepads.iter() {
             if let ControllerType::Gamepad(index) = other_player_controller_type {
-                if index as usize == i {
-                    available_gamepads -= 1;
+                if index as usize == *i {
                     continue;
                 }
             }
 
-            self.select_controller.push_entry(
-                SelectControllerMenuEntry::Gamepad(i),
-                MenuEntry::Active(format!("{} {}", gamepads[i].get_gamepad_name(), i + 1)),
-            );
+            self.select_controller
+                .push_entry(SelectControllerMenuEntry::Gamepad(*i), MenuEntry::Active(format!("{} {}", name, i + 1)));
         }
 
         self.select_controller
@@ -451,7 +449,7 @@ impl ControlsMenu {
         };
 
         if let ControllerType::Gamepad(index) = controller_type {
-            if index as usize >= available_gamepads {
+            if !gamepads.iter().any(|(slot, _)| *slot == index as usize) {
                 self.selected_controller = ControllerType::Keyboard;
                 self.main.set_entry(MainMenuEntry::Rumble, MenuEntry::Hidden);
             } else {
@@ -617,6 +615,10 @@ impl ControlsMenu {
                 Player::Player1 => state.settings.player1_key_map.strafe = scan_code,
                 Player::Player2 => state.settings.player2_key_map.strafe = scan_code,
             },
+            ControlEntry::Run => match self.selected_player {
+                Player::Player1 => state.settings.player1_key_map.run = scan_code,
+                Player::Player2 => state.settings.player2_key_map.run = scan_code,
+            },
             ControlEntry::MenuOk => match self.selected_player {
                 Player::Player1 => {
                     did_swap_controls = self.swap_if_same(
@@ -767,6 +769,8 @@ impl ControlsMenu {
                 Player::Player1 => state.settings.player1_controller_button_map.strafe = input_type,
                 Player::Player2 => state.settings.player2_controller_button_map.strafe = input_type,
             },
+            // only the keyboard has a run binding, it's never listed for gamepads
+            ControlEntry::Run => {}
             ControlEntry::MenuOk => match self.selected_player {
                 Player::Player1 => {
                     did_swap_controls = self.swap_if_same(
@@ -4,7 +4,9 @@ use crate::framework::context::Context;
 use crate::framework::error::GameResult;
 use crate::framework::graphics::VSyncMode;
 use crate::framework::{filesystem, graphics};
-use crate::game::shared_game_state::{CutsceneSkipMode, ScreenShakeIntensity, SharedGameState, TimingMode, WindowMode};
+use crate::game::shared_game_state::{
+    CutsceneSkipMode, RunMode, ScalingMode, ScreenShakeIntensity, SharedGameState, TimingMode, WindowMode,
+};
 use crate::graphics::font::Font;
 use crate::input::combined_menu_controller::CombinedMenuController;
 use crate::menu::MenuEntry;
@@ -53,11 +55,14 @@ impl Default for MainMenuEntry {
 enum GraphicsMenuEntry {
     VSyncMode,
     WindowMode,
+    ScalingMode,
     LightingEffects,
     WeaponLightCone,
     ScreenShake,
     MotionInterpolation,
     SubpixelScrolling,
+    CameraPixelSnap,
+    ReduceFlashing,
     OriginalTextures,
     SeasonalTextures,
     Renderer,
@@ -115,7 +120,9 @@ enum BehaviorMenuEntry {
     GameTiming,
     PauseOnFocusLoss,
     AllowStrafe,
+    SmoothWaterPhysics,
     CutsceneSkipMode,
+    RunMode,
     #[cfg(feature = "discord-rpc")]
     DiscordRPC,
     Back,
@@ -259,6 +266,19 @@ impl SettingsMenu {
                 ],
             ),
         );
+        self.graphics.push_entry(
+            GraphicsMenuEntry::ScalingMode,
+            MenuEntry::Options(
+                state.loc.t("menus.options_menu.graphics_menu.scaling_mode.entry").to_owned(),
+                state.settings.scaling_mode as usize,
+                vec![
+                    state.loc.t("menus.options_menu.graphics_menu.scaling_mode.integer").to_owned(),
+                    state.loc.t("menus.options_menu.graphics_menu.scaling_mode.fractional").to_owned(),
+                    state.loc.t("menus.options_menu.graphics_menu.scaling_mode.fit_width").to_owned(),
+                    state.loc.t("menus.options_menu.graphics_menu.scaling_mode.fit_height").to_owned(),
+                ],
+            ),
+        );
         self.graphics.push_entry(
             GraphicsMenuEntry::LightingEffects,
             MenuEntry::Toggle(
@@ -299,6 +319,20 @@ impl SettingsMenu {
                 state.settings.subpixel_coords,
             ),
         );
+        self.graphics.push_entry(
+            GraphicsMenuEntry::CameraPixelSnap,
+            MenuEntry::Toggle(
+                state.loc.t("menus.options_menu.graphics_menu.camera_pixel_snap").to_owned(),
+                state.settings.camera_pixel_snap,
+            ),
+        );
+        self.graphics.push_entry(
+            GraphicsMenuEntry::ReduceFlashing,
+            MenuEntry::Toggle(
+                state.loc.t("menus.options_menu.graphics_menu.reduce_flashing").to_owned(),
+                state.settings.reduce_flashing,
+            ),
+        );
 
         // NS version uses two different maps, therefore we can't dynamically switch between graphics presets.
         if state.constants.supports_og_textures {
@@ -571,6 +605,14 @@ impl SettingsMenu {
             ),
         );
 
+        self.behavior.push_entry(
+            BehaviorMenuEntry::SmoothWaterPhysics,
+            MenuEntry::Toggle(
+                state.loc.t("menus.options_menu.behavior_menu.smooth_water_physics").to_owned(),
+                state.settings.smooth_water_physics,
+            ),
+        );
+
         self.behavior.push_entry(
             BehaviorMenuEntry::CutsceneSkipMode,
             MenuEntry::Options(
@@ -584,6 +626,18 @@ impl SettingsMenu {
             ),
         );
 
+        self.behavior.push_entry(
+            BehaviorMenuEntry::RunMode,
+            MenuEntry::Options(
+                state.loc.t("menus.options_menu.behavior_menu.run_mode.entry").to_owned(),
+                state.settings.run_mode as usize,
+                vec![
+                    state.loc.t("menus.options_menu.behavior_menu.run_mode.hold").to_owned(),
+                    state.loc.t("menus.options_menu.behavior_menu.run_mode.toggle").to_owned(),
+                ],
+            ),
+        );
+
         #[cfg(feature = "discord-rpc")]
         self.behavior.push_entry(
             BehaviorMenuEntry::DiscordRPC,
@@ -703,6 +757,39 @@ impl SettingsMenu {
                         let _ = state.settings.save(ctx);
                     }
                 }
+                MenuSelectionResult::Selected(GraphicsMenuEntry::ScalingMode, toggle)
+                | MenuSelectionResult::Right(GraphicsMenuEntry::ScalingMode, toggle, _) => {
+                    if let MenuEntry::Options(_, value, _) = toggle {
+                        let (new_mode, new_value) = match *value {
+                            0 => (ScalingMode::Fractional, 1),
+                            1 => (ScalingMode::FitWidth, 2),
+                            2 => (ScalingMode::FitHeight, 3),
+                            _ => (ScalingMode::Integer, 0),
+                        };
+
+                        *value = new_value;
+                        state.settings.scaling_mode = new_mode;
+                        state.handle_resize(ctx)?;
+
+                        let _ = state.settings.save(ctx);
+                    }
+                }
+                MenuSelectionResult::Left(GraphicsMenuEntry::ScalingMode, toggle, _) => {
+                    if let MenuEntry::Options(_, value, _) = toggle {
+                        let (new_mode, new_value) = match *value {
+                            0 => (ScalingMode::FitHeight, 3),
+                            1 => (ScalingMode::Integer, 0),
+                            2 => (ScalingMode::Fractional, 1),
+                            _ => (ScalingMode::FitWidth, 2),
+                        };
+
+                        *value = new_value;
+                        state.settings.scaling_mode = new_mode;
+                        state.handle_resize(ctx)?;
+
+                        let _ = state.settings.save(ctx);
+                    }
+                }
                 MenuSelectionResult::Selected(GraphicsMenuEntry::VSyncMode, toggle)
                 | MenuSelectionResult::Right(GraphicsMenuEntry::VSyncMode, toggle, _) => {
                     if let MenuEntry::DescriptiveOptions(_, value, _, _) = toggle {
@@ -799,6 +886,22 @@ impl SettingsMenu {
                         *value = state.settings.subpixel_coords;
                     }
                 }
+                MenuSelectionResult::Selected(GraphicsMenuEntry::CameraPixelSnap, toggle) => {
+                    if let MenuEntry::Toggle(_, value) = toggle {
+                        state.settings.camera_pixel_snap = !state.settings.camera_pixel_snap;
+                        let _ = state.settings.save(ctx);
+
+                        *value = state.settings.camera_pixel_snap;
+                    }
+                }
+                MenuSelectionResult::Selected(GraphicsMenuEntry::ReduceFlashing, toggle) => {
+                    if let MenuEntry::Toggle(_, value) = toggle {
+                        state.settings.reduce_flashing = !state.settings.reduce_flashing;
+                        let _ = state.settings.save(ctx);
+
+                        *value = state.settings.reduce_flashing;
+                    }
+                }
                 MenuSelectionResult::Selected(GraphicsMenuEntry::OriginalTextures, toggle) => {
                     if let MenuEntry::Toggle(_, value) = toggle {
                         state.settings.original_textures = !state.settings.original_textures;
@@ -1006,6 +1109,14 @@ impl SettingsMenu {
                         *value = state.settings.allow_strafe;
                     }
                 }
+                MenuSelectionResult::Selected(BehaviorMenuEntry::SmoothWaterPhysics, toggle) => {
+                    if let MenuEntry::Toggle(_, value) = toggle {
+                        state.settings.smooth_water_physics = !state.settings.smooth_water_physics;
+                        let _ = state.settings.save(ctx);
+
+                        *value = state.settings.smooth_water_physics;
+                    }
+                }
                 MenuSelectionResult::Selected(BehaviorMenuEntry::CutsceneSkipMode, toggle)
                 | MenuSelectionResult::Right(BehaviorMenuEntry::CutsceneSkipMode, toggle, _) => {
                     if let MenuEntry::Options(_, value, _) = toggle {
@@ -1033,6 +1144,20 @@ impl SettingsMenu {
                         let _ = state.settings.save(ctx);
                     }
                 }
+                MenuSelectionResult::Selected(BehaviorMenuEntry::RunMode, toggle)
+                | MenuSelectionResult::Left(BehaviorMenuEntry::RunMode, toggle, _)
+                | MenuSelectionResult::Right(BehaviorMenuEntry::RunMode, toggle, _) => {
+                    if let MenuEntry::Options(_, value, _) = toggle {
+                        let (new_mode, new_value) = match state.settings.run_mode {
+                            RunMode::Hold => (RunMode::Toggle, 1),
+                            RunMode::Toggle => (RunMode::Hold, 0),
+                        };
+
+                        state.settings.run_mode = new_mode;
+                        *value = new_value;
+                        let _ = state.settings.save(ctx);
+                    }
+                }
                 #[cfg(feature = "discord-rpc")]
                 MenuSelectionResult::Selected(BehaviorMenuEntry::DiscordRPC, toggle) => {
                     if let MenuEntry::Toggle(_, value) = toggle {
@@ -1,4 +1,5 @@
 use std::cell::RefCell;
+use std::io::Write;
 use std::ops::{Deref, Range};
 use std::rc::Rc;
 
@@ -8,6 +9,7 @@ use crate::common::{interpolate_fix9_scale, Color, Direction, Rect};
 use crate::components::background::Background;
 use crate::components::boss_life_bar::BossLifeBar;
 use crate::components::credits::Credits;
+use crate::components::darkness::{Darkness, DarknessLight};
 use crate::components::draw_common::Alignment;
 use crate::components::fade::Fade;
 use crate::components::falling_island::FallingIsland;
@@ -16,6 +18,7 @@ use crate::components::hud::HUD;
 use crate::components::inventory::InventoryUI;
 use crate::components::map_system::MapSystem;
 use crate::components::nikumaru::NikumaruCounter;
+use crate::components::npc_spawner::NPCSpawner;
 use crate::components::replay::Replay;
 use crate::components::stage_select::StageSelect;
 use crate::components::text_boxes::TextBoxes;
@@ -44,6 +47,7 @@ use crate::game::scripting::tsc::text_script::{ScriptMode, TextScriptExecutionSt
 use crate::game::settings::ControllerType;
 use crate::game::shared_game_state::{CutsceneSkipMode, PlayerCount, ReplayState, SharedGameState, TileSize};
 use crate::game::stage::{BackgroundType, Stage, StageTexturePaths};
+use crate::game::state_dump;
 use crate::game::weapon::bullet::BulletManager;
 use crate::game::weapon::{Weapon, WeaponType};
 use crate::graphics::font::{Font, Symbols};
@@ -54,6 +58,24 @@ use crate::scene::title_scene::TitleScene;
 use crate::scene::Scene;
 use crate::util::rng::RNG;
 
+/// Lines drawn over the map to line sprites and collision up with, cycled with Ctrl+G in debug mode.
+#[cfg(feature = "debug")]
+#[derive(Debug, Clone, Copy)]
+pub struct DebugGrid {
+    /// Distance between the lines, in canvas pixels. Aligned to the map's tile boundaries, not the screen.
+    pub spacing: u16,
+    pub color: Color,
+}
+
+/// Canvas positions of the debug grid lines along one axis, from the first one at or before the screen edge.
+/// Tiles are drawn centered on their position, so their boundaries are half a tile off the map's origin.
+#[cfg(feature = "debug")]
+fn debug_grid_lines(frame: f32, half_tile: f32, spacing: f32, extent: f32) -> impl Iterator<Item = f32> {
+    let start = -(frame + half_tile).rem_euclid(spacing);
+
+    (0..).map(move |i| start + i as f32 * spacing).take_while(move |&pos| pos < extent)
+}
+
 pub struct GameScene {
     pub tick: u32,
     pub stage: Stage,
@@ -63,6 +85,8 @@ pub struct GameScene {
     pub stage_select: StageSelect,
     pub flash: Flash,
     pub credits: Credits,
+    pub darkness: Darkness,
+    pub npc_spawner: NPCSpawner,
     pub falling_island: FallingIsland,
     pub inventory_ui: InventoryUI,
     pub map_system: MapSystem,
@@ -91,6 +115,11 @@ pub struct GameScene {
     map_name_counter: u16,
     skip_counter: u16,
     inventory_dim: f32,
+    /// Blend mode NPCs are drawn with instead of the usual one, cycled with Ctrl+F11 in debug mode.
+    #[cfg(feature = "debug")]
+    debug_npc_blend_mode: Option<BlendMode>,
+    #[cfg(feature = "debug")]
+    pub debug_grid: Option<DebugGrid>,
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -159,6 +188,8 @@ impl GameScene {
             stage_select: StageSelect::new(),
             flash: Flash::new(),
             credits: Credits::new(),
+            darkness: Darkness::new(),
+            npc_spawner: NPCSpawner::new(),
             falling_island: FallingIsland::new(),
             inventory_ui: InventoryUI::new(),
             map_system: MapSystem::new(),
@@ -182,6 +213,10 @@ impl GameScene {
             map_name_counter: 0,
             skip_counter: 0,
             inventory_dim: 0.0,
+            #[cfg(feature = "debug")]
+            debug_npc_blend_mode: None,
+            #[cfg(feature = "debug")]
+            debug_grid: None,
             replay: Replay::new(),
         })
     }
@@ -210,6 +245,21 @@ impl GameScene {
     }
 
     fn draw_npc_layer(&self, state: &mut SharedGameState, ctx: &mut Context, layer: NPCLayer) -> GameResult {
+        #[cfg(feature = "debug")]
+        if let Some(blend_mode) = self.debug_npc_blend_mode {
+            graphics::push_state(ctx)?;
+
+            let result =
+                graphics::set_blend_mode(ctx, blend_mode).and_then(|_| self.draw_npcs_in_layer(state, ctx, layer));
+
+            graphics::pop_state(ctx)?;
+            return result;
+        }
+
+        self.draw_npcs_in_layer(state, ctx, layer)
+    }
+
+    fn draw_npcs_in_layer(&self, state: &mut SharedGameState, ctx: &mut Context, layer: NPCLayer) -> GameResult {
         for npc in self.npc_list.iter_alive() {
             if npc.layer != layer
                 || npc.x < (self.frame.x - 128 * 0x200 - npc.display_bounds.width() as i32 * 0x200)
@@ -282,11 +332,25 @@ impl GameScene {
                 Direction::FacingPlayer => unreachable!(),
             }
 
-            batch.add_rect(
-                interpolate_fix9_scale(prev_x - self.frame.prev_x, x - self.frame.x, state.frame_time),
-                interpolate_fix9_scale(prev_y - self.frame.prev_y, y - self.frame.y, state.frame_time),
-                &bullet.anim_rect,
-            );
+            let draw_x = interpolate_fix9_scale(prev_x - self.frame.prev_x, x - self.frame.x, state.frame_time);
+            let draw_y = interpolate_fix9_scale(prev_y - self.frame.prev_y, y - self.frame.y, state.frame_time);
+
+            let bullet_data = state.constants.weapon.bullet_table.get(bullet.btype as usize);
+            let rotate = bullet_data.map_or(false, |data| data.rotate_with_velocity);
+            if rotate && (bullet.vel_x != 0 || bullet.vel_y != 0) {
+                // the frame is already drawn facing the bullet's direction, only the difference is rotated
+                let base_angle = match bullet.direction {
+                    Direction::Left => 180.0,
+                    Direction::Up => 270.0,
+                    Direction::Bottom => 90.0,
+                    _ => 0.0,
+                };
+                let angle = (bullet.vel_y as f32).atan2(bullet.vel_x as f32).to_degrees() - base_angle;
+
+                batch.add_rect_ex(draw_x, draw_y, angle, false, false, &bullet.anim_rect);
+            } else {
+                batch.add_rect(draw_x, draw_y, &bullet.anim_rect);
+            }
         }
 
         batch.draw(ctx)?;
@@ -1179,18 +1243,19 @@ impl GameScene {
                             npc.cond.set_explode_die(true);
                         }
                     } else {
-                        if npc.shock < 14 {
+                        let effects = state.constants.npc_effects;
+                        if npc.shock < effects.hurt_effect_threshold {
                             if let Some(table_entry) = state.npc_table.get_entry(npc.npc_type) {
                                 state.sound_manager.play_sfx(table_entry.hurt_sound);
                             }
 
-                            npc.shock = 16;
+                            npc.shock = effects.shock_duration;
 
-                            for _ in 0..3 {
+                            for _ in 0..effects.hurt_caret_count {
                                 state.create_caret(
                                     (bullet.x + npc.x) / 2,
                                     (bullet.y + npc.y) / 2,
-                                    CaretType::HurtParticles,
+                                    effects.hurt_caret,
                                     Direction::Left,
                                 );
                             }
@@ -1279,7 +1344,12 @@ impl GameScene {
                         } else {
                             state.sound_manager.play_sfx(self.boss.death_sound[idx]);
 
-                            let destroy_count = 4usize * (2usize).pow((npc.size as u32).saturating_sub(1));
+                            // sizes outside of the table keep the original formula
+                            let destroy_count = state
+                                .constants
+                                .npc_effects
+                                .vanish_smoke_count(npc.size)
+                                .unwrap_or_else(|| 4usize * (2usize).pow((npc.size as u32).saturating_sub(1)));
 
                             self.npc_list.create_death_smoke(
                                 npc.x,
@@ -1292,20 +1362,21 @@ impl GameScene {
                             npc.cond.set_alive(false);
                         }
                     } else {
-                        if shock < 14 {
-                            for _ in 0..3 {
-                                state.create_caret(bullet.x, bullet.y, CaretType::HurtParticles, Direction::Left);
+                        let effects = state.constants.npc_effects;
+                        if shock < effects.hurt_effect_threshold {
+                            for _ in 0..effects.hurt_caret_count {
+                                state.create_caret(bullet.x, bullet.y, effects.hurt_caret, Direction::Left);
                             }
                             state.sound_manager.play_sfx(self.boss.hurt_sound[idx]);
                         }
 
-                        npc.shock = 8;
+                        npc.shock = effects.boss_shock_duration;
                         if npc.npc_flags.show_damage() {
                             npc.popup.add_value(-bullet.damage);
                         }
 
                         npc = unsafe { self.boss.parts.get_unchecked_mut(i) };
-                        npc.shock = 8;
+                        npc.shock = effects.boss_shock_duration;
                     }
 
                     bullet.life = bullet.life.saturating_sub(1);
@@ -1352,6 +1423,21 @@ impl GameScene {
 
         self.whimsical_star.tick(state, (&self.player1, &mut self.bullet_manager))?;
 
+        if self.darkness.darkness().is_some() {
+            let mut lights = [DarknessLight::default(); 2];
+            let mut count = 0;
+            for player in [&self.player1, &self.player2] {
+                if player.cond.alive() && !player.cond.hidden() {
+                    lights[count] =
+                        DarknessLight { x: player.x, y: player.y, prev_x: player.prev_x, prev_y: player.prev_y };
+                    count += 1;
+                }
+            }
+
+            self.darkness.tick(state, &lights[..count])?;
+        }
+        self.npc_spawner.tick(state, &self.npc_list, &self.stage, self.stage_id)?;
+
         if self.player1.damage > 0 {
             let xp_loss = self.player1.damage * if self.player1.equip.has_arms_barrier() { 1 } else { 2 };
             match self.inventory_player1.take_xp(xp_loss, state) {
@@ -1519,6 +1605,7 @@ impl GameScene {
 
         self.tilemap.tick()?;
 
+        self.frame.set_camera_pixel_snap(state.settings.camera_pixel_snap);
         self.frame.update(state, &self.stage);
 
         if state.control_flags.control_enabled() {
@@ -1637,6 +1724,37 @@ impl GameScene {
 
         Ok(())
     }
+
+    /// Current map and NPCs as JSON, see `state_dump::dump_state`.
+    pub fn dump_state(&self) -> GameResult<String> {
+        state_dump::dump_state(self.stage_id, &self.stage.map, &self.npc_list)
+    }
+
+    fn write_state_dump(&self, ctx: &mut Context) -> GameResult {
+        let mut file = filesystem::user_create(ctx, "/state_dump.json")?;
+        file.write_all(self.dump_state()?.as_bytes())?;
+        Ok(())
+    }
+
+    #[cfg(feature = "debug")]
+    fn draw_debug_grid(&self, grid: DebugGrid, state: &mut SharedGameState, ctx: &mut Context) -> GameResult {
+        let spacing = grid.spacing.max(1) as f32;
+        let half_tile = state.tile_size.as_float() / 2.0;
+        let (frame_x, frame_y) = self.frame.xy_interpolated(state.frame_time);
+        let (width, height) = (state.canvas_size.0 * state.scale, state.canvas_size.1 * state.scale);
+
+        for x in debug_grid_lines(frame_x, half_tile, spacing, state.canvas_size.0) {
+            let left = (x * state.scale).round() as isize;
+            draw_rect(ctx, Rect::new(left, 0, left + 1, height as isize), grid.color)?;
+        }
+
+        for y in debug_grid_lines(frame_y, half_tile, spacing, state.canvas_size.1) {
+            let top = (y * state.scale).round() as isize;
+            draw_rect(ctx, Rect::new(0, top, width as isize, top + 1), grid.color)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl Scene for GameScene {
@@ -1656,7 +1774,12 @@ impl Scene for GameScene {
             }
         }
 
+        // after the replay had a chance to restore game_rng, so bullets follow the recorded seed
+        self.bullet_manager.reseed_from(&state.game_rng);
+
         self.npc_list.set_rng_seed(state.game_rng.next());
+        self.npc_list.set_spawn_limit(state.constants.game.max_npcs);
+        self.bullet_manager.max_bullets = state.constants.game.max_bullets;
         self.boss.init_rng(state.game_rng.next());
         state.textscript_vm.set_scene_script(self.stage.load_text_script(
             &state.constants.base_paths,
@@ -1691,6 +1814,23 @@ impl Scene for GameScene {
 
         state.npc_table.stage_textures = self.stage_textures.clone();
 
+        {
+            let paths = self.stage_textures.deref().borrow();
+            let names = [
+                paths.background.as_str(),
+                paths.tileset_fg.as_str(),
+                paths.tileset_mg.as_str(),
+                paths.tileset_bg.as_str(),
+                paths.npc1.as_str(),
+                paths.npc2.as_str(),
+            ];
+
+            // only an optimization, the textures get loaded on first draw anyway
+            if let Err(e) = state.texture_set.warm(ctx, &state.constants, &names) {
+                log::warn!("Failed to warm stage textures: {}", e);
+            }
+        }
+
         self.boss.boss_type = self.stage.data.boss_no as u16;
         self.player1.target_x = self.player1.x;
         self.player1.target_y = self.player1.y;
@@ -1702,6 +1842,7 @@ impl Scene for GameScene {
         self.player2.camera_target_y = 0;
         self.frame.target_x = self.player1.x;
         self.frame.target_y = self.player1.y;
+        self.frame.set_camera_pixel_snap(state.settings.camera_pixel_snap);
         self.frame.immediate_update(state, &self.stage);
 
         // I'd personally set it to something higher but left it as is for accuracy.
@@ -2025,6 +2166,7 @@ impl Scene for GameScene {
         {
             self.draw_light_map(state, ctx)?;
         }
+        self.darkness.draw(state, ctx, &self.frame)?;
         self.flash.draw(state, ctx, &self.frame)?;
 
         self.draw_black_bars(state, ctx)?;
@@ -2242,6 +2384,11 @@ impl Scene for GameScene {
             self.draw_debug_outlines(state, ctx)?;
         }
 
+        #[cfg(feature = "debug")]
+        if let Some(grid) = self.debug_grid {
+            self.draw_debug_grid(grid, state, ctx)?;
+        }
+
         if state.settings.god_mode {
             let debug_name = "GOD";
             state
@@ -2316,13 +2463,68 @@ impl Scene for GameScene {
             return Ok(());
         }
 
+        #[cfg(feature = "debug")]
+        if key_code == ScanCode::F1 && ctx.keyboard_context.active_mods().ctrl() {
+            state.frame_advance = !state.frame_advance;
+            state.frame_advance_steps = 0;
+            return Ok(());
+        }
+
+        if key_code == ScanCode::D && ctx.keyboard_context.active_mods().ctrl() {
+            match self.write_state_dump(ctx) {
+                Ok(()) => info!("Dumped game state to state_dump.json"),
+                Err(e) => log::warn!("Failed to dump game state: {}", e),
+            }
+            return Ok(());
+        }
+
         if key_code == ScanCode::S && ctx.keyboard_context.active_mods().ctrl() {
             let _ = state.save_game(self, ctx, None);
             state.sound_manager.play_sfx(18);
             return Ok(());
         }
 
+        #[cfg(feature = "debug-outlines")]
+        if key_code == ScanCode::F10 && ctx.keyboard_context.active_mods().ctrl() {
+            let enabled = !graphics::sprite_outlines(ctx);
+            graphics::set_sprite_outlines(ctx, enabled);
+            return Ok(());
+        }
+
+        #[cfg(feature = "debug")]
+        if key_code == ScanCode::G && ctx.keyboard_context.active_mods().ctrl() {
+            let tile_size = state.tile_size.as_int() as u16;
+            let color = Color::from_rgba(0, 255, 255, 96);
+
+            // tile boundaries, then half tiles, then off
+            self.debug_grid = match self.debug_grid {
+                None => Some(DebugGrid { spacing: tile_size, color }),
+                Some(grid) if grid.spacing == tile_size => Some(DebugGrid { spacing: tile_size / 2, ..grid }),
+                Some(_) => None,
+            };
+            return Ok(());
+        }
+
+        #[cfg(feature = "debug")]
+        if key_code == ScanCode::F11 && ctx.keyboard_context.active_mods().ctrl() {
+            self.debug_npc_blend_mode = match self.debug_npc_blend_mode {
+                None => Some(BlendMode::None),
+                Some(BlendMode::None) => Some(BlendMode::Add),
+                Some(BlendMode::Add) => Some(BlendMode::Alpha),
+                Some(BlendMode::Alpha) => Some(BlendMode::Multiply),
+                Some(BlendMode::Multiply) => None,
+            };
+
+            match self.debug_npc_blend_mode {
+                Some(mode) => info!("Drawing NPCs with blend mode {:?}", mode),
+                None => info!("Drawing NPCs with their default blend mode"),
+            }
+            return Ok(());
+        }
+
         match key_code {
+            #[cfg(feature = "debug")]
+            ScanCode::F1 if state.frame_advance => state.frame_advance_steps += 1,
             ScanCode::F3 => state.settings.god_mode = !state.settings.god_mode,
             ScanCode::F4 => state.settings.infinite_booster = !state.settings.infinite_booster,
             ScanCode::F5 => state.settings.subpixel_coords = !state.settings.subpixel_coords,
@@ -2354,3 +2556,22 @@ impl Scene for GameScene {
         Ok(())
     }
 }
+
+#[cfg(all(test, feature = "debug"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_grid_lines_follow_tile_boundaries() {
+        // 16px tiles are drawn from -8 to 8 around their position
+        let lines: Vec<f32> = debug_grid_lines(0.0, 8.0, 16.0, 40.0).collect();
+        assert_eq!(lines, vec![-8.0, 8.0, 24.0]);
+
+        // tile 1 spans 8..24 on the map, -2..14 on the screen
+        let lines: Vec<f32> = debug_grid_lines(10.0, 8.0, 16.0, 40.0).collect();
+        assert_eq!(lines, vec![-2.0, 14.0, 30.0]);
+
+        let lines: Vec<f32> = debug_grid_lines(-4.0, 8.0, 8.0, 20.0).collect();
+        assert_eq!(lines, vec![-4.0, 4.0, 12.0]);
+    }
+}
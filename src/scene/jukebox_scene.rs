@@ -8,7 +8,7 @@ use crate::framework::context::Context;
 use crate::framework::error::GameResult;
 use crate::framework::filesystem;
 use crate::game::frame::Frame;
-use crate::game::map::Map;
+use crate::game::map::{CameraBoundaries, Map};
 use crate::game::settings::ControllerType;
 use crate::game::shared_game_state::{SharedGameState, TileSize};
 use crate::game::stage::{BackgroundType, NpcType, Stage, StageData, StageTexturePaths, Tileset};
@@ -66,7 +66,14 @@ pub struct JukeboxScene {
 impl JukeboxScene {
     pub fn new() -> JukeboxScene {
         let fake_stage = Stage {
-            map: Map { width: 0, height: 0, tiles: vec![], attrib: [0; 0x100], tile_size: TileSize::Tile16x16 },
+            map: Map {
+                width: 0,
+                height: 0,
+                tiles: vec![],
+                attrib: [0; 0x100],
+                tile_size: TileSize::Tile16x16,
+                camera_boundaries: CameraBoundaries::default(),
+            },
             data: StageData {
                 name: String::new(),
                 name_jp: String::new(),
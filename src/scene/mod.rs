@@ -1,5 +1,6 @@
 use crate::framework::context::Context;
 use crate::framework::error::GameResult;
+use crate::framework::event::GameEvent;
 use crate::framework::keyboard::ScanCode;
 use crate::framework::ui::Components;
 use crate::game::shared_game_state::SharedGameState;
@@ -46,6 +47,11 @@ pub trait Scene: downcast::Any {
         Ok(())
     }
 
+    /// Called for every queued input/window event, before the ticks of the update it was drained in.
+    fn process_event(&mut self, _state: &mut SharedGameState, _ctx: &mut Context, _event: &GameEvent) -> GameResult {
+        Ok(())
+    }
+
     fn process_debug_keys(
         &mut self,
         _state: &mut SharedGameState,
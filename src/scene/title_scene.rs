@@ -6,7 +6,7 @@ use crate::entity::GameEntity;
 use crate::framework::context::Context;
 use crate::framework::error::GameResult;
 use crate::game::frame::Frame;
-use crate::game::map::Map;
+use crate::game::map::{CameraBoundaries, Map};
 use crate::game::shared_game_state::{
     GameDifficulty, MenuCharacter, ReplayKind, ReplayState, Season, SharedGameState, TileSize,
 };
@@ -97,7 +97,14 @@ pub struct TitleScene {
 impl TitleScene {
     pub fn new() -> Self {
         let fake_stage = Stage {
-            map: Map { width: 0, height: 0, tiles: vec![], attrib: [0; 0x100], tile_size: TileSize::Tile16x16 },
+            map: Map {
+                width: 0,
+                height: 0,
+                tiles: vec![],
+                attrib: [0; 0x100],
+                tile_size: TileSize::Tile16x16,
+                camera_boundaries: CameraBoundaries::default(),
+            },
             data: StageData {
                 name: String::new(),
                 name_jp: String::new(),
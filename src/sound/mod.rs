@@ -24,6 +24,7 @@ use crate::sound::pixtone::{PixToneParameters, PixTonePlayback};
 use crate::sound::wave_bank::SoundBank;
 
 mod fir;
+pub mod music_control;
 #[cfg(feature = "ogg-playback")]
 mod ogg_playback;
 mod org_playback;
@@ -281,7 +282,7 @@ impl SoundManager {
             } else {
                 self.send(PlaybackMessage::Stop).unwrap();
             }
-        } else if let Some(song_name) = constants.music_table.get(song_id) {
+        } else if let Some(song_name) = constants.music_path(song_id) {
             let mut paths = constants.organya_paths.clone();
 
             paths.insert(0, "/Soundtracks/".to_owned() + &settings.soundtrack + "/");
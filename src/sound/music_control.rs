@@ -0,0 +1,165 @@
+use crate::engine_constants::EngineConstants;
+use crate::framework::context::Context;
+use crate::framework::error::GameResult;
+use crate::game::settings::Settings;
+use crate::sound::SoundManager;
+
+/// Ids of the songs in the default `EngineConstants::music_table`, named after their files.
+pub mod song {
+    pub const SILENCE: usize = 0;
+    pub const WANPAKU: usize = 1;
+    pub const ANZEN: usize = 2;
+    pub const GAMEOVER: usize = 3;
+    pub const GRAVITY: usize = 4;
+    pub const WEED: usize = 5;
+    pub const MDOWN2: usize = 6;
+    pub const FIREEYE: usize = 7;
+    pub const VIVI: usize = 8;
+    pub const MURA: usize = 9;
+    pub const FANFALE1: usize = 10;
+    pub const GINSUKE: usize = 11;
+    pub const CEMETERY: usize = 12;
+    pub const PLANT: usize = 13;
+    pub const KODOU: usize = 14;
+    pub const FANFALE3: usize = 15;
+    pub const FANFALE2: usize = 16;
+    pub const DR: usize = 17;
+    pub const ESCAPE: usize = 18;
+    pub const JENKA: usize = 19;
+    pub const MAZE: usize = 20;
+    pub const ACCESS: usize = 21;
+    pub const IRONH: usize = 22;
+    pub const GRAND: usize = 23;
+    pub const CURLY: usize = 24;
+    pub const OSIDE: usize = 25;
+    pub const REQUIEM: usize = 26;
+    pub const WANPAK2: usize = 27;
+    pub const QUIET: usize = 28;
+    pub const LASTCAVE: usize = 29;
+    pub const BALCONY: usize = 30;
+    pub const LASTBTL: usize = 31;
+    pub const LASTBT3: usize = 32;
+    pub const ENDING: usize = 33;
+    pub const ZONBIE: usize = 34;
+    pub const BDOWN: usize = 35;
+    pub const HELL: usize = 36;
+    pub const JENKA2: usize = 37;
+    pub const MARINE: usize = 38;
+    pub const BALLOS: usize = 39;
+    pub const TOROKO: usize = 40;
+    pub const WHITE: usize = 41;
+    pub const KAZE: usize = 42;
+    pub const IKA: usize = 43;
+}
+
+/// Music requests made by game logic (`<CMU`, `<FMU`, `<RMU`), kept apart from the audio backend.
+pub trait MusicControl {
+    fn play_song(
+        &mut self,
+        song_id: usize,
+        constants: &EngineConstants,
+        settings: &Settings,
+        ctx: &mut Context,
+    ) -> GameResult;
+
+    /// Fades out the current song, it can be brought back with `resume_previous`.
+    fn fade_out(&mut self, constants: &EngineConstants, settings: &Settings, ctx: &mut Context) -> GameResult;
+
+    /// Goes back to the song that was playing before the last song change.
+    fn resume_previous(&mut self) -> GameResult;
+
+    fn set_volume(&mut self, volume: f32);
+}
+
+impl MusicControl for SoundManager {
+    fn play_song(
+        &mut self,
+        song_id: usize,
+        constants: &EngineConstants,
+        settings: &Settings,
+        ctx: &mut Context,
+    ) -> GameResult {
+        SoundManager::play_song(self, song_id, constants, settings, ctx, false)
+    }
+
+    fn fade_out(&mut self, constants: &EngineConstants, settings: &Settings, ctx: &mut Context) -> GameResult {
+        SoundManager::play_song(self, song::SILENCE, constants, settings, ctx, true)
+    }
+
+    fn resume_previous(&mut self) -> GameResult {
+        self.restore_state()
+    }
+
+    fn set_volume(&mut self, volume: f32) {
+        self.set_song_volume(volume);
+    }
+}
+
+/// Plays nothing, only keeps track of what was requested. For headless runs and tests.
+#[derive(Debug, Clone, Default)]
+pub struct HeadlessMusic {
+    pub current_song: usize,
+    pub prev_song: usize,
+    pub volume: f32,
+    /// Every song started, in order. Stopping and fading out are recorded as `song::SILENCE`.
+    pub history: Vec<usize>,
+}
+
+impl HeadlessMusic {
+    pub fn new() -> HeadlessMusic {
+        HeadlessMusic { volume: 1.0, ..Default::default() }
+    }
+
+    fn change_song(&mut self, song_id: usize) {
+        if self.current_song == song_id {
+            return;
+        }
+
+        self.prev_song = self.current_song;
+        self.current_song = song_id;
+        self.history.push(song_id);
+    }
+}
+
+impl MusicControl for HeadlessMusic {
+    fn play_song(&mut self, song_id: usize, _: &EngineConstants, _: &Settings, _: &mut Context) -> GameResult {
+        self.change_song(song_id);
+        Ok(())
+    }
+
+    fn fade_out(&mut self, _: &EngineConstants, _: &Settings, _: &mut Context) -> GameResult {
+        self.change_song(song::SILENCE);
+        Ok(())
+    }
+
+    fn resume_previous(&mut self) -> GameResult {
+        self.current_song = self.prev_song;
+        self.history.push(self.prev_song);
+        Ok(())
+    }
+
+    fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_music_requests() {
+        let constants = EngineConstants::defaults();
+        let settings = Settings::default();
+        let mut ctx = Context::new();
+        let mut music = HeadlessMusic::new();
+
+        music.play_song(song::MURA, &constants, &settings, &mut ctx).unwrap();
+        music.play_song(song::MURA, &constants, &settings, &mut ctx).unwrap();
+        music.fade_out(&constants, &settings, &mut ctx).unwrap();
+        music.resume_previous().unwrap();
+
+        assert_eq!(music.current_song, song::MURA);
+        assert_eq!(music.history, vec![song::MURA, song::SILENCE, song::MURA]);
+    }
+}
@@ -0,0 +1,152 @@
+/// Tick rate animation speeds are authored at, the original game runs at 50 ticks per second.
+pub const ANIMATION_REFERENCE_TPS: f32 = 50.0;
+
+/// Resolution of the counter used by `Animator::tick_scaled`, in steps per tick. Ticks at both 50 and 60 TPS
+/// are a whole number of steps, so animations don't drift at either rate.
+const SUBTICKS: u32 = 60;
+
+/// What happens after the last frame of an animation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AnimationMode {
+    /// Start over from the first frame.
+    Loop,
+    /// Play backwards to the first frame, then forwards again.
+    PingPong,
+    /// Stay on the last frame, `Animator::tick` reports the animation as finished.
+    OneShot,
+}
+
+/// Advances through a table of animation frames at a fixed rate.
+///
+/// The animator only holds the timing, the progress is kept in the entity's own `anim_counter`/`anim_num`
+/// fields, so it can be shared between entities and stored in constants.
+#[derive(Debug, Copy, Clone)]
+pub struct Animator {
+    /// How many ticks each frame is shown for.
+    pub ticks_per_frame: u16,
+    pub mode: AnimationMode,
+}
+
+impl Animator {
+    pub const fn new(ticks_per_frame: u16, mode: AnimationMode) -> Animator {
+        Animator { ticks_per_frame, mode }
+    }
+
+    /// Advances the animation by a tick. Returns `false` once a one-shot animation has run past its last frame.
+    pub fn tick(&self, frame_count: usize, counter: &mut u16, step: &mut u16) -> bool {
+        self.advance(frame_count, counter, step, 1, self.ticks_per_frame as u32)
+    }
+
+    /// Advances the animation by `ticks`, which can be fractional, for advancing by elapsed time rather than
+    /// once per tick (see `SharedGameState::animation_tick_scale`). `counter` is kept in 1/60 of a tick here,
+    /// so an animation has to be advanced with either this or `tick`, not both.
+    pub fn tick_scaled(&self, frame_count: usize, counter: &mut u16, step: &mut u16, ticks: f32) -> bool {
+        let elapsed = (ticks.max(0.0) * SUBTICKS as f32).round() as u32;
+        self.advance(frame_count, counter, step, elapsed, self.ticks_per_frame as u32 * SUBTICKS)
+    }
+
+    fn advance(&self, frame_count: usize, counter: &mut u16, step: &mut u16, elapsed: u32, frame_length: u32) -> bool {
+        let frame_count = frame_count.max(1) as u16;
+        let frame_length = frame_length.clamp(1, u16::MAX as u32);
+
+        let mut time = *counter as u32 + elapsed;
+        while time >= frame_length {
+            time -= frame_length;
+            *step = step.saturating_add(1);
+        }
+        *counter = time as u16;
+
+        match self.mode {
+            AnimationMode::Loop => *step %= frame_count,
+            AnimationMode::PingPong => *step %= (frame_count * 2 - 2).max(1),
+            AnimationMode::OneShot => {
+                if *step >= frame_count {
+                    *step = frame_count - 1;
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Returns the index of the frame shown at given step.
+    pub fn frame(&self, frame_count: usize, step: u16) -> usize {
+        let step = step as usize;
+        let frame_count = frame_count.max(1);
+
+        match self.mode {
+            AnimationMode::Loop => step % frame_count,
+            AnimationMode::PingPong => {
+                let step = step % (frame_count * 2 - 2).max(1);
+                if step < frame_count {
+                    step
+                } else {
+                    frame_count * 2 - 2 - step
+                }
+            }
+            AnimationMode::OneShot => step.min(frame_count - 1),
+        }
+    }
+
+    /// Returns the frame shown at given step, `frames` must not be empty.
+    pub fn current<T: Copy>(&self, frames: &[T], step: u16) -> T {
+        frames[self.frame(frames.len(), step)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(animator: Animator, frame_count: usize, ticks: usize) -> (Vec<usize>, bool) {
+        let (mut counter, mut step) = (0, 0);
+        let mut alive = true;
+        let frames = (0..ticks)
+            .map(|_| {
+                alive &= animator.tick(frame_count, &mut counter, &mut step);
+                animator.frame(frame_count, step)
+            })
+            .collect();
+
+        (frames, alive)
+    }
+
+    #[test]
+    fn test_modes() {
+        let (frames, alive) = play(Animator::new(2, AnimationMode::Loop), 3, 8);
+        assert_eq!(frames, [0, 1, 1, 2, 2, 0, 0, 1]);
+        assert!(alive);
+
+        let (frames, _) = play(Animator::new(1, AnimationMode::PingPong), 3, 6);
+        assert_eq!(frames, [1, 2, 1, 0, 1, 2]);
+
+        let (frames, alive) = play(Animator::new(1, AnimationMode::OneShot), 3, 4);
+        assert_eq!(frames, [1, 2, 2, 2]);
+        assert!(!alive);
+
+        let (frames, _) = play(Animator::new(1, AnimationMode::PingPong), 1, 3);
+        assert_eq!(frames, [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_scaled() {
+        let animator = Animator::new(5, AnimationMode::Loop);
+
+        // 60 TPS ticks are 5/6 of a 50 TPS tick, the frame changes after 6 of them instead of 5
+        let (mut counter, mut step) = (0, 0);
+        for _ in 0..5 {
+            animator.tick_scaled(4, &mut counter, &mut step, 50.0 / 60.0);
+        }
+        assert_eq!(step, 0);
+        animator.tick_scaled(4, &mut counter, &mut step, 50.0 / 60.0);
+        assert_eq!(step, 1);
+
+        // whole ticks behave just like `tick`
+        let (mut counter, mut step) = (0, 0);
+        for _ in 0..10 {
+            animator.tick_scaled(4, &mut counter, &mut step, 1.0);
+        }
+        assert_eq!(step, 2);
+    }
+}
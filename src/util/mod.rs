@@ -1,3 +1,4 @@
+pub mod animator;
 pub mod bitvec;
 pub mod browser;
 pub mod rng;